@@ -1,5 +1,5 @@
 import React, { useRef, useState, useEffect, useMemo } from "react";
-import { BOUNDARY_CHOICES } from "../../utils/boundaryUtils";
+import { BOUNDARY_CHOICES, EXCLUSION_COLOR } from "../../utils/boundaryUtils";
 import type {
   Boundaries,
   BoundaryKey,
@@ -14,6 +14,9 @@ interface BoundaryCanvasProps {
   activeBoundary: BoundaryKey;
   mode: EditorMode;
   onBoundariesChange: (boundaries: Boundaries) => void;
+  exclusions: Point[][];
+  activeExclusion: number | null;
+  onExclusionsChange: (exclusions: Point[][]) => void;
   onImageLoad: (width: number, height: number) => void;
   roi: ROI | null;
 }
@@ -24,6 +27,9 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
   activeBoundary,
   mode,
   onBoundariesChange,
+  exclusions,
+  activeExclusion,
+  onExclusionsChange,
   onImageLoad,
   roi,
 }) => {
@@ -76,6 +82,24 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
     return { x, y, width: w, height: h };
   }, [imageRes, scale, offset]);
 
+  // The polygon being edited: the selected exclusion, or else the active zone
+  const activePoints =
+    activeExclusion !== null
+      ? (exclusions[activeExclusion] ?? [])
+      : boundaries[activeBoundary];
+
+  const setActivePoints = (points: Point[]) => {
+    if (activeExclusion !== null) {
+      onExclusionsChange(
+        exclusions.map((polygon, i) =>
+          i === activeExclusion ? points : polygon,
+        ),
+      );
+    } else {
+      onBoundariesChange({ ...boundaries, [activeBoundary]: points });
+    }
+  };
+
   const handleMouseDown = (e: React.MouseEvent<SVGSVGElement>) => {
     const svg = e.currentTarget;
     const pt = svg.createSVGPoint();
@@ -98,7 +122,7 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
       let closestPt: Point | null = null;
       let minDistance = snapThreshold;
 
-      Object.values(boundaries)
+      [...Object.values(boundaries), ...exclusions]
         .flat()
         .forEach((p) => {
           const dist = Math.hypot(p.x - cursorPt.x, p.y - cursorPt.y);
@@ -112,24 +136,20 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
         finalPt = closestPt;
       }
 
-      const newPoints = [...boundaries[activeBoundary], finalPt];
-      onBoundariesChange({ ...boundaries, [activeBoundary]: newPoints });
+      setActivePoints([...activePoints, finalPt]);
     } else if (mode === "move") {
-      const hitIndex = boundaries[activeBoundary].findIndex(
+      const hitIndex = activePoints.findIndex(
         (p) => Math.hypot(p.x - cursorPt.x, p.y - cursorPt.y) < 20 / scale,
       );
       if (hitIndex !== -1) {
         setDragPoint(hitIndex);
       }
     } else if (mode === "delete") {
-      const hitIndex = boundaries[activeBoundary].findIndex(
+      const hitIndex = activePoints.findIndex(
         (p) => Math.hypot(p.x - cursorPt.x, p.y - cursorPt.y) < 20 / scale,
       );
       if (hitIndex !== -1) {
-        const newPoints = boundaries[activeBoundary].filter(
-          (_, i) => i !== hitIndex,
-        );
-        onBoundariesChange({ ...boundaries, [activeBoundary]: newPoints });
+        setActivePoints(activePoints.filter((_, i) => i !== hitIndex));
       }
     }
   };
@@ -151,9 +171,9 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
 
       if (!cursorPt) return;
 
-      const newPoints = [...boundaries[activeBoundary]];
+      const newPoints = [...activePoints];
       newPoints[dragPoint] = { x: cursorPt.x, y: cursorPt.y };
-      onBoundariesChange({ ...boundaries, [activeBoundary]: newPoints });
+      setActivePoints(newPoints);
     }
   };
 
@@ -196,7 +216,7 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
         {Object.entries(boundaries).map(([key, points]) => {
           const choice = BOUNDARY_CHOICES.find((c) => c.key === key);
           if (!choice) return null;
-          const isActive = key === activeBoundary;
+          const isActive = activeExclusion === null && key === activeBoundary;
           const color = choice.color;
 
           return (
@@ -224,6 +244,39 @@ const BoundaryCanvas: React.FC<BoundaryCanvasProps> = ({
             </g>
           );
         })}
+
+        {exclusions.map((points, index) => {
+          const isActive = index === activeExclusion;
+          const color = EXCLUSION_COLOR;
+
+          return (
+            <g
+              key={`exclusion-${index}`}
+              style={{ pointerEvents: isActive ? "auto" : "none" }}
+            >
+              {points.length > 2 && (
+                <polygon
+                  points={points.map((p: Point) => `${p.x},${p.y}`).join(" ")}
+                  fill={isActive ? `${color}44` : `${color}22`}
+                  stroke={color}
+                  strokeWidth={(isActive ? 2 : 1) / scale}
+                  strokeDasharray="3,3"
+                />
+              )}
+              {points.map((p: Point, i: number) => (
+                <circle
+                  key={i}
+                  cx={p.x}
+                  cy={p.y}
+                  r={(isActive ? 6 : 4) / scale}
+                  fill={isActive ? color : `${color}88`}
+                  stroke="white"
+                  strokeWidth={1 / scale}
+                />
+              ))}
+            </g>
+          );
+        })}
       </svg>
       <div className="canvas-hint">Shift+Drag to Pan | +/- to Zoom</div>
     </div>
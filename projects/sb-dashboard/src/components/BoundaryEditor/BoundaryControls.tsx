@@ -1,5 +1,6 @@
 import React from "react";
-import { BOUNDARY_CHOICES } from "../../utils/boundaryUtils";
+import { Plus, Trash2 } from "lucide-react";
+import { BOUNDARY_CHOICES, EXCLUSION_COLOR } from "../../utils/boundaryUtils";
 import type { BoundaryKey } from "../../utils/boundaryUtils";
 
 export type EditorMode = "explore" | "add" | "move" | "delete";
@@ -10,6 +11,11 @@ interface BoundaryControlsProps {
   onFrameChange: (index: number) => void;
   activeBoundary: BoundaryKey;
   onBoundaryChange: (key: BoundaryKey) => void;
+  exclusionCount: number;
+  activeExclusion: number | null;
+  onExclusionChange: (index: number) => void;
+  onExclusionAdd: () => void;
+  onExclusionDelete: (index: number) => void;
   mode: EditorMode;
   onModeChange: (mode: EditorMode) => void;
 }
@@ -20,6 +26,11 @@ const BoundaryControls: React.FC<BoundaryControlsProps> = ({
   onFrameChange,
   activeBoundary,
   onBoundaryChange,
+  exclusionCount,
+  activeExclusion,
+  onExclusionChange,
+  onExclusionAdd,
+  onExclusionDelete,
   mode,
   onModeChange,
 }) => {
@@ -52,7 +63,11 @@ const BoundaryControls: React.FC<BoundaryControlsProps> = ({
           {BOUNDARY_CHOICES.map((c) => (
             <button
               key={c.key}
-              className={activeBoundary === c.key ? "active" : ""}
+              className={
+                activeExclusion === null && activeBoundary === c.key
+                  ? "active"
+                  : ""
+              }
               onClick={() => onBoundaryChange(c.key)}
               style={{ borderBottom: `3px solid ${c.color}` }}
             >
@@ -62,6 +77,34 @@ const BoundaryControls: React.FC<BoundaryControlsProps> = ({
         </div>
       </div>
 
+      <div className="control-group">
+        <label>Exclusions:</label>
+        <div className="toggle-group">
+          {Array.from({ length: exclusionCount }, (_, i) => (
+            <button
+              key={i}
+              className={activeExclusion === i ? "active" : ""}
+              onClick={() => onExclusionChange(i)}
+              style={{ borderBottom: `3px solid ${EXCLUSION_COLOR}` }}
+            >
+              {i + 1}
+            </button>
+          ))}
+          <button onClick={onExclusionAdd} title="New Exclusion">
+            <Plus size={14} />
+          </button>
+          {activeExclusion !== null && (
+            <button
+              className="delete-btn"
+              onClick={() => onExclusionDelete(activeExclusion)}
+              title="Delete Exclusion"
+            >
+              <Trash2 size={14} />
+            </button>
+          )}
+        </div>
+      </div>
+
       <div className="control-group">
         <label>Mode:</label>
         <div className="toggle-group">
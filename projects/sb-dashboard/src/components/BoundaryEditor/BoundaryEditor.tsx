@@ -3,11 +3,18 @@ import BoundaryCanvas from "./BoundaryCanvas";
 import BoundaryControls from "./BoundaryControls";
 import type { EditorMode } from "./BoundaryControls";
 import PointsList from "./PointsList";
-import { computeROI, normalizeBoundaries } from "../../utils/boundaryUtils";
+import PreprocessPanel from "./PreprocessPanel";
+import {
+  computeROI,
+  globalToRoi,
+  normalizeBoundaries,
+  roiToGlobal,
+} from "../../utils/boundaryUtils";
 import type {
   Boundaries,
   BoundaryKey,
   FieldBoundariesConfig,
+  Point,
 } from "../../utils/boundaryUtils";
 import "./BoundaryEditor.css";
 import { Loader2, AlertCircle } from "lucide-react";
@@ -32,6 +39,10 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
     left_end_zone: [],
     right_end_zone: [],
   });
+  // Exclusion polygons in whole-frame coordinates; while one is selected, the
+  // canvas edits it instead of the active zone
+  const [exclusions, setExclusions] = useState<Point[][]>([]);
+  const [activeExclusion, setActiveExclusion] = useState<number | null>(null);
   const [imageSize, setImageSize] = useState({ width: 0, height: 0 });
   const [isLoading, setIsLoading] = useState(true);
   const [isSaving, setIsSaving] = useState(false);
@@ -68,10 +79,35 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
     loadFrames();
   }, [loadFrames]);
 
+  useEffect(() => {
+    fetch(`/api/runs/${runId}/calibration/boundaries`)
+      .then((res) => (res.ok ? res.json() : null))
+      .then((saved: FieldBoundariesConfig | null) => {
+        if (!saved) return;
+        setExclusions(
+          (saved.exclusions ?? []).map((polygon) =>
+            roiToGlobal(polygon, saved.roi),
+          ),
+        );
+      })
+      .catch((err) => console.error("Failed to load saved boundaries:", err));
+  }, [runId]);
+
   const roi = useMemo(() => {
     return computeROI(boundaries, imageSize.width, imageSize.height);
   }, [boundaries, imageSize]);
 
+  const handleExclusionAdd = () => {
+    setExclusions((prev) => [...prev, []]);
+    setActiveExclusion(exclusions.length);
+    setMode("add");
+  };
+
+  const handleExclusionDelete = (index: number) => {
+    setExclusions((prev) => prev.filter((_, i) => i !== index));
+    setActiveExclusion(null);
+  };
+
   const handleSave = async () => {
     // Validation
     for (const [key, pts] of Object.entries(boundaries)) {
@@ -80,6 +116,10 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
         return;
       }
     }
+    if (exclusions.some((polygon) => polygon.length < 3)) {
+      alert("Each exclusion must have at least 3 points.");
+      return;
+    }
 
     if (!roi) {
       alert("Please define boundaries before saving.");
@@ -92,6 +132,7 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
       const config: FieldBoundariesConfig = {
         ...normalized,
         roi,
+        exclusions: exclusions.map((polygon) => globalToRoi(polygon, roi)),
       };
 
       // Save boundaries and ROI (consolidated)
@@ -173,7 +214,15 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
             currentIndex={currentFrameIndex}
             onFrameChange={setCurrentFrameIndex}
             activeBoundary={activeBoundary}
-            onBoundaryChange={setActiveBoundary}
+            onBoundaryChange={(key) => {
+              setActiveBoundary(key);
+              setActiveExclusion(null);
+            }}
+            exclusionCount={exclusions.length}
+            activeExclusion={activeExclusion}
+            onExclusionChange={setActiveExclusion}
+            onExclusionAdd={handleExclusionAdd}
+            onExclusionDelete={handleExclusionDelete}
             mode={mode}
             onModeChange={setMode}
           />
@@ -183,6 +232,9 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
             activeBoundary={activeBoundary}
             mode={mode}
             onBoundariesChange={setBoundaries}
+            exclusions={exclusions}
+            activeExclusion={activeExclusion}
+            onExclusionsChange={setExclusions}
             onImageLoad={(w, h) => setImageSize({ width: w, height: h })}
             roi={roi}
           />
@@ -190,14 +242,25 @@ const BoundaryEditor: React.FC<BoundaryEditorProps> = ({
 
         <div className="editor-sidebar">
           <PointsList
-            points={boundaries[activeBoundary]}
+            points={
+              activeExclusion !== null
+                ? exclusions[activeExclusion]
+                : boundaries[activeBoundary]
+            }
             onPointsChange={(newPoints) =>
-              setBoundaries((prev) => ({
-                ...prev,
-                [activeBoundary]: newPoints,
-              }))
+              activeExclusion !== null
+                ? setExclusions((prev) =>
+                    prev.map((polygon, i) =>
+                      i === activeExclusion ? newPoints : polygon,
+                    ),
+                  )
+                : setBoundaries((prev) => ({
+                    ...prev,
+                    [activeBoundary]: newPoints,
+                  }))
             }
           />
+          <PreprocessPanel runId={runId} frame={frames[currentFrameIndex]} />
         </div>
       </div>
     </div>
@@ -0,0 +1,264 @@
+import React, { useState, useEffect } from "react";
+import type { PreprocessConfig, PreprocessStep } from "../../types/run";
+import {
+  X,
+  ChevronRight,
+  ChevronLeft,
+  ChevronUp,
+  ChevronDown,
+  Loader2,
+} from "lucide-react";
+
+const CROPS = ["overview", "left", "right"];
+
+const STEP_DEFAULTS: Record<PreprocessStep["op"], PreprocessStep> = {
+  clahe: { op: "clahe", clip_limit: 2.0, tile_grid: 8 },
+  gamma: { op: "gamma", gamma: 0.8 },
+  white_balance: { op: "white_balance" },
+  denoise: { op: "denoise", strength: 5 },
+  sharpen: { op: "sharpen", amount: 0.5, sigma: 1.5 },
+  dehaze: { op: "dehaze", strength: 0.8, window: 15 },
+  letterbox: { op: "letterbox", width: 640, height: 640 },
+};
+
+const STEP_LABELS: Record<PreprocessStep["op"], string> = {
+  clahe: "CLAHE",
+  gamma: "Gamma",
+  white_balance: "White balance",
+  denoise: "Denoise",
+  sharpen: "Sharpen",
+  dehaze: "Dehaze",
+  letterbox: "Letterbox",
+};
+
+interface PreprocessPanelProps {
+  runId: string;
+  frame: string;
+}
+
+const PreprocessPanel: React.FC<PreprocessPanelProps> = ({ runId, frame }) => {
+  const [isCollapsed, setIsCollapsed] = useState(true);
+  const [config, setConfig] = useState<PreprocessConfig | null>(null);
+  const [crop, setCrop] = useState("overview");
+  const [isDirty, setIsDirty] = useState(false);
+  const [isSaving, setIsSaving] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  // Bumped after each save so the previews reload
+  const [version, setVersion] = useState(0);
+
+  useEffect(() => {
+    fetch(`/api/runs/${runId}/preprocess`)
+      .then((res) => res.json())
+      .then(setConfig)
+      .catch((err) => {
+        console.error(err);
+        setError("Failed to load preprocessing settings.");
+      });
+  }, [runId]);
+
+  if (isCollapsed) {
+    return (
+      <button
+        className="points-toggle-btn collapsed"
+        onClick={() => setIsCollapsed(false)}
+        title="Show Preprocessing"
+      >
+        <ChevronLeft size={20} />
+      </button>
+    );
+  }
+
+  const hasOwnChain = config ? crop in config.crops : false;
+  const chain = config ? (config.crops[crop] ?? config.steps) : [];
+
+  const updateChain = (steps: PreprocessStep[]) => {
+    if (!config) return;
+    setConfig(
+      hasOwnChain
+        ? { ...config, crops: { ...config.crops, [crop]: steps } }
+        : { ...config, steps },
+    );
+    setIsDirty(true);
+  };
+
+  const toggleOwnChain = () => {
+    if (!config) return;
+    const crops = { ...config.crops };
+    if (hasOwnChain) {
+      delete crops[crop];
+    } else {
+      crops[crop] = [...config.steps];
+    }
+    setConfig({ ...config, crops });
+    setIsDirty(true);
+  };
+
+  const addStep = (op: PreprocessStep["op"]) => {
+    const step = STEP_DEFAULTS[op];
+    // A letterbox has to stay last
+    const last = chain.length - 1;
+    if (op === "letterbox") {
+      if (chain.some((s) => s.op === "letterbox")) return;
+      updateChain([...chain, step]);
+    } else if (last >= 0 && chain[last].op === "letterbox") {
+      updateChain([...chain.slice(0, last), step, chain[last]]);
+    } else {
+      updateChain([...chain, step]);
+    }
+  };
+
+  const moveStep = (index: number, delta: number) => {
+    const target = index + delta;
+    if (target < 0 || target >= chain.length) return;
+    const steps = [...chain];
+    [steps[index], steps[target]] = [steps[target], steps[index]];
+    updateChain(steps);
+  };
+
+  const setParam = (index: number, key: string, value: number) => {
+    const steps = [...chain];
+    steps[index] = { ...steps[index], [key]: value } as PreprocessStep;
+    updateChain(steps);
+  };
+
+  const handleSave = async () => {
+    if (!config) return;
+    setIsSaving(true);
+    setError(null);
+    try {
+      const res = await fetch(`/api/runs/${runId}/preprocess`, {
+        method: "PUT",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(config),
+      });
+      if (!res.ok) throw new Error("Invalid preprocessing settings");
+      setIsDirty(false);
+      setVersion((v) => v + 1);
+    } catch (err) {
+      console.error(err);
+      setError("Failed to save: check step parameters and order.");
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
+  const previewUrl = (steps: number) =>
+    `/api/runs/${runId}/calibration/preview/${frame}?crop=${crop}&steps=${steps}&v=${version}`;
+
+  return (
+    <div className="preprocess-panel side-panel-section">
+      <div className="section-header">
+        <h3>Preprocessing</h3>
+        <button
+          className="icon-btn"
+          onClick={() => setIsCollapsed(true)}
+          title="Hide Preprocessing"
+        >
+          <ChevronRight size={18} />
+        </button>
+      </div>
+
+      <div className="toggle-group">
+        {CROPS.map((c) => (
+          <button
+            key={c}
+            className={crop === c ? "active" : ""}
+            onClick={() => setCrop(c)}
+          >
+            {c}
+          </button>
+        ))}
+      </div>
+
+      <label className="preprocess-own-chain">
+        <input
+          type="checkbox"
+          checked={hasOwnChain}
+          onChange={toggleOwnChain}
+          disabled={!config}
+        />
+        Separate chain for this crop
+      </label>
+
+      <div className="points-scroll-area">
+        {error && <p className="preprocess-error">{error}</p>}
+        <ul className="preprocess-steps">
+          <li>
+            <span>Original</span>
+            <img src={previewUrl(0)} alt="Original crop" />
+          </li>
+          {chain.map((step, i) => (
+            <li key={i}>
+              <div className="preprocess-step-header">
+                <span>
+                  {i + 1}. {STEP_LABELS[step.op]}
+                </span>
+                <div>
+                  <button className="icon-btn" onClick={() => moveStep(i, -1)}>
+                    <ChevronUp size={14} />
+                  </button>
+                  <button className="icon-btn" onClick={() => moveStep(i, 1)}>
+                    <ChevronDown size={14} />
+                  </button>
+                  <button
+                    className="delete-btn"
+                    onClick={() =>
+                      updateChain(chain.filter((_, j) => j !== i))
+                    }
+                  >
+                    <X size={14} />
+                  </button>
+                </div>
+              </div>
+              {Object.entries(step)
+                .filter(([key]) => key !== "op")
+                .map(([key, value]) => (
+                  <label key={key} className="preprocess-param">
+                    {key}
+                    <input
+                      type="number"
+                      step="any"
+                      value={value as number}
+                      onChange={(e) =>
+                        setParam(i, key, Number(e.target.value))
+                      }
+                    />
+                  </label>
+                ))}
+              <img
+                src={previewUrl(i + 1)}
+                alt={`After ${STEP_LABELS[step.op]}`}
+              />
+            </li>
+          ))}
+        </ul>
+      </div>
+
+      <select
+        value=""
+        onChange={(e) => addStep(e.target.value as PreprocessStep["op"])}
+      >
+        <option value="" disabled>
+          Add step…
+        </option>
+        {Object.entries(STEP_LABELS).map(([op, label]) => (
+          <option key={op} value={op}>
+            {label}
+          </option>
+        ))}
+      </select>
+
+      {isDirty && <p className="hint">Save to refresh the previews.</p>}
+      <button
+        className="btn btn-primary"
+        onClick={handleSave}
+        disabled={!config || !isDirty || isSaving}
+      >
+        {isSaving ? <Loader2 className="animate-spin" size={18} /> : null}
+        Save Preprocessing
+      </button>
+    </div>
+  );
+};
+
+export default PreprocessPanel;
@@ -9,13 +9,13 @@ import {
   Clock,
   ExternalLink,
 } from "lucide-react";
-import type { RunDetail } from "../../types/run";
+import type { GameFormat, RunDetail } from "../../types/run";
 
 interface PropertiesCardProps {
   run: RunDetail;
   isEditing: boolean;
-  editTeamSize: number;
-  setEditTeamSize: (val: number) => void;
+  editGameFormat: GameFormat;
+  setEditGameFormat: (val: GameFormat) => void;
   editTags: string;
   setEditTags: (val: string) => void;
   editSampleRate: number;
@@ -26,6 +26,13 @@ interface PropertiesCardProps {
   setEditFuegostatsLink: (val: string) => void;
 }
 
+const GAME_FORMATS: Record<GameFormat, string> = {
+  sevens: "7v7",
+  fives: "5v5",
+  fours: "4v4",
+  beach: "5v5 beach",
+};
+
 function formatDuration(totalFrames: number, fps: number): string {
   if (!fps || fps <= 0) return "Unknown";
   const totalSecs = Math.round(totalFrames / fps);
@@ -41,8 +48,8 @@ function formatDuration(totalFrames: number, fps: number): string {
 const PropertiesCard: React.FC<PropertiesCardProps> = ({
   run,
   isEditing,
-  editTeamSize,
-  setEditTeamSize,
+  editGameFormat,
+  setEditGameFormat,
   editTags,
   setEditTags,
   editSampleRate,
@@ -98,19 +105,24 @@ const PropertiesCard: React.FC<PropertiesCardProps> = ({
             }}
           >
             <Users size={14} />
-            Team Size
+            Game Format
           </div>
           {isEditing ? (
-            <input
-              type="number"
+            <select
               className="form-input"
-              style={{ width: "80px", padding: "0.25rem 0.5rem" }}
-              value={editTeamSize}
-              onChange={(e) => setEditTeamSize(parseInt(e.target.value) || 0)}
-            />
+              style={{ width: "120px", padding: "0.25rem 0.5rem" }}
+              value={editGameFormat}
+              onChange={(e) => setEditGameFormat(e.target.value as GameFormat)}
+            >
+              {Object.entries(GAME_FORMATS).map(([format, label]) => (
+                <option key={format} value={format}>
+                  {label}
+                </option>
+              ))}
+            </select>
           ) : (
             <div style={{ fontWeight: 500, fontSize: "0.875rem" }}>
-              {run.run_context.team_size} players
+              {GAME_FORMATS[run.run_context.game_format]}
             </div>
           )}
         </div>
@@ -1,5 +1,5 @@
 import { useState, useCallback, useEffect } from "react";
-import type { RunDetail } from "../types/run";
+import type { GameFormat, RunDetail } from "../types/run";
 
 export const useRunDetails = (id: string | undefined) => {
   const [run, setRun] = useState<RunDetail | null>(null);
@@ -8,7 +8,7 @@ export const useRunDetails = (id: string | undefined) => {
   // Edit State
   const [isEditing, setIsEditing] = useState(false);
   const [editName, setEditName] = useState("");
-  const [editTeamSize, setEditTeamSize] = useState(7);
+  const [editGameFormat, setEditGameFormat] = useState<GameFormat>("sevens");
   const [editTags, setEditTags] = useState("");
   const [editSampleRate, setEditSampleRate] = useState(1.0);
   const [editYoutubeLink, setEditYoutubeLink] = useState("");
@@ -26,7 +26,7 @@ export const useRunDetails = (id: string | undefined) => {
       .then((data: RunDetail) => {
         setRun(data);
         setEditName(data.run_context.display_name);
-        setEditTeamSize(data.run_context.team_size);
+        setEditGameFormat(data.run_context.game_format);
         setEditTags(data.run_context.tags.join(", "));
         setEditSampleRate(data.run_context.sample_rate || 1.0);
         setEditYoutubeLink(data.run_context.youtube_link || "");
@@ -50,7 +50,7 @@ export const useRunDetails = (id: string | undefined) => {
     const updatedRunContext = {
       ...run.run_context,
       display_name: editName,
-      team_size: editTeamSize,
+      game_format: editGameFormat,
       tags: editTags
         .split(",")
         .map((t) => t.trim())
@@ -87,8 +87,8 @@ export const useRunDetails = (id: string | undefined) => {
     setIsEditing,
     editName,
     setEditName,
-    editTeamSize,
-    setEditTeamSize,
+    editGameFormat,
+    setEditGameFormat,
     editTags,
     setEditTags,
     editSampleRate,
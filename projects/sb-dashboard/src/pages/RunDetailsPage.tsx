@@ -33,8 +33,8 @@ const RunDetailsPage: React.FC = () => {
     setIsEditing,
     editName,
     setEditName,
-    editTeamSize,
-    setEditTeamSize,
+    editGameFormat,
+    setEditGameFormat,
     editTags,
     setEditTags,
     editSampleRate,
@@ -247,8 +247,8 @@ const RunDetailsPage: React.FC = () => {
                 <PropertiesCard
                   run={run}
                   isEditing={isEditing}
-                  editTeamSize={editTeamSize}
-                  setEditTeamSize={setEditTeamSize}
+                  editGameFormat={editGameFormat}
+                  setEditGameFormat={setEditGameFormat}
                   editTags={editTags}
                   setEditTags={setEditTags}
                   editSampleRate={editSampleRate}
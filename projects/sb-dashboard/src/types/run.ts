@@ -4,12 +4,16 @@ export interface RunDependency {
   valid: boolean;
 }
 
+export type GameFormat = "sevens" | "fives" | "fours" | "beach";
+
 export interface RunContext {
   original_name: string;
   display_name: string;
   created_at: string;
   run_id: string;
+  /** Players per team, written from game_format */
   team_size: number;
+  game_format: GameFormat;
   light_team_name: string;
   dark_team_name: string;
   tags: string[];
@@ -47,3 +51,17 @@ export interface ProcessingProgress {
   effective_fps?: number;
   elapsed_secs?: number;
 }
+
+export type PreprocessStep =
+  | { op: "clahe"; clip_limit: number; tile_grid: number }
+  | { op: "gamma"; gamma: number }
+  | { op: "white_balance" }
+  | { op: "denoise"; strength: number }
+  | { op: "sharpen"; amount: number; sigma: number }
+  | { op: "dehaze"; strength: number; window: number }
+  | { op: "letterbox"; width: number; height: number };
+
+export interface PreprocessConfig {
+  steps: PreprocessStep[];
+  crops: Record<string, PreprocessStep[]>;
+}
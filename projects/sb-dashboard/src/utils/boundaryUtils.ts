@@ -23,7 +23,9 @@ export interface ROI {
 }
 
 export interface FieldBoundariesConfig extends Boundaries {
-  roi: ROI;
+  roi: ROI | null;
+  // Areas whose people are never counted, ROI-relative like the boundaries
+  exclusions?: Point[][];
 }
 
 export const BOUNDARY_CHOICES = [
@@ -34,6 +36,8 @@ export const BOUNDARY_CHOICES = [
 
 export type BoundaryKey = (typeof BOUNDARY_CHOICES)[number]["key"];
 
+export const EXCLUSION_COLOR = "#ffaa00";
+
 export function computeROI(
   boundaries: Boundaries,
   imageWidth: number,
@@ -71,6 +75,23 @@ export function computeROI(
   };
 }
 
+// Map ROI-relative points of a saved config to whole-frame normalized points
+export function roiToGlobal(points: Point[], roi: ROI | null): Point[] {
+  if (!roi) return points;
+  return points.map((p) => ({
+    x: roi.x_normalized + p.x * roi.width_normalized,
+    y: roi.y_normalized + p.y * roi.height_normalized,
+  }));
+}
+
+// Map whole-frame normalized points into `roi`, the inverse of roiToGlobal
+export function globalToRoi(points: Point[], roi: ROI): Point[] {
+  return points.map((p) => ({
+    x: (p.x - roi.x_normalized) / roi.width_normalized,
+    y: (p.y - roi.y_normalized) / roi.height_normalized,
+  }));
+}
+
 export function normalizeBoundaries(
   boundaries: Boundaries,
   roi: ROI,
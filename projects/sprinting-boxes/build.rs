@@ -1,4 +1,5 @@
-//! This build script automates the frontend build process and macOS CoreML compilation.
+//! This build script automates the frontend build process and macOS CoreML compilation,
+//! and records the git version for the per-run processing records.
 
 use std::process::Command;
 
@@ -7,6 +8,8 @@ fn main() {
     #[cfg(target_os = "macos")]
     compile_coreml_helpers();
 
+    emit_git_version();
+
     println!("cargo:rerun-if-changed=../sb-dashboard/package.json");
     println!("cargo:rerun-if-changed=../sb-dashboard/package-lock.json");
     println!("cargo:rerun-if-changed=../sb-dashboard/src");
@@ -52,6 +55,26 @@ fn main() {
     }
 }
 
+/// Exposes `git describe` of the source tree as `SPRINTING_BOXES_GIT_VERSION`;
+/// left unset when building outside a git checkout.
+fn emit_git_version() {
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/index");
+
+    let output = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--tags"])
+        .output();
+    if let Ok(output) = output {
+        if output.status.success() {
+            let version = String::from_utf8_lossy(&output.stdout);
+            println!(
+                "cargo:rustc-env=SPRINTING_BOXES_GIT_VERSION={}",
+                version.trim()
+            );
+        }
+    }
+}
+
 #[cfg(target_os = "macos")]
 fn compile_coreml_helpers() {
     // Compile CoreMLHelper.m Objective-C source
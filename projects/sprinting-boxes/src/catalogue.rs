@@ -0,0 +1,891 @@
+// Run catalogue: a SQLite index of the runs in the output root and their points
+//
+// `catalogue.sqlite` in the output root holds one row per run (status, teams,
+// tags, point counts) and one row per cliff of its audit, so listings and
+// cross-game queries do not parse every run's JSON. The run directories and
+// the team registry stay the source of truth: `sync` compares each run's stamp
+// (the mtime of its directory) and re-reads only the runs that changed, so the
+// catalogue can be deleted at any time and is rebuilt on the next request.
+
+use crate::registry::{Registry, REGISTRY_FILE};
+use crate::run_context::RunContext;
+use crate::web::audit::models::{AuditState, CliffData};
+use crate::web::audit::utils::load_audit_state;
+use anyhow::Result;
+use chrono::{NaiveDate, SecondsFormat};
+use rusqlite::{params, params_from_iter, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+
+pub const CATALOGUE_FILE: &str = "catalogue.sqlite";
+
+/// Bumped whenever the tables change; older catalogues are rebuilt
+const SCHEMA_VERSION: i64 = 2;
+
+const SCHEMA: &str = "
+    CREATE TABLE runs (
+        run_id TEXT PRIMARY KEY,
+        display_name TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        game_format TEXT NOT NULL,
+        light_team TEXT NOT NULL COLLATE NOCASE,
+        dark_team TEXT NOT NULL COLLATE NOCASE,
+        light_team_id TEXT,
+        dark_team_id TEXT,
+        tournament_id TEXT,
+        round TEXT,
+        season_id TEXT,
+        status TEXT NOT NULL,
+        points INTEGER NOT NULL,
+        unaudited INTEGER NOT NULL,
+        breaks INTEGER NOT NULL,
+        final_light INTEGER NOT NULL,
+        final_dark INTEGER NOT NULL,
+        metadata TEXT NOT NULL,
+        stamp TEXT NOT NULL
+    );
+    CREATE TABLE run_tags (
+        run_id TEXT NOT NULL,
+        tag TEXT NOT NULL COLLATE NOCASE,
+        PRIMARY KEY (run_id, tag)
+    );
+    CREATE TABLE points (
+        run_id TEXT NOT NULL,
+        frame_index INTEGER NOT NULL,
+        timestamp TEXT NOT NULL,
+        status TEXT NOT NULL,
+        pull_side TEXT,
+        pulling_color TEXT,
+        scoring_color TEXT,
+        pulling_team TEXT COLLATE NOCASE,
+        scored_by TEXT COLLATE NOCASE,
+        is_break INTEGER NOT NULL,
+        score_light INTEGER NOT NULL,
+        score_dark INTEGER NOT NULL,
+        PRIMARY KEY (run_id, frame_index)
+    );
+    CREATE INDEX points_scored_by ON points (scored_by);
+";
+
+lazy_static::lazy_static! {
+    /// Serializes syncs so concurrent requests do not refresh the same run twice
+    static ref SYNC: Mutex<()> = Mutex::new(());
+}
+
+/// Where a run is in its life: "new" (no crops yet), "configured" (not processed),
+/// "processed" (cliffs left to audit) or "audited".
+fn run_status(run_context: &RunContext, audit: Option<&AuditState>) -> &'static str {
+    let output_dir = &run_context.output_dir;
+    if !output_dir.join("crops.json").exists() {
+        return "new";
+    }
+    match audit {
+        None => "configured",
+        Some(state)
+            if !output_dir.join("audit.json").exists()
+                || state.cliffs.iter().any(|c| c.status == "Unconfirmed") =>
+        {
+            "processed"
+        }
+        Some(_) => "audited",
+    }
+}
+
+/// Stamp of the run directory and the registry. Every artifact write records the
+/// artifact in `manifest.json`, which is replaced by a rename, so the directory's
+/// mtime changes with any file the rows are derived from; one stat per run.
+fn stamp(run_dir: &Path, registry_stamp: &str) -> String {
+    format!("{},{}", file_stamp(run_dir), registry_stamp)
+}
+
+fn file_stamp(path: &Path) -> String {
+    match fs::metadata(path) {
+        Ok(metadata) => {
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .unwrap_or_default();
+            format!("{}:{}", metadata.len(), modified.as_nanos())
+        }
+        Err(_) => "-".to_string(),
+    }
+}
+
+fn open(output_root: &Path) -> Result<Connection> {
+    fs::create_dir_all(output_root)?;
+    let conn = Connection::open(output_root.join(CATALOGUE_FILE))?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version != SCHEMA_VERSION {
+        conn.execute_batch(
+            "DROP TABLE IF EXISTS runs;
+             DROP TABLE IF EXISTS run_tags;
+             DROP TABLE IF EXISTS points;",
+        )?;
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    Ok(conn)
+}
+
+/// Opens the catalogue of `output_root` and brings it up to date with the run
+/// directories and the registry.
+pub fn sync(output_root: &Path) -> Result<Connection> {
+    let _guard = SYNC.lock().unwrap();
+    let mut conn = open(output_root)?;
+    let registry = Registry::load(output_root)?;
+    // Registry edits rename teams and move games between seasons: refresh every run
+    let registry_stamp = file_stamp(&output_root.join(REGISTRY_FILE));
+
+    let stored: HashMap<String, String> = conn
+        .prepare("SELECT run_id, stamp FROM runs")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let tx = conn.transaction()?;
+    let mut present = HashSet::new();
+    for entry in fs::read_dir(output_root)? {
+        let entry = entry?;
+        let run_id = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        // Hidden directories are imports in progress
+        if run_id.starts_with('.') || !path.join("metadata.json").exists() {
+            continue;
+        }
+        let run_stamp = stamp(&path, &registry_stamp);
+        if stored.get(&run_id) != Some(&run_stamp) {
+            // One unreadable run must not hide the others
+            let mut run_context = match RunContext::load(&path) {
+                Ok(run_context) => run_context,
+                Err(e) => {
+                    tracing::warn!("Skipping run {} in the catalogue: {}", run_id, e);
+                    continue;
+                }
+            };
+            run_context.run_id = run_id.clone();
+            refresh(&tx, &run_context, &registry, &run_stamp)?;
+        }
+        present.insert(run_id);
+    }
+    for run_id in stored.keys().filter(|id| !present.contains(*id)) {
+        remove(&tx, run_id)?;
+    }
+    tx.commit()?;
+    Ok(conn)
+}
+
+fn remove(tx: &Transaction, run_id: &str) -> Result<()> {
+    for table in ["runs", "run_tags", "points"] {
+        tx.execute(
+            &format!("DELETE FROM {} WHERE run_id = ?1", table),
+            params![run_id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Rewrites the rows of one run from its directory.
+fn refresh(
+    tx: &Transaction,
+    run_context: &RunContext,
+    registry: &Registry,
+    stamp: &str,
+) -> Result<()> {
+    let run_id = &run_context.run_id;
+    tracing::debug!("Cataloguing run {}", run_id);
+    remove(tx, run_id)?;
+
+    let audit = match load_audit_state(run_context, registry) {
+        Ok(audit) => audit,
+        Err(e) => {
+            tracing::warn!("Failed to load audit of {}: {}", run_id, e);
+            None
+        }
+    };
+    let (light_team, dark_team) = match &audit {
+        Some(state) => (
+            state.settings.light_team_name.clone(),
+            state.settings.dark_team_name.clone(),
+        ),
+        None => run_context.team_names(registry),
+    };
+    // Unlinked teams count as the registry team of the same name
+    let team_id = |linked: &Option<String>, name: &str| {
+        linked
+            .clone()
+            .or_else(|| registry.team_named(name).map(|team| team.id.clone()))
+    };
+    let game = &run_context.game;
+    let light_team_id = team_id(&game.light_team_id, &light_team);
+    let dark_team_id = team_id(&game.dark_team_id, &dark_team);
+    let season = registry.season_of(
+        game.tournament_id.as_deref(),
+        run_context.created_at.date_naive(),
+    );
+    let cliffs: &[CliffData] = audit.as_ref().map_or(&[], |state| &state.cliffs);
+    let team_name = |color: &str| match color {
+        "light" => Some(light_team.clone()),
+        "dark" => Some(dark_team.clone()),
+        _ => None,
+    };
+
+    let mut point_count = 0;
+    let mut breaks = 0;
+    for (i, cliff) in cliffs.iter().enumerate() {
+        let is_point = cliff.status != "FalsePositive" && cliff.status != "Halftime";
+        if is_point {
+            point_count += 1;
+            if cliff.is_break {
+                breaks += 1;
+            }
+        }
+        let pulling_color = cliff.pulling_color().filter(|_| is_point);
+        let scoring_color = scoring_color(cliffs, i);
+        tx.execute(
+            "INSERT INTO points (run_id, frame_index, timestamp, status, pull_side,
+                 pulling_color, scoring_color, pulling_team, scored_by, is_break,
+                 score_light, score_dark)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                run_id,
+                cliff.frame_index as i64,
+                cliff.timestamp,
+                cliff.status,
+                cliff.pull_side().filter(|_| is_point),
+                pulling_color,
+                scoring_color,
+                pulling_color.and_then(team_name),
+                scoring_color.and_then(team_name),
+                cliff.is_break,
+                cliff.score_light,
+                cliff.score_dark,
+            ],
+        )?;
+    }
+    let unaudited = cliffs.iter().filter(|c| c.status == "Unconfirmed").count();
+    // Score when the last recorded point started; its own result is unknown
+    let (final_light, final_dark) = cliffs
+        .last()
+        .map_or((0, 0), |c| (c.score_light, c.score_dark));
+
+    tx.execute(
+        "INSERT INTO runs (run_id, display_name, created_at, game_format, light_team,
+             dark_team, light_team_id, dark_team_id, tournament_id, round, season_id,
+             status, points, unaudited, breaks, final_light, final_dark, metadata, stamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+             ?17, ?18, ?19)",
+        params![
+            run_id,
+            run_context.display_name,
+            run_context
+                .created_at
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+            run_context.game_format.as_str(),
+            light_team,
+            dark_team,
+            light_team_id,
+            dark_team_id,
+            game.tournament_id,
+            game.round,
+            season.map(|s| &s.id),
+            run_status(run_context, audit.as_ref()),
+            point_count,
+            unaudited as i64,
+            breaks,
+            final_light,
+            final_dark,
+            serde_json::to_string(run_context)?,
+            stamp,
+        ],
+    )?;
+    for tag in &run_context.tags {
+        tx.execute(
+            "INSERT OR IGNORE INTO run_tags (run_id, tag) VALUES (?1, ?2)",
+            params![run_id, tag],
+        )?;
+    }
+    Ok(())
+}
+
+/// Color of the team that scored the point started by `cliffs[i]`: the team
+/// pulling the next point, or the halftime winner if the half ended.
+fn scoring_color(cliffs: &[CliffData], i: usize) -> Option<&str> {
+    let cliff = &cliffs[i];
+    if cliff.status == "FalsePositive" || cliff.status == "Halftime" {
+        return None;
+    }
+    let next = cliffs[i + 1..]
+        .iter()
+        .find(|c| c.status != "FalsePositive")?;
+    if next.status == "Halftime" {
+        next.halftime_winner.as_deref()
+    } else {
+        next.pulling_color()
+    }
+}
+
+/// The runs of the output root, as `run_context::list_runs` returns them.
+pub fn list_runs(output_root: &Path) -> Result<Vec<(String, RunContext)>> {
+    if !output_root.exists() {
+        return Ok(Vec::new());
+    }
+    let conn = sync(output_root)?;
+    let mut stmt = conn.prepare("SELECT run_id, metadata FROM runs ORDER BY run_id")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut runs = Vec::new();
+    for row in rows {
+        let (run_id, metadata) = row?;
+        let mut run_context: RunContext = serde_json::from_str(&metadata)?;
+        run_context.output_dir = output_root.join(&run_id);
+        run_context.run_id = run_id.clone();
+        runs.push((run_id, run_context));
+    }
+    Ok(runs)
+}
+
+/// Conditions and parameters of a query, joined with AND
+#[derive(Default)]
+struct Conditions {
+    clauses: Vec<String>,
+    params: Vec<String>,
+}
+
+impl Conditions {
+    /// Adds `clause`, in which every `?` stands for `value`
+    fn add(&mut self, clause: &str, value: &str) {
+        let mut sql = String::new();
+        for (i, part) in clause.split('?').enumerate() {
+            if i > 0 {
+                self.params.push(value.to_string());
+                sql.push_str(&format!("?{}", self.params.len()));
+            }
+            sql.push_str(part);
+        }
+        self.clauses.push(sql);
+    }
+
+    /// Restricts the query to games of a registry team, season or tournament
+    fn add_registry(
+        &mut self,
+        team_id: Option<&str>,
+        season: Option<&str>,
+        tournament: Option<&str>,
+    ) {
+        if let Some(team_id) = team_id {
+            self.add("(r.light_team_id = ? OR r.dark_team_id = ?)", team_id);
+        }
+        if let Some(season) = season {
+            self.add("r.season_id = ?", season);
+        }
+        if let Some(tournament) = tournament {
+            self.add("r.tournament_id = ?", tournament);
+        }
+    }
+
+    fn add_dates(&mut self, since: Option<NaiveDate>, until: Option<NaiveDate>) {
+        if let Some(since) = since {
+            self.add("substr(r.created_at, 1, 10) >= ?", &since.to_string());
+        }
+        if let Some(until) = until {
+            self.add("substr(r.created_at, 1, 10) <= ?", &until.to_string());
+        }
+    }
+
+    fn sql(&self) -> String {
+        if self.clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", self.clauses.join(" AND "))
+        }
+    }
+}
+
+const TAG_CLAUSE: &str =
+    "EXISTS (SELECT 1 FROM run_tags t WHERE t.run_id = r.run_id AND t.tag = ?)";
+const TEAM_CLAUSE: &str = "(r.light_team = ? OR r.dark_team = ?)";
+
+/// Filters of `GET /api/catalogue/runs`; dates are inclusive, on the run's creation
+#[derive(Debug, Default, Deserialize)]
+pub struct RunFilter {
+    pub tag: Option<String>,
+    /// Either team's name
+    pub team: Option<String>,
+    /// Either team's registry id
+    pub team_id: Option<String>,
+    pub season: Option<String>,
+    pub tournament: Option<String>,
+    pub status: Option<String>,
+    /// Only runs with (or without) cliffs left to audit
+    pub unaudited: Option<bool>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CatalogueRun {
+    pub run_id: String,
+    pub display_name: String,
+    pub created_at: String,
+    pub game_format: String,
+    pub light_team_name: String,
+    pub dark_team_name: String,
+    pub light_team_id: Option<String>,
+    pub dark_team_id: Option<String>,
+    pub tournament_id: Option<String>,
+    pub round: Option<String>,
+    pub season_id: Option<String>,
+    pub tags: Vec<String>,
+    pub status: String,
+    pub points: i64,
+    pub unaudited: i64,
+    pub breaks: i64,
+    /// Score when the last recorded point started
+    pub final_score_light: i64,
+    pub final_score_dark: i64,
+}
+
+pub fn query_runs(conn: &Connection, filter: &RunFilter) -> Result<Vec<CatalogueRun>> {
+    let mut conditions = Conditions::default();
+    if let Some(tag) = &filter.tag {
+        conditions.add(TAG_CLAUSE, tag);
+    }
+    if let Some(team) = &filter.team {
+        conditions.add(TEAM_CLAUSE, team);
+    }
+    if let Some(status) = &filter.status {
+        conditions.add("r.status = ?", status);
+    }
+    match filter.unaudited {
+        Some(true) => conditions.clauses.push("r.unaudited > 0".to_string()),
+        Some(false) => conditions.clauses.push("r.unaudited = 0".to_string()),
+        None => {}
+    }
+    conditions.add_registry(
+        filter.team_id.as_deref(),
+        filter.season.as_deref(),
+        filter.tournament.as_deref(),
+    );
+    conditions.add_dates(filter.since, filter.until);
+
+    let sql = format!(
+        "SELECT r.run_id, r.display_name, r.created_at, r.game_format, r.light_team,
+             r.dark_team, r.status, r.points, r.unaudited, r.breaks,
+             (SELECT group_concat(t.tag, char(10)) FROM run_tags t WHERE t.run_id = r.run_id),
+             r.light_team_id, r.dark_team_id, r.tournament_id, r.round, r.season_id,
+             r.final_light, r.final_dark
+         FROM runs r{}
+         ORDER BY r.created_at, r.run_id",
+        conditions.sql()
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let runs = stmt
+        .query_map(params_from_iter(&conditions.params), |row| {
+            let tags: Option<String> = row.get(10)?;
+            Ok(CatalogueRun {
+                run_id: row.get(0)?,
+                display_name: row.get(1)?,
+                created_at: row.get(2)?,
+                game_format: row.get(3)?,
+                light_team_name: row.get(4)?,
+                dark_team_name: row.get(5)?,
+                status: row.get(6)?,
+                points: row.get(7)?,
+                unaudited: row.get(8)?,
+                breaks: row.get(9)?,
+                light_team_id: row.get(11)?,
+                dark_team_id: row.get(12)?,
+                tournament_id: row.get(13)?,
+                round: row.get(14)?,
+                season_id: row.get(15)?,
+                final_score_light: row.get(16)?,
+                final_score_dark: row.get(17)?,
+                tags: tags
+                    .map(|t| t.split('\n').map(str::to_string).collect())
+                    .unwrap_or_default(),
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(runs)
+}
+
+/// Filters of `GET /api/catalogue/points`, e.g. `?scored_by=X&breaks=true&status=Confirmed`
+/// for the breaks team X scored
+#[derive(Debug, Default, Deserialize)]
+pub struct PointFilter {
+    pub run_id: Option<String>,
+    pub tag: Option<String>,
+    /// Either team of the game
+    pub team: Option<String>,
+    /// Either team's registry id
+    pub team_id: Option<String>,
+    pub season: Option<String>,
+    pub tournament: Option<String>,
+    pub pulling_team: Option<String>,
+    pub scored_by: Option<String>,
+    pub breaks: Option<bool>,
+    /// Cliff status: "Unconfirmed", "Confirmed", "FalsePositive" or "Halftime"
+    pub status: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CataloguePoint {
+    pub run_id: String,
+    pub display_name: String,
+    pub created_at: String,
+    pub light_team_name: String,
+    pub dark_team_name: String,
+    pub frame_index: usize,
+    pub timestamp: String,
+    pub status: String,
+    /// "left" or "right"
+    pub pull_side: Option<String>,
+    pub pulling_team: Option<String>,
+    pub scored_by: Option<String>,
+    pub is_break: bool,
+    pub score_light: i32,
+    pub score_dark: i32,
+}
+
+pub fn query_points(conn: &Connection, filter: &PointFilter) -> Result<Vec<CataloguePoint>> {
+    let mut conditions = Conditions::default();
+    if let Some(run_id) = &filter.run_id {
+        conditions.add("r.run_id = ?", run_id);
+    }
+    if let Some(tag) = &filter.tag {
+        conditions.add(TAG_CLAUSE, tag);
+    }
+    if let Some(team) = &filter.team {
+        conditions.add(TEAM_CLAUSE, team);
+    }
+    if let Some(team) = &filter.pulling_team {
+        conditions.add("p.pulling_team = ?", team);
+    }
+    if let Some(team) = &filter.scored_by {
+        conditions.add("p.scored_by = ?", team);
+    }
+    match filter.breaks {
+        Some(true) => conditions.clauses.push("p.is_break".to_string()),
+        Some(false) => conditions.clauses.push("NOT p.is_break".to_string()),
+        None => {}
+    }
+    if let Some(status) = &filter.status {
+        conditions.add("p.status = ?", status);
+    }
+    conditions.add_registry(
+        filter.team_id.as_deref(),
+        filter.season.as_deref(),
+        filter.tournament.as_deref(),
+    );
+    conditions.add_dates(filter.since, filter.until);
+
+    let sql = format!(
+        "SELECT r.run_id, r.display_name, r.created_at, r.light_team, r.dark_team,
+             p.frame_index, p.timestamp, p.status, p.pulling_team, p.scored_by,
+             p.is_break, p.score_light, p.score_dark, p.pull_side
+         FROM points p JOIN runs r ON r.run_id = p.run_id{}
+         ORDER BY r.created_at, r.run_id, p.frame_index",
+        conditions.sql()
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let points = stmt
+        .query_map(params_from_iter(&conditions.params), |row| {
+            Ok(CataloguePoint {
+                run_id: row.get(0)?,
+                display_name: row.get(1)?,
+                created_at: row.get(2)?,
+                light_team_name: row.get(3)?,
+                dark_team_name: row.get(4)?,
+                frame_index: row.get::<_, i64>(5)? as usize,
+                timestamp: row.get(6)?,
+                status: row.get(7)?,
+                pulling_team: row.get(8)?,
+                scored_by: row.get(9)?,
+                is_break: row.get(10)?,
+                score_light: row.get(11)?,
+                score_dark: row.get(12)?,
+                pull_side: row.get(13)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(points)
+}
+
+/// Filters of `GET /api/registry/teams/:id/stats`
+#[derive(Debug, Default, Deserialize)]
+pub struct StatsFilter {
+    pub season: Option<String>,
+    pub tournament: Option<String>,
+    pub since: Option<NaiveDate>,
+    pub until: Option<NaiveDate>,
+}
+
+/// A registry team's games and point outcomes. Point counts only include
+/// confirmed points whose scorer is known; holds and breaks also need the puller.
+#[derive(Debug, Default, Serialize)]
+pub struct TeamStats {
+    pub team_id: String,
+    pub games: Vec<GameLine>,
+    pub points: i64,
+    pub scored: i64,
+    /// Points the team received
+    pub received: i64,
+    /// Received points the team scored
+    pub holds: i64,
+    /// Points the team pulled
+    pub pulled: i64,
+    /// Pulled points the team scored
+    pub breaks: i64,
+    /// Points whose pulling team is unknown, left out of holds and breaks
+    pub unknown_puller: i64,
+    pub hold_rate: Option<f64>,
+    pub break_rate: Option<f64>,
+    pub pull_sides: Vec<PullSideStats>,
+}
+
+/// One game of the team, from its side
+#[derive(Debug, Serialize)]
+pub struct GameLine {
+    pub run_id: String,
+    pub display_name: String,
+    pub created_at: String,
+    pub tournament_id: Option<String>,
+    pub round: Option<String>,
+    /// "light" or "dark"
+    pub color: String,
+    pub opponent: String,
+    /// Score when the last recorded point started
+    pub score_for: i64,
+    pub score_against: i64,
+    /// Cliffs of the game left to audit
+    pub unaudited: i64,
+}
+
+/// The team's pulls from one side of the video
+#[derive(Debug, Serialize)]
+pub struct PullSideStats {
+    /// "left" or "right"
+    pub side: String,
+    pub pulls: i64,
+    pub breaks: i64,
+    pub break_rate: Option<f64>,
+}
+
+fn rate(count: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| count as f64 / total as f64)
+}
+
+pub fn team_stats(conn: &Connection, team_id: &str, filter: &StatsFilter) -> Result<TeamStats> {
+    let mut conditions = Conditions::default();
+    conditions.add_registry(
+        Some(team_id),
+        filter.season.as_deref(),
+        filter.tournament.as_deref(),
+    );
+    conditions.add_dates(filter.since, filter.until);
+
+    let sql = format!(
+        "SELECT r.run_id, r.display_name, r.created_at, r.tournament_id, r.round,
+             r.light_team_id, r.light_team, r.dark_team, r.final_light, r.final_dark,
+             r.unaudited
+         FROM runs r{}
+         ORDER BY r.created_at, r.run_id",
+        conditions.sql()
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let games = stmt
+        .query_map(params_from_iter(&conditions.params), |row| {
+            let is_light = row.get::<_, Option<String>>(5)?.as_deref() == Some(team_id);
+            let (light, dark): (String, String) = (row.get(6)?, row.get(7)?);
+            let (final_light, final_dark): (i64, i64) = (row.get(8)?, row.get(9)?);
+            Ok(GameLine {
+                run_id: row.get(0)?,
+                display_name: row.get(1)?,
+                created_at: row.get(2)?,
+                tournament_id: row.get(3)?,
+                round: row.get(4)?,
+                color: if is_light { "light" } else { "dark" }.to_string(),
+                opponent: if is_light { dark } else { light },
+                score_for: if is_light { final_light } else { final_dark },
+                score_against: if is_light { final_dark } else { final_light },
+                unaudited: row.get(10)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stats = TeamStats {
+        team_id: team_id.to_string(),
+        games,
+        ..TeamStats::default()
+    };
+    let mut sides: Vec<PullSideStats> = Vec::new();
+    conditions.add("p.status = ?", "Confirmed");
+    conditions
+        .clauses
+        .push("p.scoring_color IS NOT NULL".to_string());
+    let sql = format!(
+        "SELECT r.light_team_id, p.pull_side, p.pulling_color, p.scoring_color
+         FROM points p JOIN runs r ON r.run_id = p.run_id{}",
+        conditions.sql()
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(params_from_iter(&conditions.params))?;
+    while let Some(row) = rows.next()? {
+        let color = if row.get::<_, Option<String>>(0)?.as_deref() == Some(team_id) {
+            "light"
+        } else {
+            "dark"
+        };
+        let pull_side: Option<String> = row.get(1)?;
+        let pulling_color: Option<String> = row.get(2)?;
+        let scored = row.get::<_, String>(3)? == color;
+
+        stats.points += 1;
+        stats.scored += scored as i64;
+        let pulled = match pulling_color {
+            Some(pulling_color) => pulling_color == color,
+            None => {
+                stats.unknown_puller += 1;
+                continue;
+            }
+        };
+        if pulled {
+            stats.pulled += 1;
+            stats.breaks += scored as i64;
+            if let Some(side) = pull_side {
+                let i = match sides.iter().position(|s| s.side == side) {
+                    Some(i) => i,
+                    None => {
+                        sides.push(PullSideStats {
+                            side,
+                            pulls: 0,
+                            breaks: 0,
+                            break_rate: None,
+                        });
+                        sides.len() - 1
+                    }
+                };
+                sides[i].pulls += 1;
+                sides[i].breaks += scored as i64;
+            }
+        } else {
+            stats.received += 1;
+            stats.holds += scored as i64;
+        }
+    }
+
+    stats.hold_rate = rate(stats.holds, stats.received);
+    stats.break_rate = rate(stats.breaks, stats.pulled);
+    for side in &mut sides {
+        side.break_rate = rate(side.breaks, side.pulls);
+    }
+    sides.sort_by(|a, b| a.side.cmp(&b.side));
+    stats.pull_sides = sides;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cliff(frame_index: usize, status: &str, pulling: Option<&str>) -> CliffData {
+        let mut cliff = CliffData::from_detection(frame_index, String::new(), true, false);
+        cliff.status = status.to_string();
+        cliff.left_team_color = pulling.map(str::to_string);
+        cliff
+    }
+
+    #[test]
+    fn points_are_scored_by_the_next_pulling_team() {
+        let mut halftime = cliff(30, "Halftime", None);
+        halftime.halftime_winner = Some("dark".to_string());
+        let cliffs = vec![
+            cliff(10, "Confirmed", Some("light")),
+            cliff(15, "FalsePositive", None),
+            cliff(20, "Confirmed", Some("light")),
+            halftime,
+            cliff(40, "Confirmed", Some("dark")),
+        ];
+        assert_eq!(scoring_color(&cliffs, 0), Some("light"));
+        assert_eq!(scoring_color(&cliffs, 1), None);
+        assert_eq!(scoring_color(&cliffs, 2), Some("dark"));
+        assert_eq!(scoring_color(&cliffs, 3), None);
+        assert_eq!(scoring_color(&cliffs, 4), None);
+    }
+
+    #[test]
+    fn sync_skips_runs_it_cannot_read() {
+        let tmp = tempfile::tempdir().unwrap();
+        let good = tmp.path().join("good");
+        fs::create_dir_all(&good).unwrap();
+        RunContext::new("good.mp4", "good", good).save().unwrap();
+        let bad = tmp.path().join("bad");
+        fs::create_dir_all(&bad).unwrap();
+        fs::write(bad.join("metadata.json"), "{").unwrap();
+
+        let runs = list_runs(tmp.path()).unwrap();
+        let ids: Vec<&str> = runs.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["good"]);
+
+        // A save records the file in the manifest, which moves the directory's mtime
+        let mut run_context = runs[0].1.clone();
+        run_context.display_name = "Final".to_string();
+        run_context.save().unwrap();
+        let runs = list_runs(tmp.path()).unwrap();
+        assert_eq!(runs[0].1.display_name, "Final");
+    }
+
+    #[test]
+    fn points_with_an_unknown_puller_are_neither_received_nor_pulled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let conn = open(tmp.path()).unwrap();
+        conn.execute(
+            "INSERT INTO runs (run_id, display_name, created_at, game_format, light_team,
+                 dark_team, light_team_id, status, points, unaudited, breaks, final_light,
+                 final_dark, metadata, stamp)
+             VALUES ('game', 'game', '2026-10-18T12:00:00Z', 'sevens', 'Ravens', 'Owls',
+                 'ravens', 'audited', 2, 0, 0, 1, 1, '{}', '')",
+            [],
+        )
+        .unwrap();
+        for (frame_index, pulling, scoring) in [(10, Some("dark"), "light"), (20, None, "dark")] {
+            conn.execute(
+                "INSERT INTO points (run_id, frame_index, timestamp, status, pulling_color,
+                     scoring_color, is_break, score_light, score_dark)
+                 VALUES ('game', ?1, '', 'Confirmed', ?2, ?3, 0, 0, 0)",
+                params![frame_index, pulling, scoring],
+            )
+            .unwrap();
+        }
+
+        let stats = team_stats(&conn, "ravens", &StatsFilter::default()).unwrap();
+        assert_eq!(stats.points, 2);
+        assert_eq!(stats.unknown_puller, 1);
+        assert_eq!((stats.received, stats.holds), (1, 1));
+        assert_eq!((stats.pulled, stats.breaks), (0, 0));
+        assert_eq!(stats.hold_rate, Some(1.0));
+    }
+
+    #[test]
+    fn conditions_number_their_parameters() {
+        let mut conditions = Conditions::default();
+        conditions.add("r.status = ?", "audited");
+        conditions.add(TEAM_CLAUSE, "Ravens");
+        assert_eq!(
+            conditions.sql(),
+            " WHERE r.status = ?1 AND (r.light_team = ?2 OR r.dark_team = ?3)"
+        );
+        assert_eq!(conditions.params, ["audited", "Ravens", "Ravens"]);
+    }
+}
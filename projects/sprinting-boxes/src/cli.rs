@@ -19,6 +19,10 @@ pub struct Args {
     /// Root directory for output artifacts
     #[arg(long, env = "SPRINTING_BOXES_OUTPUT_ROOT")]
     pub output_root: String,
+
+    /// Disk budget per run for crops regenerated from the source video, in MB
+    #[arg(long, env = "SPRINTING_BOXES_CROP_CACHE_MB", default_value_t = 512)]
+    pub crop_cache_mb: u64,
 }
 
 impl Args {
@@ -2,9 +2,12 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
-use ultimate_event_detection::CliffDetectorConfig;
+use ultimate_event_detection::{CliffDetectorConfig, GameFormat};
 use crate::web::evaluation::models::DetectorConfigParams;
 
+/// Cliff detector settings file, relative to the working directory
+pub const DETECTOR_CONFIG_FILE: &str = "detector.config.yaml";
+
 /// Configuration for the cliff detector, loaded from detector.config.yaml
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DetectorConfig {
@@ -67,6 +70,17 @@ impl DetectorConfig {
             }
         }
     }
+
+    /// Cliff detector parameters for a run of the given format.
+    ///
+    /// `detector.config.yaml` is tuned on 7s footage, so it only applies to
+    /// `Sevens`; other formats use their built-in defaults.
+    pub fn cliff_config_for(&self, format: GameFormat) -> CliffDetectorConfig {
+        match format {
+            GameFormat::Sevens => CliffDetectorConfig::from(self.clone()),
+            other => other.cliff_config(),
+        }
+    }
 }
 
 impl From<DetectorConfig> for CliffDetectorConfig {
@@ -0,0 +1,271 @@
+//! Coarse-to-fine detection cascade.
+//!
+//! One downscaled pass over the overview decides which tiles need the full-resolution
+//! detector. Tiles whose coarse view is confident and unchanged since the previous
+//! frame reuse their last fine detections instead.
+
+use std::collections::HashMap;
+use ultimate_event_detection::Aabb;
+
+/// Cascade settings, stored per run in `metadata.json`
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CascadeConfig {
+    /// Coarse detections below this confidence are ignored entirely
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f32,
+    /// Coarse detections below this confidence send their tile to the fine pass
+    #[serde(default = "default_confident")]
+    pub confident: f32,
+    /// Coarse detections shorter than this, in coarse-pass pixels, send their tile
+    /// to the fine pass
+    #[serde(default = "default_min_height_px")]
+    pub min_height_px: f32,
+    /// Tiles reused this many frames in a row are refreshed anyway
+    #[serde(default = "default_max_reuse_frames")]
+    pub max_reuse_frames: usize,
+}
+
+fn default_min_confidence() -> f32 {
+    0.25
+}
+
+fn default_confident() -> f32 {
+    0.7
+}
+
+fn default_min_height_px() -> f32 {
+    24.0
+}
+
+fn default_max_reuse_frames() -> usize {
+    15
+}
+
+impl Default for CascadeConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: default_min_confidence(),
+            confident: default_confident(),
+            min_height_px: default_min_height_px(),
+            max_reuse_frames: default_max_reuse_frames(),
+        }
+    }
+}
+
+/// A detection from the coarse pass, in overview pixels
+#[derive(Clone, Copy, Debug)]
+pub struct CoarseDetection {
+    pub bounds: Aabb,
+    pub confidence: f32,
+    /// Height as the detector saw it, in coarse-pass pixels
+    pub height_px: f32,
+}
+
+/// Identifies a tile across frames: crop, position and size in crop pixels
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TileKey {
+    pub crop: String,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub tile_size: u32,
+}
+
+/// What to do with a tile this frame
+#[derive(Debug)]
+pub enum TileAction<T> {
+    /// Run the detector; store the result with `CascadeState::store`
+    Refine { coarse_count: usize },
+    /// Static region: reuse the tile's detections from an earlier frame
+    Reuse(Vec<T>),
+}
+
+struct CachedTile<T> {
+    detections: Vec<T>,
+    coarse_count: usize,
+    reused: usize,
+    seen: bool,
+}
+
+/// Fine detections of each tile from the last frame it was run
+pub struct CascadeState<T> {
+    tiles: HashMap<TileKey, CachedTile<T>>,
+}
+
+impl<T: Clone> CascadeState<T> {
+    pub fn new() -> Self {
+        Self {
+            tiles: HashMap::new(),
+        }
+    }
+
+    /// Decides whether a tile needs the fine pass. `bounds` is the tile's area in
+    /// overview pixels, or `None` when it cannot be mapped there.
+    pub fn plan_tile(
+        &mut self,
+        key: &TileKey,
+        bounds: Option<Aabb>,
+        coarse: &[CoarseDetection],
+        config: &CascadeConfig,
+    ) -> TileAction<T> {
+        let bounds = match bounds {
+            Some(bounds) => bounds,
+            None => return TileAction::Refine { coarse_count: 0 },
+        };
+
+        let mut coarse_count = 0;
+        let mut uncertain = false;
+        for det in coarse.iter().filter(|d| d.bounds.intersects(&bounds)) {
+            coarse_count += 1;
+            uncertain |= det.confidence < config.confident || det.height_px < config.min_height_px;
+        }
+
+        match self.tiles.get_mut(key) {
+            Some(cached)
+                if !uncertain
+                    && cached.coarse_count == coarse_count
+                    && cached.reused < config.max_reuse_frames =>
+            {
+                cached.reused += 1;
+                cached.seen = true;
+                TileAction::Reuse(cached.detections.clone())
+            }
+            _ => TileAction::Refine { coarse_count },
+        }
+    }
+
+    /// Records the fine detections of a tile that was just run
+    pub fn store(&mut self, key: TileKey, detections: Vec<T>, coarse_count: usize) {
+        self.tiles.insert(
+            key,
+            CachedTile {
+                detections,
+                coarse_count,
+                reused: 0,
+                seen: true,
+            },
+        );
+    }
+
+    /// Forgets tiles not planned this frame, e.g. after the tile size changed
+    pub fn finish_frame(&mut self) {
+        self.tiles.retain(|_, tile| tile.seen);
+        for tile in self.tiles.values_mut() {
+            tile.seen = false;
+        }
+    }
+}
+
+impl<T: Clone> Default for CascadeState<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(x_offset: i32) -> TileKey {
+        TileKey {
+            crop: "left".to_string(),
+            x_offset,
+            y_offset: 0,
+            tile_size: 640,
+        }
+    }
+
+    fn coarse(x: f32, confidence: f32, height_px: f32) -> CoarseDetection {
+        CoarseDetection {
+            bounds: Aabb::from_xywh(x, 100.0, 20.0, 40.0),
+            confidence,
+            height_px,
+        }
+    }
+
+    #[test]
+    fn empty_tile_is_reused_until_refresh() {
+        let config = CascadeConfig {
+            max_reuse_frames: 2,
+            ..Default::default()
+        };
+        let mut state = CascadeState::<u32>::new();
+        let tile = Some(Aabb::from_xywh(0.0, 0.0, 640.0, 640.0));
+
+        // First sighting always runs
+        assert!(matches!(
+            state.plan_tile(&key(0), tile, &[], &config),
+            TileAction::Refine { coarse_count: 0 }
+        ));
+        state.store(key(0), vec![7], 0);
+        state.finish_frame();
+
+        for _ in 0..2 {
+            match state.plan_tile(&key(0), tile, &[], &config) {
+                TileAction::Reuse(dets) => assert_eq!(dets, vec![7]),
+                other => panic!("expected reuse, got {:?}", other),
+            }
+            state.finish_frame();
+        }
+        // Refreshed after max_reuse_frames
+        assert!(matches!(
+            state.plan_tile(&key(0), tile, &[], &config),
+            TileAction::Refine { .. }
+        ));
+    }
+
+    #[test]
+    fn uncertain_or_changed_tiles_are_refined() {
+        let config = CascadeConfig::default();
+        let mut state = CascadeState::<u32>::new();
+        let tile = Some(Aabb::from_xywh(0.0, 0.0, 640.0, 640.0));
+        state.store(key(0), vec![1], 1);
+
+        // Same confident, tall player: reuse
+        let steady = [coarse(50.0, 0.9, 60.0)];
+        assert!(matches!(
+            state.plan_tile(&key(0), tile, &steady, &config),
+            TileAction::Reuse(_)
+        ));
+        // A second player walked in
+        let more = [coarse(50.0, 0.9, 60.0), coarse(300.0, 0.9, 60.0)];
+        assert!(matches!(
+            state.plan_tile(&key(0), tile, &more, &config),
+            TileAction::Refine { coarse_count: 2 }
+        ));
+        // Low confidence or small players need the fine pass
+        for hint in [coarse(50.0, 0.4, 60.0), coarse(50.0, 0.9, 12.0)] {
+            assert!(matches!(
+                state.plan_tile(&key(0), tile, &[hint], &config),
+                TileAction::Refine { coarse_count: 1 }
+            ));
+        }
+        // Detections outside the tile don't count; unmappable tiles always run
+        let elsewhere = [coarse(700.0, 0.4, 12.0)];
+        assert!(matches!(
+            state.plan_tile(&key(0), tile, &elsewhere, &config),
+            TileAction::Refine { coarse_count: 0 }
+        ));
+        assert!(matches!(
+            state.plan_tile(&key(0), None, &[], &config),
+            TileAction::Refine { .. }
+        ));
+    }
+
+    #[test]
+    fn tiles_not_planned_are_forgotten() {
+        let config = CascadeConfig::default();
+        let mut state = CascadeState::<u32>::new();
+        let tile = Some(Aabb::from_xywh(0.0, 0.0, 640.0, 640.0));
+        state.store(key(0), vec![], 0);
+        state.store(key(512), vec![], 0);
+        state.finish_frame();
+
+        // Only the first tile is planned, e.g. after a layout change
+        state.plan_tile(&key(0), tile, &[], &config);
+        state.finish_frame();
+        assert!(matches!(
+            state.plan_tile(&key(512), tile, &[], &config),
+            TileAction::Refine { .. }
+        ));
+    }
+}
@@ -5,7 +5,11 @@
 ///
 /// - macOS: CoreML (zero-copy GPU pipeline via CVPixelBuffer)
 /// - Other: ONNX/RT-DETR via the USLS library
+///
+/// `pose` adds an optional keypoint stage (ONNX/RTMO on every platform).
+pub mod cascade;
 pub mod onnx;
+pub mod pose;
 pub mod slicing;
 
 #[cfg(target_os = "macos")]
@@ -38,6 +42,13 @@ pub trait Detector {
     fn detect(&self, tile: &Mat) -> Result<Vec<Detection>>;
 }
 
+/// Person/disc detection model, relative to the working directory (D-FINE on macOS)
+#[cfg(target_os = "macos")]
+pub const DETECTOR_MODEL_PATH: &str = "models/dfine_n_coco.mlpackage";
+/// Person/disc detection model, relative to the working directory (RT-DETR elsewhere)
+#[cfg(not(target_os = "macos"))]
+pub const DETECTOR_MODEL_PATH: &str = "rtdetr/v2-m.onnx";
+
 /// Create the platform-appropriate detector with hardcoded model paths.
 ///
 /// On macOS, uses CoreML for zero-copy GPU inference (D-FINE model).
@@ -45,13 +56,11 @@ pub trait Detector {
 pub fn create_detector() -> Result<Box<dyn Detector>> {
     #[cfg(target_os = "macos")]
     {
-        const MODEL_PATH: &str = "models/dfine_n_coco.mlpackage";
-        Ok(Box::new(coreml::CoremlDetector::new(MODEL_PATH)?))
+        Ok(Box::new(coreml::CoremlDetector::new(DETECTOR_MODEL_PATH)?))
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        const MODEL_PATH: &str = "rtdetr/v2-m.onnx";
-        Ok(Box::new(onnx::OnnxDetector::new(MODEL_PATH)?))
+        Ok(Box::new(onnx::OnnxDetector::new(DETECTOR_MODEL_PATH)?))
     }
 }
@@ -84,7 +84,7 @@ impl Detector for OnnxDetector {
 }
 
 /// Convert OpenCV Mat (BGR) to DynamicImage (RGB)
-fn mat_to_dynamic_image(mat: &Mat) -> Result<DynamicImage> {
+pub(crate) fn mat_to_dynamic_image(mat: &Mat) -> Result<DynamicImage> {
     let mut rgb_mat = Mat::default();
     opencv::imgproc::cvt_color_def(mat, &mut rgb_mat, opencv::imgproc::COLOR_BGR2RGB)?;
 
@@ -0,0 +1,103 @@
+//! Pose estimation for end-zone crops (RTMO via USLS).
+//!
+//! RTMO is a one-stage multi-person model, so it needs no person boxes and runs on
+//! whole crops. The keypoints feed the stance features in `ultimate_event_detection`.
+
+use anyhow::Result;
+use opencv::core::Mat;
+use std::sync::Mutex;
+use ultimate_event_detection::Pose;
+use usls::models::RTMO;
+use usls::{Config, Image};
+
+use crate::detection::onnx::mat_to_dynamic_image;
+
+/// Pose stage settings, stored per run in `metadata.json`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PoseConfig {
+    /// RTMO ONNX model, relative to the working directory
+    #[serde(default = "default_model_path")]
+    pub model_path: String,
+    /// Keypoints below this confidence are treated as hidden
+    #[serde(default = "default_min_keypoint_confidence")]
+    pub min_keypoint_confidence: f32,
+}
+
+fn default_model_path() -> String {
+    "rtmo/s.onnx".to_string()
+}
+
+fn default_min_keypoint_confidence() -> f32 {
+    0.3
+}
+
+impl Default for PoseConfig {
+    fn default() -> Self {
+        Self {
+            model_path: default_model_path(),
+            min_keypoint_confidence: default_min_keypoint_confidence(),
+        }
+    }
+}
+
+/// Pose estimator trait — accepts an OpenCV Mat crop, returns each person's
+/// keypoints in crop pixels.
+pub trait PoseDetector {
+    fn detect_poses(&self, crop: &Mat) -> Result<Vec<Pose>>;
+}
+
+pub struct OnnxPoseDetector {
+    model: Mutex<RTMO>,
+}
+
+impl OnnxPoseDetector {
+    pub fn new(config: &PoseConfig) -> Result<Self> {
+        let model_config = Config::rtmo()
+            .with_model_file(&config.model_path)
+            .with_keypoint_confs(&[config.min_keypoint_confidence]);
+
+        #[cfg(target_os = "macos")]
+        let model_config = model_config.with_model_device(usls::Device::CoreMl);
+
+        let model = RTMO::new(model_config.commit()?)?;
+        Ok(Self {
+            model: Mutex::new(model),
+        })
+    }
+}
+
+impl PoseDetector for OnnxPoseDetector {
+    fn detect_poses(&self, crop: &Mat) -> Result<Vec<Pose>> {
+        let usls_image = Image::from(mat_to_dynamic_image(crop)?);
+
+        let mut model = self
+            .model
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Mutex poisoned: {}", e))?;
+        let results = model.forward(&[usls_image])?;
+
+        let people = match results.first() {
+            Some(y) => &y.keypointss,
+            None => return Ok(Vec::new()),
+        };
+
+        // Keypoints below the confidence threshold come back without a confidence
+        Ok(people
+            .iter()
+            .map(|keypoints| {
+                let mut pose = Pose::default();
+                for (slot, keypoint) in pose.keypoints.iter_mut().zip(keypoints) {
+                    if keypoint.confidence().is_some() {
+                        *slot = Some(keypoint.xy());
+                    }
+                }
+                pose
+            })
+            .collect())
+    }
+}
+
+/// Create the pose detector for a run's pose settings.
+pub fn create_pose_detector(config: &PoseConfig) -> Result<Box<dyn PoseDetector>> {
+    Ok(Box::new(OnnxPoseDetector::new(config)?))
+}
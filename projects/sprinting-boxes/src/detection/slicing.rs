@@ -1,7 +1,8 @@
-use crate::pipeline::types::{BoundingBox, Point};
+use crate::pipeline::types::{AdjustableBoundingBox, BoundingBox, NmsStats, Point};
 use anyhow::Result;
 use opencv::core::{Mat, Rect, Scalar};
 use opencv::prelude::*;
+use ultimate_event_detection::{Aabb, BoxGrid};
 
 /// Wrapper around usls::Hbb to implement BoundingBox trait
 #[derive(Clone)]
@@ -25,6 +26,17 @@ impl BoundingBox for HbbWrapper {
     }
 }
 
+impl AdjustableBoundingBox for HbbWrapper {
+    fn adjusted(&self, x: f32, y: f32, w: f32, h: f32, confidence: f32) -> Self {
+        HbbWrapper(
+            self.0
+                .clone()
+                .with_xywh(x, y, w, h)
+                .with_confidence(confidence),
+        )
+    }
+}
+
 impl From<HbbWrapper> for usls::Hbb {
     fn from(wrapper: HbbWrapper) -> Self {
         wrapper.0
@@ -37,6 +49,83 @@ impl From<usls::Hbb> for HbbWrapper {
     }
 }
 
+/// How overlapping detections are resolved
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NmsStrategy {
+    /// Greedy hard NMS: drop any box whose IoU with a more confident kept box exceeds the threshold
+    #[default]
+    Hard,
+    /// Gaussian Soft-NMS: decay overlapping scores by `exp(-iou² / sigma)` instead of
+    /// dropping them, and remove boxes whose score falls below `score_threshold`.
+    /// Kept boxes keep their detector confidence. Ignores the IoU threshold.
+    SoftGaussian {
+        #[serde(default = "default_soft_nms_sigma")]
+        sigma: f32,
+        #[serde(default = "default_soft_nms_score_threshold")]
+        score_threshold: f32,
+    },
+    /// DIoU-NMS: hard NMS on IoU minus normalized centre distance, so neighbours in a
+    /// tight line-up (overlapping boxes, distinct centres) survive
+    Diou,
+    /// Weighted Boxes Fusion: average overlapping boxes and their confidences into one,
+    /// weighted by confidence.
+    /// Meant for the overview/EZ merge, where both crops see the same players.
+    Wbf,
+}
+
+fn default_soft_nms_sigma() -> f32 {
+    0.5
+}
+
+fn default_soft_nms_score_threshold() -> f32 {
+    0.3
+}
+
+impl NmsStrategy {
+    /// Soft-NMS with the default sigma (0.5) and score threshold (0.3)
+    pub fn soft_gaussian() -> Self {
+        NmsStrategy::SoftGaussian {
+            sigma: default_soft_nms_sigma(),
+            score_threshold: default_soft_nms_score_threshold(),
+        }
+    }
+
+    /// Short name recorded in `NmsStats` and `detection_summary.csv`
+    pub fn name(&self) -> &'static str {
+        match self {
+            NmsStrategy::Hard => "hard",
+            NmsStrategy::SoftGaussian { .. } => "soft_gaussian",
+            NmsStrategy::Diou => "diou",
+            NmsStrategy::Wbf => "wbf",
+        }
+    }
+}
+
+impl std::str::FromStr for NmsStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hard" | "nms" => Ok(NmsStrategy::Hard),
+            "soft" | "soft_nms" | "soft_gaussian" => Ok(NmsStrategy::soft_gaussian()),
+            "diou" | "diou_nms" => Ok(NmsStrategy::Diou),
+            "wbf" => Ok(NmsStrategy::Wbf),
+            other => Err(format!("Unknown NMS strategy: {}", other)),
+        }
+    }
+}
+
+/// Suppression strategies of a run (`metadata.json` `nms`); hard NMS by default
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct NmsConfig {
+    /// Strategy for deduplicating detections within each crop
+    pub strategy: NmsStrategy,
+    /// Strategy for merging end zone detections into the overview
+    pub merge_strategy: NmsStrategy,
+}
+
 /// Configuration for sliding window inference
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct SliceConfig {
@@ -46,6 +135,12 @@ pub struct SliceConfig {
     pub overlap: f32,
     /// IoU threshold for NMS deduplication
     pub nms_iou_threshold: f32,
+    /// Strategy for deduplicating detections within each crop
+    #[serde(default)]
+    pub nms_strategy: NmsStrategy,
+    /// Strategy for merging end zone detections into the overview
+    #[serde(default)]
+    pub merge_strategy: NmsStrategy,
 }
 
 impl SliceConfig {
@@ -54,9 +149,22 @@ impl SliceConfig {
             tile_size,
             overlap: overlap.clamp(0.0, 0.5),
             nms_iou_threshold: 0.5,
+            nms_strategy: NmsStrategy::Hard,
+            merge_strategy: NmsStrategy::Hard,
         }
     }
 
+    /// Set the per-crop and merge suppression strategies
+    pub fn with_strategies(
+        mut self,
+        nms_strategy: NmsStrategy,
+        merge_strategy: NmsStrategy,
+    ) -> Self {
+        self.nms_strategy = nms_strategy;
+        self.merge_strategy = merge_strategy;
+        self
+    }
+
     /// Returns true if slicing is enabled (tile_size > 0)
     pub fn is_enabled(&self) -> bool {
         self.tile_size > 0
@@ -74,14 +182,197 @@ impl Default for SliceConfig {
             tile_size: 0, // Disabled by default
             overlap: 0.2,
             nms_iou_threshold: 0.5,
+            nms_strategy: NmsStrategy::Hard,
+            merge_strategy: NmsStrategy::Hard,
+        }
+    }
+}
+
+/// How the tile size is chosen for each crop
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TilingPolicy {
+    /// Every crop is cut into `SliceConfig::tile_size` tiles
+    #[default]
+    Fixed,
+    /// Size tiles per crop so players reach the model about `target_player_px` tall.
+    /// Tiles are resized to `SliceConfig::tile_size` before inference: far-field crops
+    /// get small tiles that are upscaled, near-field crops large ones that are downscaled.
+    Adaptive {
+        /// Player height to aim for, in model-input pixels
+        #[serde(default = "default_target_player_px")]
+        target_player_px: f32,
+        /// Smallest tile edge in crop pixels, which caps the zoom
+        #[serde(default = "default_min_tile_size")]
+        min_tile_size: u32,
+        /// Largest tile edge in crop pixels
+        #[serde(default = "default_max_tile_size")]
+        max_tile_size: u32,
+        /// Allow tiles smaller than the model input. When false, tiles only grow,
+        /// which saves inference on near-field crops without zooming into far ones.
+        #[serde(default = "default_upscale")]
+        upscale: bool,
+    },
+}
+
+fn default_target_player_px() -> f32 {
+    64.0
+}
+
+fn default_min_tile_size() -> u32 {
+    320
+}
+
+fn default_max_tile_size() -> u32 {
+    1280
+}
+
+fn default_upscale() -> bool {
+    true
+}
+
+/// Tile size chosen for one crop and the resize applied to its tiles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TilePlan {
+    /// Tile edge in crop pixels
+    pub tile_size: u32,
+    /// Model-input pixels per crop pixel (> 1 upscales)
+    pub scale: f32,
+}
+
+impl TilingPolicy {
+    /// Adaptive tiling with the default target (64 px), tile range (320-1280) and upscaling
+    pub fn adaptive() -> Self {
+        TilingPolicy::Adaptive {
+            target_player_px: default_target_player_px(),
+            min_tile_size: default_min_tile_size(),
+            max_tile_size: default_max_tile_size(),
+            upscale: default_upscale(),
+        }
+    }
+
+    /// Plan tiles for a crop whose players are about `player_height` crop pixels tall.
+    /// `input_size` is the model input edge; tiles match it under `Fixed` or when
+    /// there is no height estimate yet.
+    pub fn plan(&self, input_size: u32, player_height: Option<f32>) -> TilePlan {
+        let unscaled = TilePlan {
+            tile_size: input_size,
+            scale: 1.0,
+        };
+        match *self {
+            TilingPolicy::Fixed => unscaled,
+            TilingPolicy::Adaptive {
+                target_player_px,
+                min_tile_size,
+                max_tile_size,
+                upscale,
+            } => {
+                let height = match player_height {
+                    Some(h) if h.is_finite() && h > 0.0 => h,
+                    _ => return unscaled,
+                };
+                if input_size == 0 || target_player_px <= 0.0 {
+                    return unscaled;
+                }
+
+                let min = if upscale {
+                    min_tile_size.max(32)
+                } else {
+                    min_tile_size.max(input_size)
+                };
+                let max = max_tile_size.max(min);
+                // Steps of 32 px so small drifts in the running median keep the same tiles
+                let ideal = input_size as f32 * height / target_player_px;
+                let tile_size = (((ideal / 32.0).round() as u32) * 32).clamp(min, max);
+                TilePlan {
+                    tile_size,
+                    scale: input_size as f32 / tile_size as f32,
+                }
+            }
+        }
+    }
+}
+
+/// Player height over the square root of the region's area: 1.8 m against an
+/// 18 x 37 m end zone, and against a 100 x 37 m field
+const END_ZONE_PLAYER_RATIO: f32 = 0.07;
+const FIELD_PLAYER_RATIO: f32 = 0.03;
+
+/// Rough player height in crop pixels from a region polygon's on-screen area.
+/// Used until a crop has enough detections for a running median. Perspective
+/// shrinks far regions, so the estimate errs small there, which only adds zoom.
+pub fn player_height_prior(polygon: &[Point], end_zone: bool) -> Option<f32> {
+    if polygon.len() < 3 {
+        return None;
+    }
+    let mut twice_area = 0.0;
+    for (i, p) in polygon.iter().enumerate() {
+        let q = &polygon[(i + 1) % polygon.len()];
+        twice_area += p.x * q.y - q.x * p.y;
+    }
+    let area = twice_area.abs() / 2.0;
+    if area <= 0.0 {
+        return None;
+    }
+    let ratio = if end_zone {
+        END_ZONE_PLAYER_RATIO
+    } else {
+        FIELD_PLAYER_RATIO
+    };
+    Some(ratio * area.sqrt())
+}
+
+/// Recent detection heights per crop, whose median drives `TilingPolicy::Adaptive`
+pub struct PlayerScaleTracker {
+    window: usize,
+    min_samples: usize,
+    heights: std::collections::HashMap<String, std::collections::VecDeque<f32>>,
+}
+
+impl PlayerScaleTracker {
+    /// Keeps the last `window` heights per crop and reports a median once
+    /// `min_samples` have been seen
+    pub fn new(window: usize, min_samples: usize) -> Self {
+        Self {
+            window: window.max(1),
+            min_samples: min_samples.max(1),
+            heights: std::collections::HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, crop: &str, heights: impl IntoIterator<Item = f32>) {
+        let window = self.window;
+        let recent = self.heights.entry(crop.to_string()).or_default();
+        for h in heights.into_iter().filter(|h| h.is_finite() && *h > 0.0) {
+            if recent.len() == window {
+                recent.pop_front();
+            }
+            recent.push_back(h);
+        }
+    }
+
+    /// Median recent height for the crop, or `None` while it has too few samples
+    pub fn median(&self, crop: &str) -> Option<f32> {
+        let recent = self.heights.get(crop)?;
+        if recent.len() < self.min_samples {
+            return None;
         }
+        let mut sorted: Vec<f32> = recent.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        Some(sorted[sorted.len() / 2])
+    }
+}
+
+impl Default for PlayerScaleTracker {
+    fn default() -> Self {
+        Self::new(300, 20)
     }
 }
 
 /// A tile extracted from a larger image
 #[derive(Clone)]
 pub struct Tile {
-    /// The tile image (padded to tile_size x tile_size)
+    /// The tile image (padded to tile_size x tile_size, then resized by `scale`)
     pub image: Mat,
     /// X offset of this tile in the original image
     pub x_offset: i32,
@@ -93,6 +384,8 @@ pub struct Tile {
     /// Original height before padding
     #[allow(dead_code)]
     pub original_height: i32,
+    /// Model-input pixels per crop pixel; the image was resized by this factor
+    pub scale: f32,
 }
 
 /// Helper to generate tile offsets along one dimension
@@ -200,6 +493,7 @@ pub fn generate_tiles(
                 y_offset: tile_y,
                 original_width: tile_w,
                 original_height: tile_h,
+                scale: 1.0,
             };
 
             let keep = if let Some(polys) = regions {
@@ -219,6 +513,45 @@ pub fn generate_tiles(
     Ok(tiles)
 }
 
+/// Generate tiles of `plan.tile_size` and resize them to the model input
+/// (`config.tile_size`) so every tile reaches the detector at the same size.
+pub fn generate_planned_tiles(
+    image: &Mat,
+    config: &SliceConfig,
+    plan: TilePlan,
+    regions: Option<&[Vec<Point>]>,
+) -> Result<Vec<Tile>> {
+    let planned = SliceConfig {
+        tile_size: plan.tile_size,
+        ..config.clone()
+    };
+    let mut tiles = generate_tiles(image, &planned, regions)?;
+    if plan.tile_size == config.tile_size {
+        return Ok(tiles);
+    }
+
+    let input = config.tile_size as i32;
+    let interpolation = if plan.scale > 1.0 {
+        opencv::imgproc::INTER_CUBIC
+    } else {
+        opencv::imgproc::INTER_AREA
+    };
+    for tile in &mut tiles {
+        let mut resized = Mat::default();
+        opencv::imgproc::resize(
+            &tile.image,
+            &mut resized,
+            opencv::core::Size::new(input, input),
+            0.0,
+            0.0,
+            interpolation,
+        )?;
+        tile.image = resized;
+        tile.scale = plan.scale;
+    }
+    Ok(tiles)
+}
+
 /// Check if a tile overlaps with a polygon
 fn is_tile_overlapping_polygon(tile: &Tile, poly: &[Point]) -> bool {
     if poly.is_empty() {
@@ -337,44 +670,100 @@ pub fn transform_detection_to_image_coords(detection: &usls::Hbb, tile: &Tile) -
 /// Apply Non-Maximum Suppression to remove duplicate detections
 /// Generic version that works with any type implementing BoundingBox
 /// Returns (filtered detections, NMS statistics)
-pub fn nms<T: BoundingBox + Clone>(
+pub fn nms<T: BoundingBox + Clone>(detections: Vec<T>, iou_threshold: f32) -> (Vec<T>, NmsStats) {
+    greedy_nms(
+        detections,
+        iou_threshold,
+        NmsStrategy::Hard,
+        compute_iou_bbox,
+    )
+}
+
+/// Resolve overlapping detections with the given strategy
+/// Returns (filtered detections, NMS statistics)
+pub fn suppress<T: AdjustableBoundingBox>(
     detections: Vec<T>,
     iou_threshold: f32,
-) -> (Vec<T>, crate::pipeline::types::NmsStats) {
+    strategy: NmsStrategy,
+) -> (Vec<T>, NmsStats) {
+    match strategy {
+        NmsStrategy::Hard => nms(detections, iou_threshold),
+        NmsStrategy::Diou => greedy_nms(detections, iou_threshold, strategy, compute_diou_bbox),
+        NmsStrategy::SoftGaussian {
+            sigma,
+            score_threshold,
+        } => soft_nms(detections, sigma, score_threshold),
+        NmsStrategy::Wbf => weighted_box_fusion(detections, iou_threshold),
+    }
+}
+
+fn empty_stats(strategy: NmsStrategy) -> NmsStats {
+    NmsStats {
+        original_count: 0,
+        suppressed_count: 0,
+        close_but_kept_count: 0,
+        kept_count: 0,
+        strategy: strategy.name().to_string(),
+        decayed_count: 0,
+        fused_count: 0,
+    }
+}
+
+/// Sort by confidence (highest first)
+fn sort_by_confidence<T: BoundingBox>(detections: &mut [T]) {
+    detections.sort_by(|a, b| {
+        let conf_a = a.confidence();
+        let conf_b = b.confidence();
+        conf_b
+            .partial_cmp(&conf_a)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Detection count above which `greedy_nms` only compares boxes sharing a grid cell.
+/// Below it, comparing every pair is faster (see the `nms` group in
+/// `ultimate-event-detection/benches/spatial.rs`).
+const GRID_NMS_MIN_DETECTIONS: usize = 200;
+
+/// Greedy suppression shared by hard NMS and DIoU-NMS; `overlap` is IoU or DIoU
+///
+/// With a non-negative threshold only touching boxes can be suppressed (DIoU never
+/// exceeds IoU), so large inputs visit grid neighbours instead of every pair.
+fn greedy_nms<T: BoundingBox + Clone>(
+    detections: Vec<T>,
+    threshold: f32,
+    strategy: NmsStrategy,
+    overlap: fn(&T, &T) -> f32,
+) -> (Vec<T>, NmsStats) {
     if detections.is_empty() {
-        return (
-            detections,
-            crate::pipeline::types::NmsStats {
-                original_count: 0,
-                suppressed_count: 0,
-                close_but_kept_count: 0,
-                kept_count: 0,
-            },
-        );
+        return (detections, empty_stats(strategy));
     }
 
     tracing::debug!(
-        "NMS: Processing {} detections with IoU threshold {}",
+        "NMS ({}): Processing {} detections with threshold {}",
+        strategy.name(),
         detections.len(),
-        iou_threshold
+        threshold
     );
 
     let original_count = detections.len();
 
-    // Sort by confidence (highest first)
-    let mut sorted: Vec<_> = detections.into_iter().collect();
-    sorted.sort_by(|a, b| {
-        let conf_a = a.confidence();
-        let conf_b = b.confidence();
-        conf_b
-            .partial_cmp(&conf_a)
-            .unwrap_or(std::cmp::Ordering::Equal)
+    let mut sorted = detections;
+    sort_by_confidence(&mut sorted);
+
+    let grid = (sorted.len() >= GRID_NMS_MIN_DETECTIONS && threshold >= 0.0).then(|| {
+        let boxes: Vec<Aabb> = sorted
+            .iter()
+            .map(|d| Aabb::from_xywh(d.xmin(), d.ymin(), d.width(), d.height()))
+            .collect();
+        BoxGrid::new(&boxes)
     });
 
     let mut keep = Vec::new();
     let mut suppressed = vec![false; sorted.len()];
     let mut suppression_count = 0;
     let mut close_but_kept_count = 0;
+    let mut candidates = Vec::new();
 
     for i in 0..sorted.len() {
         if suppressed[i] {
@@ -383,35 +772,43 @@ pub fn nms<T: BoundingBox + Clone>(
 
         keep.push(sorted[i].clone());
 
-        for j in (i + 1)..sorted.len() {
-            if suppressed[j] {
+        match &grid {
+            Some(grid) => grid.neighbours(i, &mut candidates),
+            None => {
+                candidates.clear();
+                candidates.extend((i + 1)..sorted.len());
+            }
+        }
+
+        for &j in &candidates {
+            if j <= i || suppressed[j] {
                 continue;
             }
 
-            let iou = compute_iou_bbox(&sorted[i], &sorted[j]);
-            if iou > iou_threshold {
+            let score = overlap(&sorted[i], &sorted[j]);
+            if score > threshold {
                 suppressed[j] = true;
                 suppression_count += 1;
 
                 // Log high-overlap pairs that are being suppressed
-                if iou > 0.7 {
+                if score > 0.7 {
                     tracing::debug!(
-                        "NMS: Suppressing detection {} (conf={:.2}) with IoU={:.3} vs detection {} (conf={:.2})",
+                        "NMS: Suppressing detection {} (conf={:.2}) with overlap={:.3} vs detection {} (conf={:.2})",
                         j,
                         sorted[j].confidence(),
-                        iou,
+                        score,
                         i,
                         sorted[i].confidence()
                     );
                 }
-            } else if iou > 0.3 {
+            } else if score > 0.3 {
                 // Log moderate-overlap pairs that are NOT being suppressed
                 close_but_kept_count += 1;
                 tracing::trace!(
-                    "NMS: NOT suppressing detection {} (conf={:.2}) with IoU={:.3} vs detection {} (conf={:.2}) - BELOW THRESHOLD",
+                    "NMS: NOT suppressing detection {} (conf={:.2}) with overlap={:.3} vs detection {} (conf={:.2}) - BELOW THRESHOLD",
                     j,
                     sorted[j].confidence(),
-                    iou,
+                    score,
                     i,
                     sorted[i].confidence()
                 );
@@ -435,30 +832,254 @@ pub fn nms<T: BoundingBox + Clone>(
 
     (
         keep,
-        crate::pipeline::types::NmsStats {
+        NmsStats {
             original_count,
             suppressed_count: suppression_count,
             close_but_kept_count,
             kept_count,
+            ..empty_stats(strategy)
         },
     )
 }
 
+/// Gaussian Soft-NMS (Bodla et al., 2017)
+/// Repeatedly keeps the highest-scoring box and decays the scores of the rest by
+/// `exp(-iou² / sigma)`; boxes whose score falls below `score_threshold` are dropped.
+/// The decayed score only decides what is dropped: kept boxes keep their detector
+/// confidence, so a player packed next to a teammate still passes the count gate.
+fn soft_nms<T: AdjustableBoundingBox>(
+    detections: Vec<T>,
+    sigma: f32,
+    score_threshold: f32,
+) -> (Vec<T>, NmsStats) {
+    let strategy = NmsStrategy::SoftGaussian {
+        sigma,
+        score_threshold,
+    };
+    if detections.is_empty() {
+        return (detections, empty_stats(strategy));
+    }
+
+    let original_count = detections.len();
+    let sigma = sigma.max(f32::EPSILON);
+    let mut scores: Vec<f32> = detections.iter().map(|d| d.confidence()).collect();
+    let mut remaining: Vec<usize> = (0..detections.len())
+        .filter(|&i| scores[i] >= score_threshold)
+        .collect();
+
+    let mut suppressed_count = original_count - remaining.len();
+    let mut close_but_kept_count = 0;
+    let mut order = Vec::new();
+
+    while !remaining.is_empty() {
+        // Highest current score; ties go to the earlier detection
+        let mut best_pos = 0;
+        for (pos, &idx) in remaining.iter().enumerate() {
+            if scores[idx] > scores[remaining[best_pos]] {
+                best_pos = pos;
+            }
+        }
+        let best = remaining.remove(best_pos);
+        order.push(best);
+
+        remaining.retain(|&j| {
+            let iou = compute_iou_bbox(&detections[best], &detections[j]);
+            if iou > 0.0 {
+                scores[j] *= (-(iou * iou) / sigma).exp();
+            }
+            if scores[j] < score_threshold {
+                suppressed_count += 1;
+                false
+            } else {
+                if iou > 0.3 {
+                    close_but_kept_count += 1;
+                }
+                true
+            }
+        });
+    }
+
+    let decayed_count = order
+        .iter()
+        .filter(|&&i| scores[i] < detections[i].confidence())
+        .count();
+    let keep: Vec<T> = order.into_iter().map(|i| detections[i].clone()).collect();
+
+    tracing::debug!(
+        "Soft-NMS: Kept {} of {} detections ({} decayed), dropped {}",
+        keep.len(),
+        original_count,
+        decayed_count,
+        suppressed_count
+    );
+
+    let kept_count = keep.len();
+    (
+        keep,
+        NmsStats {
+            original_count,
+            suppressed_count,
+            close_but_kept_count,
+            kept_count,
+            decayed_count,
+            ..empty_stats(strategy)
+        },
+    )
+}
+
+/// A WBF cluster: member indices into the sorted detections and the fused box
+struct FusionCluster {
+    members: Vec<usize>,
+    fused: [f32; 4],
+}
+
+/// Weighted Boxes Fusion (Solovyev et al., 2021)
+/// Each detection joins the cluster whose fused box it overlaps most above
+/// `iou_threshold`, or starts a new one. A cluster's box and confidence are the
+/// confidence-weighted averages of its members' corners and confidences, so a weak
+/// duplicate barely lowers a confident detection. Class and other attributes come
+/// from the most confident member.
+fn weighted_box_fusion<T: AdjustableBoundingBox>(
+    detections: Vec<T>,
+    iou_threshold: f32,
+) -> (Vec<T>, NmsStats) {
+    if detections.is_empty() {
+        return (detections, empty_stats(NmsStrategy::Wbf));
+    }
+
+    let original_count = detections.len();
+    let mut sorted = detections;
+    sort_by_confidence(&mut sorted);
+
+    let mut clusters: Vec<FusionCluster> = Vec::new();
+    let mut close_but_kept_count = 0;
+
+    for (i, det) in sorted.iter().enumerate() {
+        let corners = xyxy(det);
+        let mut best: Option<(usize, f32)> = None;
+        let mut max_iou = 0.0f32;
+        for (c, cluster) in clusters.iter().enumerate() {
+            let iou = iou_xyxy(&cluster.fused, &corners);
+            max_iou = max_iou.max(iou);
+            if iou > iou_threshold && best.is_none_or(|(_, b)| iou > b) {
+                best = Some((c, iou));
+            }
+        }
+
+        match best {
+            Some((c, _)) => {
+                let cluster = &mut clusters[c];
+                cluster.members.push(i);
+                cluster.fused = fuse_corners(&sorted, &cluster.members);
+            }
+            None => {
+                if max_iou > 0.3 {
+                    close_but_kept_count += 1;
+                }
+                clusters.push(FusionCluster {
+                    members: vec![i],
+                    fused: corners,
+                });
+            }
+        }
+    }
+
+    let mut fused_count = 0;
+    let keep: Vec<T> = clusters
+        .iter()
+        .map(|cluster| {
+            let head = &sorted[cluster.members[0]];
+            if cluster.members.len() == 1 {
+                return head.clone();
+            }
+            fused_count += 1;
+            let confidence = fuse_confidence(&sorted, &cluster.members);
+            let [x1, y1, x2, y2] = cluster.fused;
+            head.adjusted(x1, y1, x2 - x1, y2 - y1, confidence)
+        })
+        .collect();
+
+    let kept_count = keep.len();
+    tracing::debug!(
+        "WBF: Fused {} detections into {} ({} from multiple boxes)",
+        original_count,
+        kept_count,
+        fused_count
+    );
+
+    (
+        keep,
+        NmsStats {
+            original_count,
+            suppressed_count: original_count - kept_count,
+            close_but_kept_count,
+            kept_count,
+            fused_count,
+            ..empty_stats(NmsStrategy::Wbf)
+        },
+    )
+}
+
+/// Confidence-weighted average of the members' corners (plain average if all
+/// confidences are zero)
+fn fuse_corners<T: BoundingBox>(detections: &[T], members: &[usize]) -> [f32; 4] {
+    let total: f32 = members.iter().map(|&m| detections[m].confidence()).sum();
+    let mut fused = [0.0f32; 4];
+    for &m in members {
+        let weight = if total > 0.0 {
+            detections[m].confidence() / total
+        } else {
+            1.0 / members.len() as f32
+        };
+        for (f, c) in fused.iter_mut().zip(xyxy(&detections[m])) {
+            *f += weight * c;
+        }
+    }
+    fused
+}
+
+/// Confidence-weighted average of the members' confidences
+fn fuse_confidence<T: BoundingBox>(detections: &[T], members: &[usize]) -> f32 {
+    let total: f32 = members.iter().map(|&m| detections[m].confidence()).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    members
+        .iter()
+        .map(|&m| detections[m].confidence().powi(2))
+        .sum::<f32>()
+        / total
+}
+
+fn xyxy<T: BoundingBox>(b: &T) -> [f32; 4] {
+    [
+        b.xmin(),
+        b.ymin(),
+        b.xmin() + b.width(),
+        b.ymin() + b.height(),
+    ]
+}
+
 /// Compute Intersection over Union between two bounding boxes
 /// Generic version that works with any type implementing BoundingBox
 fn compute_iou_bbox<T: BoundingBox>(a: &T, b: &T) -> f32 {
-    let x1 = a.xmin().max(b.xmin());
-    let y1 = a.ymin().max(b.ymin());
-    let x2 = (a.xmin() + a.width()).min(b.xmin() + b.width());
-    let y2 = (a.ymin() + a.height()).min(b.ymin() + b.height());
+    iou_xyxy(&xyxy(a), &xyxy(b))
+}
+
+/// Intersection over Union of two `[x1, y1, x2, y2]` boxes
+fn iou_xyxy(a: &[f32; 4], b: &[f32; 4]) -> f32 {
+    let x1 = a[0].max(b[0]);
+    let y1 = a[1].max(b[1]);
+    let x2 = a[2].min(b[2]);
+    let y2 = a[3].min(b[3]);
 
     if x2 <= x1 || y2 <= y1 {
         return 0.0;
     }
 
     let intersection = (x2 - x1) * (y2 - y1);
-    let area_a = a.width() * a.height();
-    let area_b = b.width() * b.height();
+    let area_a = (a[2] - a[0]) * (a[3] - a[1]);
+    let area_b = (b[2] - b[0]) * (b[3] - b[1]);
     let union = area_a + area_b - intersection;
 
     if union <= 0.0 {
@@ -469,6 +1090,26 @@ fn compute_iou_bbox<T: BoundingBox>(a: &T, b: &T) -> f32 {
     }
 }
 
+/// Distance-IoU (Zheng et al., 2020): IoU minus the squared distance between the
+/// box centres over the squared diagonal of the smallest enclosing box
+fn compute_diou_bbox<T: BoundingBox>(a: &T, b: &T) -> f32 {
+    let [ax1, ay1, ax2, ay2] = xyxy(a);
+    let [bx1, by1, bx2, by2] = xyxy(b);
+    let iou = iou_xyxy(&[ax1, ay1, ax2, ay2], &[bx1, by1, bx2, by2]);
+
+    let dx = (ax1 + ax2 - bx1 - bx2) / 2.0;
+    let dy = (ay1 + ay2 - by1 - by2) / 2.0;
+    let cw = ax2.max(bx2) - ax1.min(bx1);
+    let ch = ay2.max(by2) - ay1.min(by1);
+    let diagonal = cw * cw + ch * ch;
+
+    if diagonal <= 0.0 {
+        iou
+    } else {
+        iou - (dx * dx + dy * dy) / diagonal
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -482,6 +1123,7 @@ mod tests {
             y_offset: 100,
             original_width: 100,
             original_height: 100,
+            scale: 1.0,
         };
 
         // 1. Polygon entirely inside tile
@@ -582,4 +1224,302 @@ mod tests {
         let (result, _stats) = nms(vec![d1, d2], 0.5);
         assert_eq!(result.len(), 1);
     }
+
+    fn player(x: f32, y: f32, w: f32, h: f32, conf: f32) -> HbbWrapper {
+        HbbWrapper(
+            usls::Hbb::default()
+                .with_xyxy(x, y, x + w, y + h)
+                .with_confidence(conf)
+                .with_id(0),
+        )
+    }
+
+    /// Seven players lined up in an end zone, mostly in shoulder-to-shoulder pairs:
+    /// 40px wide boxes 12px apart, so partners overlap with IoU ~0.54
+    fn tight_line_up() -> Vec<HbbWrapper> {
+        (0..7)
+            .map(|i| {
+                let x = 100.0 + 80.0 * (i / 2) as f32 + 12.0 * (i % 2) as f32;
+                player(x, 50.0, 40.0, 100.0, 0.9 - 0.02 * i as f32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hard_nms_drops_packed_line_up() {
+        let (result, stats) = suppress(tight_line_up(), 0.5, NmsStrategy::Hard);
+        assert_eq!(result.len(), 4);
+        assert_eq!(stats.strategy, "hard");
+        assert_eq!(stats.kept_count + stats.suppressed_count, 7);
+    }
+
+    #[test]
+    fn test_soft_nms_keeps_packed_line_up() {
+        let (result, stats) = suppress(tight_line_up(), 0.5, NmsStrategy::soft_gaussian());
+        assert_eq!(result.len(), 7);
+        assert_eq!(stats.strategy, "soft_gaussian");
+        assert_eq!(stats.suppressed_count, 0);
+        // The less confident partner of each pair was decayed, but keeps its
+        // confidence, so every player still passes the default 0.5 count gate
+        assert_eq!(stats.decayed_count, 3);
+        assert_eq!(result[0].confidence(), 0.9);
+        let mut confidences: Vec<f32> = result.iter().map(|d| d.confidence()).collect();
+        confidences.sort_by(|a, b| b.total_cmp(a));
+        let expected: Vec<f32> = tight_line_up().iter().map(|d| d.confidence()).collect();
+        assert_eq!(confidences, expected);
+    }
+
+    #[test]
+    fn test_soft_nms_removes_duplicates() {
+        // The same player detected twice, plus a neighbour
+        let detections = vec![
+            player(100.0, 50.0, 40.0, 100.0, 0.9),
+            player(102.0, 52.0, 40.0, 100.0, 0.8),
+            player(112.0, 50.0, 40.0, 100.0, 0.85),
+        ];
+        let (result, stats) = suppress(detections, 0.5, NmsStrategy::soft_gaussian());
+        assert_eq!(result.len(), 2);
+        assert_eq!(stats.suppressed_count, 1);
+        assert_eq!(stats.decayed_count, 1);
+    }
+
+    #[test]
+    fn test_diou_nms_keeps_side_by_side_players() {
+        // Neighbours 13px apart: IoU ~0.51, DIoU ~0.47. The last box duplicates the first.
+        let detections = vec![
+            player(100.0, 50.0, 40.0, 40.0, 0.9),
+            player(113.0, 50.0, 40.0, 40.0, 0.85),
+            player(101.0, 51.0, 40.0, 40.0, 0.6),
+        ];
+        let (hard, _) = suppress(detections.clone(), 0.5, NmsStrategy::Hard);
+        assert_eq!(hard.len(), 1);
+
+        let (result, stats) = suppress(detections, 0.5, NmsStrategy::Diou);
+        assert_eq!(result.len(), 2);
+        assert_eq!(stats.strategy, "diou");
+        assert_eq!(stats.suppressed_count, 1);
+    }
+
+    #[test]
+    fn test_wbf_fuses_overview_and_end_zone_views() {
+        // One player seen by the overview (0.6) and the EZ crop (0.9), and a second player
+        let detections = vec![
+            player(100.0, 50.0, 40.0, 100.0, 0.6),
+            player(104.0, 54.0, 40.0, 100.0, 0.9),
+            player(300.0, 50.0, 40.0, 100.0, 0.8),
+        ];
+        let (result, stats) = suppress(detections, 0.5, NmsStrategy::Wbf);
+        assert_eq!(result.len(), 2);
+        assert_eq!(stats.strategy, "wbf");
+        assert_eq!(stats.fused_count, 1);
+        assert_eq!(stats.suppressed_count, 1);
+
+        // Corners and confidence are weighted towards the more confident EZ box
+        let fused = &result[0];
+        assert!((fused.xmin() - 102.4).abs() < 1e-3);
+        assert!((fused.ymin() - 52.4).abs() < 1e-3);
+        assert!((fused.width() - 40.0).abs() < 1e-3);
+        assert!((fused.confidence() - 0.78).abs() < 1e-6);
+        assert_eq!(fused.0.id(), Some(0));
+    }
+
+    #[test]
+    fn test_strategies_account_for_every_detection() {
+        // Two staggered rows of a crowded line-up, with some duplicate detections
+        let mut detections = Vec::new();
+        for row in 0..2 {
+            for i in 0..8 {
+                let x = 50.0 + 15.0 * i as f32 + 7.0 * row as f32;
+                let y = 40.0 + 30.0 * row as f32;
+                detections.push(player(
+                    x,
+                    y,
+                    40.0,
+                    100.0,
+                    0.55 + 0.05 * ((i * 3 + row) % 8) as f32,
+                ));
+                if i % 3 == 0 {
+                    detections.push(player(x + 2.0, y + 1.0, 40.0, 100.0, 0.5));
+                }
+            }
+        }
+        let n = detections.len();
+
+        for strategy in [
+            NmsStrategy::Hard,
+            NmsStrategy::soft_gaussian(),
+            NmsStrategy::Diou,
+            NmsStrategy::Wbf,
+        ] {
+            let (result, stats) = suppress(detections.clone(), 0.5, strategy);
+            assert_eq!(stats.original_count, n);
+            assert_eq!(stats.kept_count, result.len());
+            assert_eq!(
+                stats.kept_count + stats.suppressed_count,
+                n,
+                "{}",
+                strategy.name()
+            );
+            assert_eq!(stats.strategy, strategy.name());
+        }
+    }
+
+    #[test]
+    fn test_grid_nms_matches_all_pairs() {
+        // A tiled frame full of line-ups, large enough to take the grid path
+        let mut detections = Vec::new();
+        for group in 0..40 {
+            let (gx, gy) = ((group % 8) as f32 * 230.0, (group / 8) as f32 * 210.0);
+            for p in 0..7 {
+                for dup in 0..2 {
+                    let x = gx + 22.0 * p as f32 + 3.0 * dup as f32;
+                    let conf = 0.5 + 0.07 * ((group * 7 + p * 3 + dup) % 7) as f32;
+                    detections.push(player(x, gy + 2.0 * dup as f32, 36.0, 90.0, conf));
+                }
+            }
+        }
+        assert!(detections.len() >= GRID_NMS_MIN_DETECTIONS);
+
+        for strategy in [NmsStrategy::Hard, NmsStrategy::Diou] {
+            let (grid, grid_stats) = suppress(detections.clone(), 0.5, strategy);
+
+            // Same greedy pass, one chunk at a time so every call stays below the grid cut-off
+            let mut expected_kept = 0;
+            let mut expected_close = 0;
+            for chunk in detections.chunks(7 * 2 * 8) {
+                let (kept, stats) = suppress(chunk.to_vec(), 0.5, strategy);
+                expected_kept += kept.len();
+                expected_close += stats.close_but_kept_count;
+            }
+            assert_eq!(grid.len(), expected_kept, "{}", strategy.name());
+            assert_eq!(grid_stats.close_but_kept_count, expected_close);
+        }
+    }
+
+    #[test]
+    fn test_nms_strategy_config() {
+        let config: SliceConfig =
+            serde_json::from_str(r#"{"tile_size": 640, "overlap": 0.2, "nms_iou_threshold": 0.5}"#)
+                .unwrap();
+        assert_eq!(config.nms_strategy, NmsStrategy::Hard);
+        assert_eq!(config.merge_strategy, NmsStrategy::Hard);
+
+        let nms: NmsConfig =
+            serde_json::from_str(r#"{"merge_strategy": {"kind": "wbf"}}"#).unwrap();
+        assert_eq!(nms.strategy, NmsStrategy::Hard);
+        assert_eq!(nms.merge_strategy, NmsStrategy::Wbf);
+
+        let strategy: NmsStrategy = serde_json::from_str(r#"{"kind": "soft_gaussian"}"#).unwrap();
+        assert_eq!(strategy, NmsStrategy::soft_gaussian());
+        assert_eq!("WBF".parse::<NmsStrategy>(), Ok(NmsStrategy::Wbf));
+        assert!("fuzzy".parse::<NmsStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_adaptive_tiling_plan() {
+        let policy = TilingPolicy::adaptive();
+
+        // Far end zone: 20 px players need 3.2x zoom, capped at 2x by the 320 px minimum
+        let far = policy.plan(640, Some(20.0));
+        assert_eq!(far.tile_size, 320);
+        assert_eq!(far.scale, 2.0);
+
+        // Mid field: 40 px players -> 400 px, rounded to 416 and upscaled to 640
+        let mid = policy.plan(640, Some(40.0));
+        assert_eq!(mid.tile_size, 416);
+        assert!((mid.scale - 640.0 / 416.0).abs() < 1e-6);
+
+        // Near end zone: 160 px players -> tiles capped at 1280, downscaled by half
+        let near = policy.plan(640, Some(160.0));
+        assert_eq!(near.tile_size, 1280);
+        assert_eq!(near.scale, 0.5);
+
+        // No estimate yet, or a fixed policy: plain model-size tiles
+        let unscaled = TilePlan {
+            tile_size: 640,
+            scale: 1.0,
+        };
+        assert_eq!(policy.plan(640, None), unscaled);
+        assert_eq!(policy.plan(640, Some(f32::NAN)), unscaled);
+        assert_eq!(TilingPolicy::Fixed.plan(640, Some(20.0)), unscaled);
+
+        // Without upscaling tiles never shrink below the model input
+        let no_upscale = TilingPolicy::Adaptive {
+            target_player_px: 64.0,
+            min_tile_size: 320,
+            max_tile_size: 1280,
+            upscale: false,
+        };
+        assert_eq!(no_upscale.plan(640, Some(20.0)), unscaled);
+        assert_eq!(no_upscale.plan(640, Some(96.0)).tile_size, 960);
+    }
+
+    #[test]
+    fn test_player_height_prior() {
+        // 700 x 350 px end zone -> sqrt(245000) ~ 495 px -> ~35 px players
+        let end_zone = vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 700.0, y: 0.0 },
+            Point { x: 700.0, y: 350.0 },
+            Point { x: 0.0, y: 350.0 },
+        ];
+        let prior = player_height_prior(&end_zone, true).unwrap();
+        assert!((prior - 34.65).abs() < 0.1, "{}", prior);
+
+        // The same area as a whole field holds players less than half as tall
+        let field = player_height_prior(&end_zone, false).unwrap();
+        assert!(field < prior / 2.0);
+
+        assert_eq!(player_height_prior(&end_zone[..2], true), None);
+    }
+
+    #[test]
+    fn test_player_scale_tracker() {
+        let mut tracker = PlayerScaleTracker::new(5, 3);
+        tracker.record("left", [30.0, 32.0]);
+        assert_eq!(tracker.median("left"), None);
+
+        tracker.record("left", [34.0, f32::NAN, 0.0]);
+        assert_eq!(tracker.median("left"), Some(32.0));
+
+        // Only the last five heights count
+        tracker.record("left", [90.0, 92.0, 94.0]);
+        assert_eq!(tracker.median("left"), Some(90.0));
+        assert_eq!(tracker.median("right"), None);
+    }
+
+    #[test]
+    fn test_tiling_policy_config() {
+        let policy: TilingPolicy = serde_json::from_str(r#"{"kind": "adaptive"}"#).unwrap();
+        assert_eq!(policy, TilingPolicy::adaptive());
+
+        let policy: TilingPolicy =
+            serde_json::from_str(r#"{"kind": "adaptive", "upscale": false}"#).unwrap();
+        assert!(matches!(
+            policy,
+            TilingPolicy::Adaptive { upscale: false, .. }
+        ));
+        assert_eq!(TilingPolicy::default(), TilingPolicy::Fixed);
+    }
+
+    #[test]
+    fn test_generate_planned_tiles_resizes_to_input() {
+        let config = SliceConfig::new(100, 0.0);
+        let image =
+            Mat::new_rows_cols_with_default(300, 300, opencv::core::CV_8UC3, Scalar::all(0.0))
+                .unwrap();
+
+        // 50 px tiles zoomed 2x: 6x6 tiles, each 100 x 100 for the model
+        let plan = TilePlan {
+            tile_size: 50,
+            scale: 2.0,
+        };
+        let tiles = generate_planned_tiles(&image, &config, plan, None).unwrap();
+        assert_eq!(tiles.len(), 36);
+        for tile in &tiles {
+            assert_eq!((tile.image.cols(), tile.image.rows()), (100, 100));
+            assert_eq!(tile.scale, 2.0);
+        }
+        assert_eq!(tiles[1].x_offset, 50);
+    }
 }
@@ -1,9 +1,12 @@
 use crate::pipeline::types::BBox as PipelineBBox;
+use crate::pipeline::types::{CropConfig, RegionalPolygon};
 use crate::run_artifacts::{BBox, Point};
+use crate::video::lens::LensProfile;
 use geo::BooleanOps;
 use geo::Contains;
 use geo_buffer::buffer_polygon;
 use geo_types::{LineString, MultiPolygon, Point as GeoPoint, Polygon};
+use ultimate_event_detection::PreparedPolygon;
 
 /// Convert our pipeline points to a geo_types Polygon
 fn to_geo_polygon(points: &[Point]) -> Polygon<f64> {
@@ -130,6 +133,98 @@ pub fn is_point_in_polygon_robust(x: f32, y: f32, polygon: &[Point]) -> bool {
     poly.contains(&point)
 }
 
+/// A named region polygon prepared for repeated point-in-polygon tests
+pub struct PreparedRegion {
+    pub name: String,
+    polygon: PreparedPolygon,
+}
+
+/// Name of the overview regions holding user-drawn exclusion polygons.
+pub const EXCLUSION_REGION: &str = "excluded";
+
+/// Overview sub-regions (left, right, field) prepared for classifying detections.
+///
+/// `from_crop_configs` prepares them once per run in global normalized
+/// coordinates, independent of the overview crop's pixel size; `from_local`
+/// prepares a single crop's pixel-space regions. Regions are tested in order
+/// and the first match wins. Exclusion polygons are kept apart from them.
+pub struct PreparedRegions {
+    regions: Vec<PreparedRegion>,
+    exclusions: Vec<PreparedPolygon>,
+    /// Overview crop bbox when the polygons are in global normalized coordinates
+    global_bbox: Option<PipelineBBox>,
+}
+
+impl PreparedRegions {
+    /// Prepare the overview crop's regions from the run's crop configs, or `None`
+    /// if there is no overview crop.
+    pub fn from_crop_configs(configs: &[CropConfig]) -> Option<Self> {
+        let overview = configs.iter().find(|c| c.suffix == "overview")?;
+        Some(Self::prepare(&overview.regions, Some(overview.bbox)))
+    }
+
+    /// Prepare regions already in crop-local pixel coordinates.
+    pub fn from_local(regions: &[RegionalPolygon]) -> Self {
+        Self::prepare(regions, None)
+    }
+
+    fn prepare(regions: &[RegionalPolygon], global_bbox: Option<PipelineBBox>) -> Self {
+        let polygon = |r: &RegionalPolygon| {
+            PreparedPolygon::new(
+                &r.effective_polygon
+                    .iter()
+                    .map(|p| (p.x, p.y))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        Self {
+            regions: regions
+                .iter()
+                .filter(|r| r.name != EXCLUSION_REGION)
+                .map(|r| PreparedRegion {
+                    name: r.name.clone(),
+                    polygon: polygon(r),
+                })
+                .collect(),
+            exclusions: regions
+                .iter()
+                .filter(|r| r.name == EXCLUSION_REGION)
+                .map(polygon)
+                .collect(),
+            global_bbox,
+        }
+    }
+
+    /// Maps crop-local pixels to the space the polygons are stored in.
+    fn to_region_space(&self, x: f32, y: f32, crop_w: f32, crop_h: f32) -> (f32, f32) {
+        match &self.global_bbox {
+            // Inverse of `transform_polygon`
+            Some(bbox) => (
+                bbox.x + (x / crop_w) * bbox.w,
+                bbox.y + (y / crop_h) * bbox.h,
+            ),
+            None => (x, y),
+        }
+    }
+
+    /// Index of the first region strictly containing a point given in the
+    /// crop-local pixels of a `crop_w` x `crop_h` overview crop.
+    pub fn locate(&self, x: f32, y: f32, crop_w: f32, crop_h: f32) -> Option<usize> {
+        let (x, y) = self.to_region_space(x, y, crop_w, crop_h);
+        self.regions.iter().position(|r| r.polygon.contains(x, y))
+    }
+
+    /// True if the point lies inside a user-drawn exclusion polygon.
+    pub fn is_excluded(&self, x: f32, y: f32, crop_w: f32, crop_h: f32) -> bool {
+        let (x, y) = self.to_region_space(x, y, crop_w, crop_h);
+        self.exclusions.iter().any(|p| p.contains(x, y))
+    }
+
+    pub fn name(&self, index: usize) -> &str {
+        &self.regions[index].name
+    }
+}
+
 /// Transforms polygon coordinates from global to crop-local space.
 pub fn transform_polygon(poly: &[Point], bbox: &BBox, crop_w: f32, crop_h: f32) -> Vec<Point> {
     poly.iter()
@@ -141,6 +236,20 @@ pub fn transform_polygon(poly: &[Point], bbox: &BBox, crop_w: f32, crop_h: f32)
         .collect()
 }
 
+/// Maps polygon vertices clicked on raw frames into the lens profile's rectified
+/// frame. Only vertices move: field lines are straight in the rectified frame, so
+/// edges between corners stay straight there too. Returns `None` if a vertex falls
+/// outside the rectified view.
+pub fn rectify_polygon(poly: &[Point], lens: Option<&LensProfile>) -> Option<Vec<Point>> {
+    let lens = match lens {
+        Some(lens) => lens,
+        None => return Some(poly.to_vec()),
+    };
+    poly.iter()
+        .map(|p| lens.to_rectified(p.x, p.y).map(|(x, y)| Point { x, y }))
+        .collect()
+}
+
 /// Transform a detection from EZ crop pixel coordinates to overview pixel coordinates.
 ///
 /// Two-step coordinate transform:
@@ -222,6 +331,30 @@ mod tests {
     use super::*;
     use crate::run_artifacts::{BBox, Point};
 
+    #[test]
+    fn test_rectify_polygon() {
+        let poly = vec![Point { x: 0.2, y: 0.3 }, Point { x: 0.8, y: 0.7 }];
+        let unchanged = rectify_polygon(&poly, None).unwrap();
+        assert_eq!(unchanged[1].x, 0.8);
+
+        // A 90° view straight ahead of a panorama sees its central quarter
+        let lens = LensProfile::Equirectangular {
+            yaw_deg: 0.0,
+            pitch_deg: 0.0,
+            hfov_deg: 90.0,
+            width: 1000,
+            height: 1000,
+        };
+        let inside = vec![Point { x: 0.5, y: 0.5 }, Point { x: 0.6, y: 0.55 }];
+        let rectified = rectify_polygon(&inside, Some(&lens)).unwrap();
+        assert!((rectified[0].x - 0.5).abs() < 1e-5 && (rectified[0].y - 0.5).abs() < 1e-5);
+        assert!(rectified[1].x > 0.6 && rectified[1].y > 0.55);
+
+        // Behind the view
+        let behind = vec![Point { x: 0.5, y: 0.5 }, Point { x: 0.0, y: 0.5 }];
+        assert!(rectify_polygon(&behind, Some(&lens)).is_none());
+    }
+
     #[test]
     fn test_transform_polygon() {
         let bbox = BBox {
@@ -259,6 +392,88 @@ mod tests {
         assert!((transformed[2].y - 200.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn test_prepared_regions_global_matches_local() {
+        let square = |x: f32, y: f32, size: f32| {
+            vec![
+                Point { x, y },
+                Point { x: x + size, y },
+                Point {
+                    x: x + size,
+                    y: y + size,
+                },
+                Point { x, y: y + size },
+            ]
+        };
+        let region = |name: &str, polygon: Vec<Point>| RegionalPolygon {
+            name: name.to_string(),
+            polygon: polygon.clone(),
+            effective_polygon: polygon,
+        };
+        let bbox = PipelineBBox {
+            x: 0.1,
+            y: 0.2,
+            w: 0.8,
+            h: 0.6,
+        };
+        let config = CropConfig {
+            bbox,
+            original_polygon: Vec::new(),
+            effective_polygon: Vec::new(),
+            suffix: "overview".to_string(),
+            regions: vec![
+                region("left", square(0.15, 0.3, 0.2)),
+                region("right", square(0.65, 0.3, 0.2)),
+                region("field", square(0.1, 0.2, 0.8)),
+            ],
+        };
+        let (crop_w, crop_h) = (1600.0, 900.0);
+        let local_regions: Vec<RegionalPolygon> = config
+            .regions
+            .iter()
+            .map(|r| {
+                let local = transform_polygon(&r.effective_polygon, &bbox, crop_w, crop_h);
+                region(&r.name, local)
+            })
+            .collect();
+
+        let mut masked = config.clone();
+        masked
+            .regions
+            .push(region(EXCLUSION_REGION, square(0.1, 0.2, 0.1)));
+        let global = PreparedRegions::from_crop_configs(&[config]).unwrap();
+        let local = PreparedRegions::from_local(&local_regions);
+        for (x, y) in [(300.0, 300.0), (1300.0, 400.0), (800.0, 450.0), (5.0, 5.0)] {
+            assert_eq!(
+                global.locate(x, y, crop_w, crop_h),
+                local.locate(x, y, crop_w, crop_h)
+            );
+        }
+        assert_eq!(
+            global
+                .locate(300.0, 300.0, crop_w, crop_h)
+                .map(|i| global.name(i)),
+            Some("left")
+        );
+        assert_eq!(
+            global
+                .locate(800.0, 450.0, crop_w, crop_h)
+                .map(|i| global.name(i)),
+            Some("field")
+        );
+
+        // Exclusion polygons are never located, only tested
+        let masked = PreparedRegions::from_crop_configs(&[masked]).unwrap();
+        assert!(masked.is_excluded(50.0, 50.0, crop_w, crop_h));
+        assert!(!masked.is_excluded(800.0, 450.0, crop_w, crop_h));
+        assert_eq!(
+            masked
+                .locate(50.0, 50.0, crop_w, crop_h)
+                .map(|i| masked.name(i)),
+            Some("field")
+        );
+    }
+
     #[test]
     fn test_transform_ez_to_overview_identity() {
         // When EZ crop bbox == overview crop bbox, transform is identity
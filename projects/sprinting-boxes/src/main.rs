@@ -1,9 +1,13 @@
+mod catalogue;
 mod cli;
 mod config;
 mod detection;
 mod geometry;
 mod pipeline;
+mod registry;
+mod render;
 mod run_artifacts;
+mod run_bundle;
 mod run_context;
 mod scoring;
 mod video;
@@ -1,11 +1,15 @@
+use crate::pipeline::preprocess::apply_chain;
 use crate::pipeline::types::{
     BBox, CropConfig, CropData, FrameData, PreprocessedFrame, ProcessingState, RawFrame,
     RegionalPolygon,
 };
+use crate::run_artifacts::PreprocessConfig;
+use crate::video::lens::LensProfile;
 use anyhow::Result;
 use crossbeam::channel::{Receiver, Sender};
+use opencv::core;
+use opencv::imgproc;
 use opencv::prelude::*;
-use opencv::{core, imgproc};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -43,51 +47,95 @@ pub fn crop_normalized(img: &core::Mat, bbox: &BBox) -> Result<core::Mat> {
     Ok(out)
 }
 
-/// Apply CLAHE (Contrast Limited Adaptive Histogram Equalization) to enhance visibility
-/// of dark objects in shadows. This helps detect people in dark uniforms.
-fn enhance_crop(img: &core::Mat) -> Result<core::Mat> {
-    let mut lab = core::Mat::default();
-    imgproc::cvt_color(
-        img,
-        &mut lab,
-        imgproc::COLOR_BGR2Lab,
-        0,
-        core::AlgorithmHint::ALGO_HINT_DEFAULT,
-    )?;
+/// Remap tables cutting one crop out of the rectified frame, built for one
+/// source frame size.
+pub struct RectifyMaps {
+    frame_size: core::Size,
+    map_x: core::Mat,
+    map_y: core::Mat,
+}
 
-    let mut channels = core::Vector::<core::Mat>::new();
-    core::split(&lab, &mut channels)?;
+impl RectifyMaps {
+    pub fn new(lens: &LensProfile, bbox: &BBox, frame_size: core::Size) -> Result<Self> {
+        let (out_w, out_h) = lens.output_size(frame_size.width, frame_size.height);
+        let x0 = (bbox.x * out_w as f32).round();
+        let y0 = (bbox.y * out_h as f32).round();
+        let w = (bbox.w * out_w as f32).round() as i32;
+        let h = (bbox.h * out_h as f32).round() as i32;
+        if w <= 0 || h <= 0 {
+            anyhow::bail!("Invalid crop dimensions: {}x{} (bbox: {:?})", w, h, bbox);
+        }
 
-    let mut clahe = imgproc::create_clahe(2.0, core::Size::new(8, 8))?;
-    let mut l_enhanced = core::Mat::default();
-    clahe.apply(&channels.get(0)?, &mut l_enhanced)?;
+        // Sample at pixel centers; points the lens never sees map off-frame
+        let mut map_x = Vec::with_capacity((w * h) as usize);
+        let mut map_y = Vec::with_capacity((w * h) as usize);
+        for row in 0..h {
+            for col in 0..w {
+                let x = (x0 + col as f32 + 0.5) / out_w as f32;
+                let y = (y0 + row as f32 + 0.5) / out_h as f32;
+                let (sx, sy) = lens.to_source(x, y).unwrap_or((-1.0, -1.0));
+                map_x.push(sx * frame_size.width as f32 - 0.5);
+                map_y.push(sy * frame_size.height as f32 - 0.5);
+            }
+        }
 
-    channels.set(0, l_enhanced)?;
+        Ok(Self {
+            frame_size,
+            map_x: core::Mat::new_rows_cols_with_data(h, w, &map_x)?.try_clone()?,
+            map_y: core::Mat::new_rows_cols_with_data(h, w, &map_y)?.try_clone()?,
+        })
+    }
+}
 
-    let mut lab_enhanced = core::Mat::default();
-    core::merge(&channels, &mut lab_enhanced)?;
+/// Crops a normalized bounding box out of the frame, rectified through the lens
+/// profile if there is one. `maps` caches the remap tables between frames.
+pub fn crop_frame(
+    img: &core::Mat,
+    bbox: &BBox,
+    lens: Option<&LensProfile>,
+    maps: &mut Option<RectifyMaps>,
+) -> Result<core::Mat> {
+    let lens = match lens {
+        Some(lens) => lens,
+        None => return crop_normalized(img, bbox),
+    };
+
+    let frame_size = img.size()?;
+    if maps.as_ref().map(|m| m.frame_size) != Some(frame_size) {
+        *maps = Some(RectifyMaps::new(lens, bbox, frame_size)?);
+    }
+    let maps = maps.as_ref().unwrap();
 
-    let mut result = core::Mat::default();
-    imgproc::cvt_color(
-        &lab_enhanced,
-        &mut result,
-        imgproc::COLOR_Lab2BGR,
-        0,
-        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+    let border = if lens.wraps() {
+        core::BORDER_WRAP
+    } else {
+        core::BORDER_CONSTANT
+    };
+    let mut out = core::Mat::default();
+    imgproc::remap(
+        img,
+        &mut out,
+        &maps.map_x,
+        &maps.map_y,
+        imgproc::INTER_LINEAR,
+        border,
+        core::Scalar::default(),
     )?;
-
-    Ok(result)
+    Ok(out)
 }
 
-/// Crop worker: receives raw frames, extracts configured regions, applies enhancements.
+/// Crop worker: receives raw frames, extracts configured regions, runs each crop's
+/// preprocessing chain. With a lens profile, crops are cut from the rectified frame.
 pub fn crop_worker(
     rx: Receiver<RawFrame>,
     tx: Sender<PreprocessedFrame>,
     configs: Arc<Vec<CropConfig>>,
-    enable_clahe: bool,
+    preprocess: Arc<PreprocessConfig>,
+    lens: Option<LensProfile>,
     state: Arc<ProcessingState>,
     target_count: Arc<std::sync::atomic::AtomicUsize>,
 ) -> Result<()> {
+    let mut rectify_maps: Vec<Option<RectifyMaps>> = configs.iter().map(|_| None).collect();
     for frame in rx {
         // Dynamic scaling check
         let current_target = target_count.load(std::sync::atomic::Ordering::Relaxed);
@@ -113,26 +161,29 @@ pub fn crop_worker(
         let FrameData::Mat(mat) = &frame.data;
 
         if !mat.empty() {
-            for config in configs.iter() {
-                let mut crop = crop_normalized(mat, &config.bbox)?;
-
+            for (config, maps) in configs.iter().zip(rectify_maps.iter_mut()) {
+                let content = crop_frame(mat, &config.bbox, lens.as_ref(), maps)?;
+                let (crop, letterbox) =
+                    apply_chain(&content, preprocess.steps_for(&config.suffix))?;
+
+                // A letterboxed crop spans more of the frame than its content
+                let bbox = match letterbox {
+                    Some(letterbox) => letterbox.expand(&config.bbox),
+                    None => config.bbox,
+                };
                 let crop_size = crop.size()?;
                 let crop_w = crop_size.width as f32;
                 let crop_h = crop_size.height as f32;
 
-                if enable_clahe {
-                    crop = enhance_crop(&crop)?;
-                }
-
                 let original_poly_local = crate::geometry::transform_polygon(
                     &config.original_polygon,
-                    &config.bbox,
+                    &bbox,
                     crop_w,
                     crop_h,
                 );
                 let effective_poly_local = crate::geometry::transform_polygon(
                     &config.effective_polygon,
-                    &config.bbox,
+                    &bbox,
                     crop_w,
                     crop_h,
                 );
@@ -143,14 +194,11 @@ pub fn crop_worker(
                     .map(|r| RegionalPolygon {
                         name: r.name.clone(),
                         polygon: crate::geometry::transform_polygon(
-                            &r.polygon,
-                            &config.bbox,
-                            crop_w,
-                            crop_h,
+                            &r.polygon, &bbox, crop_w, crop_h,
                         ),
                         effective_polygon: crate::geometry::transform_polygon(
                             &r.effective_polygon,
-                            &config.bbox,
+                            &bbox,
                             crop_w,
                             crop_h,
                         ),
@@ -163,12 +211,7 @@ pub fn crop_worker(
                     effective_polygon: effective_poly_local,
                     suffix: config.suffix.clone(),
                     regions: regions_local,
-                    source_bbox: BBox {
-                        x: config.bbox.x,
-                        y: config.bbox.y,
-                        w: config.bbox.w,
-                        h: config.bbox.h,
-                    },
+                    source_bbox: bbox,
                 });
             }
         } else {
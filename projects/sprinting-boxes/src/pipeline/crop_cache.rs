@@ -0,0 +1,274 @@
+//! Crops regenerated from the source video for runs that did not save them.
+//!
+//! `regenerate` reads the crop's sampled frame with the run's reader backend and
+//! cuts it out the way the Crop worker did, using the crop and preprocessing
+//! settings recorded in processing_config.json. Results are kept as JPEGs in
+//! `crop_cache/`, evicting the least recently served ones once the directory
+//! outgrows its budget. Processing a run again clears the cache.
+
+use crate::pipeline::crop::{crop_frame, RectifyMaps};
+use crate::pipeline::crop_store::parse_name;
+use crate::pipeline::preprocess::apply_chain;
+use crate::pipeline::snapshot::ProcessingSnapshot;
+use crate::pipeline::types::CropConfig;
+use crate::run_artifacts::{config_hash, PreprocessConfig};
+use crate::run_context::RunContext;
+use crate::video::ffmpeg_reader::FfmpegReader;
+use crate::video::lens::LensProfile;
+use crate::video::opencv_reader::OpencvReader;
+use crate::video::VideoReader;
+use anyhow::Result;
+use opencv::core::Vector;
+use opencv::imgcodecs;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+pub const CACHE_DIR: &str = "crop_cache";
+
+/// Videos kept open between requests, most recently used first
+const MAX_OPEN_VIDEOS: usize = 2;
+
+lazy_static::lazy_static! {
+    static ref OPEN_VIDEOS: Mutex<Vec<OpenVideo>> = Mutex::new(Vec::new());
+}
+
+/// A reader left open so that auditing neighbouring frames does not reopen
+/// (and for ffmpeg, re-index) the video on every request.
+struct OpenVideo {
+    /// Video path, backend and sample rate the reader was opened with
+    key: String,
+    reader: Box<dyn VideoReader>,
+    /// Remap tables per crop suffix, for runs with a lens profile
+    maps: HashMap<String, Option<RectifyMaps>>,
+}
+
+/// How the run's crops were cut, as recorded when it was processed
+struct CropSource {
+    backend: String,
+    sample_rate: f64,
+    lens: Option<LensProfile>,
+    preprocess: PreprocessConfig,
+}
+
+impl CropSource {
+    /// `None` if crops.json changed since processing: the run's detections then
+    /// refer to crops that can no longer be cut.
+    fn load(run_context: &RunContext) -> Result<Option<Self>> {
+        match ProcessingSnapshot::load(&run_context.output_dir)? {
+            Some(snapshot) => {
+                let crops = run_context.load_crop_configs()?;
+                if snapshot.crops_hash != config_hash(&[&serde_json::to_vec(&crops)?]) {
+                    tracing::warn!(
+                        "crops.json of {} changed since processing, not regenerating crops",
+                        run_context.run_id
+                    );
+                    return Ok(None);
+                }
+                Ok(Some(Self {
+                    backend: snapshot.backend,
+                    sample_rate: snapshot.sample_rate,
+                    lens: snapshot.lens,
+                    preprocess: snapshot.preprocess,
+                }))
+            }
+            // Processed before records existed: assume the current settings
+            None => Ok(Some(Self {
+                backend: "ffmpeg".to_string(),
+                sample_rate: run_context.sample_rate,
+                lens: run_context.lens,
+                preprocess: run_context.load_preprocess_config()?,
+            })),
+        }
+    }
+}
+
+/// The JPEG of crop `name` (e.g. `frame_000042_left.jpg`), from the cache or cut
+/// from the source video. `None` if `name` is not a crop of the run or the video
+/// is missing. Keeps the run's cache under `budget_bytes`.
+pub fn regenerate(
+    run_context: &RunContext,
+    video_root: &Path,
+    name: &str,
+    budget_bytes: u64,
+) -> Result<Option<Vec<u8>>> {
+    if name.contains(['/', '\\']) {
+        return Ok(None);
+    }
+    let (frame, suffix) = match parse_name(name) {
+        // Only the canonical spelling, so each crop is cached once
+        Some((frame, suffix)) if name == format!("frame_{:06}_{}.jpg", frame, suffix) => {
+            (frame, suffix)
+        }
+        _ => return Ok(None),
+    };
+
+    let cache_dir = run_context.output_dir.join(CACHE_DIR);
+    let cached = cache_dir.join(name);
+    if cached.exists() {
+        // Touch it so eviction sees it as recently used
+        File::options()
+            .append(true)
+            .open(&cached)?
+            .set_modified(SystemTime::now())?;
+        return Ok(Some(fs::read(cached)?));
+    }
+
+    let crops = run_context.load_crop_configs()?;
+    let config = match Vec::<CropConfig>::from(&crops)
+        .into_iter()
+        .find(|c| c.suffix == suffix)
+    {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    let source = match CropSource::load(run_context)? {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+    let video_path = run_context.resolve_video_path(video_root);
+    if !video_path.exists() {
+        tracing::warn!(
+            "Video of {} not found at {:?}",
+            run_context.run_id,
+            video_path
+        );
+        return Ok(None);
+    }
+
+    let key = format!(
+        "{}|{}|{}",
+        video_path.display(),
+        source.backend,
+        source.sample_rate
+    );
+    let mut video = match take_open_video(&key) {
+        Some(video) => video,
+        None => {
+            let path = video_path.to_str().unwrap();
+            let reader: Box<dyn VideoReader> = match source.backend.as_str() {
+                "ffmpeg" => Box::new(FfmpegReader::new(path, source.sample_rate)?),
+                _ => Box::new(OpencvReader::new(path, source.sample_rate)?),
+            };
+            OpenVideo {
+                key,
+                reader,
+                maps: HashMap::new(),
+            }
+        }
+    };
+
+    let result = cut_crop(&mut video, frame, &config, &source);
+    return_open_video(video);
+    let data = match result? {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+
+    fs::create_dir_all(&cache_dir)?;
+    let tmp = cache_dir.join(format!(".{}.tmp", name));
+    fs::write(&tmp, &data)?;
+    fs::rename(&tmp, &cached)?;
+    evict(&cache_dir, budget_bytes)?;
+    Ok(Some(data))
+}
+
+/// Reads `frame` and encodes its crop for `config`; `None` past the end of the video.
+fn cut_crop(
+    video: &mut OpenVideo,
+    frame: usize,
+    config: &CropConfig,
+    source: &CropSource,
+) -> Result<Option<Vec<u8>>> {
+    if frame >= video.reader.frame_count()? {
+        return Ok(None);
+    }
+    let img = video.reader.read_unit(frame)?;
+    let maps = video.maps.entry(config.suffix.clone()).or_insert(None);
+    let content = crop_frame(&img, &config.bbox, source.lens.as_ref(), maps)?;
+    let (crop, _) = apply_chain(&content, source.preprocess.steps_for(&config.suffix))?;
+
+    let mut buf = Vector::<u8>::new();
+    imgcodecs::imencode(".jpg", &crop, &mut buf, &Vector::new())?;
+    Ok(Some(buf.to_vec()))
+}
+
+fn take_open_video(key: &str) -> Option<OpenVideo> {
+    let mut videos = OPEN_VIDEOS.lock().unwrap();
+    let i = videos.iter().position(|v| v.key == key)?;
+    Some(videos.remove(i))
+}
+
+fn return_open_video(video: OpenVideo) {
+    let mut videos = OPEN_VIDEOS.lock().unwrap();
+    videos.insert(0, video);
+    videos.truncate(MAX_OPEN_VIDEOS);
+}
+
+/// Removes the least recently used files of `cache_dir` until it holds at most
+/// `budget_bytes`. Returns the number of files removed.
+pub fn evict(cache_dir: &Path, budget_bytes: u64) -> Result<usize> {
+    let mut files = Vec::new();
+    let mut total = 0;
+    for entry in fs::read_dir(cache_dir)?.flatten() {
+        let metadata = match entry.metadata() {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => continue,
+        };
+        total += metadata.len();
+        let used = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        files.push((used, metadata.len(), entry.path()));
+    }
+    files.sort();
+
+    let mut removed = 0;
+    for (_, len, path) in files {
+        if total <= budget_bytes {
+            break;
+        }
+        fs::remove_file(path)?;
+        total -= len;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Removes crops cached for an earlier processing of the run.
+pub fn clear(dir: &Path) -> Result<()> {
+    let cache_dir = dir.join(CACHE_DIR);
+    if cache_dir.exists() {
+        fs::remove_dir_all(cache_dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn eviction_removes_least_recently_used_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let now = SystemTime::now();
+        for (name, age_secs) in [("a.jpg", 30), ("b.jpg", 10), ("c.jpg", 20)] {
+            let path = dir.join(name);
+            fs::write(&path, vec![0u8; 100]).unwrap();
+            File::options()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age_secs))
+                .unwrap();
+        }
+
+        assert_eq!(evict(dir, 300).unwrap(), 0);
+        assert_eq!(evict(dir, 150).unwrap(), 2);
+        assert!(dir.join("b.jpg").exists());
+        assert!(!dir.join("a.jpg").exists());
+        assert!(!dir.join("c.jpg").exists());
+    }
+}
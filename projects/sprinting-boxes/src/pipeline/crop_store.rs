@@ -0,0 +1,399 @@
+//! Storage of the per-frame crop JPEGs.
+//!
+//! The Finalize worker writes one loose JPEG per crop per frame into `crops/`,
+//! which adds up to tens of GB per game. `compact` applies a run's
+//! `CropRetention` afterwards: it drops crops far from any cliff, downscales
+//! overview crops and optionally packs the rest into `crops.tar`, an
+//! uncompressed tar that other tools can open too.
+//!
+//! - `crops.idx`: JSON index written by `compact`, listing every retained crop
+//!   with its byte range in `crops.tar` (or none for a loose file) and its scale
+//!   relative to the crop its detections refer to.
+//!
+//! `CropStore` reads crops from either layout; without an index every loose file
+//! in `crops/` is served at scale 1.
+
+use crate::run_artifacts::Manifest;
+use anyhow::{Context as _, Result};
+use opencv::core::{Mat, Size, Vector};
+use opencv::prelude::*;
+use opencv::{imgcodecs, imgproc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub const CROPS_DIR: &str = "crops";
+pub const PACK_FILE: &str = "crops.tar";
+pub const INDEX_FILE: &str = "crops.idx";
+
+/// How much of the saved crops a run keeps, stored in `metadata.json`.
+/// The default keeps every crop as written.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CropRetention {
+    /// Keep only crops within this many seconds of a cliff in points.csv
+    #[serde(default)]
+    pub cliff_window_secs: Option<f64>,
+    /// Downscale overview crops so their longer side is at most this many px
+    #[serde(default)]
+    pub overview_max_side: Option<i32>,
+    /// Pack the retained crops into crops.tar
+    #[serde(default)]
+    pub pack: bool,
+}
+
+impl CropRetention {
+    /// Whether `compact` would leave the crops as the Finalize worker wrote them
+    pub fn keeps_everything(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Where one retained crop is stored
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CropEntry {
+    /// Byte offset of the JPEG in crops.tar; `None` for a loose file in crops/
+    pub offset: Option<u64>,
+    pub len: u64,
+    /// Stored size relative to the crop its detections refer to (< 1 once downscaled)
+    pub scale: f32,
+}
+
+/// Contents of crops.idx
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CropIndex {
+    pub entries: BTreeMap<String, CropEntry>,
+}
+
+impl CropIndex {
+    fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        fs::write(dir.join(INDEX_FILE), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Outcome of `compact`
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CompactionReport {
+    pub kept: usize,
+    pub removed: usize,
+    pub downscaled: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Read access to a run's crops, packed or loose.
+pub struct CropStore {
+    dir: PathBuf,
+    index: Option<CropIndex>,
+}
+
+impl CropStore {
+    pub fn open(dir: &Path) -> Result<Self> {
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            index: CropIndex::load(dir)?,
+        })
+    }
+
+    /// Names of all stored crops, e.g. `frame_000042_overview.jpg`, in order.
+    pub fn names(&self) -> Result<Vec<String>> {
+        if let Some(index) = &self.index {
+            return Ok(index.entries.keys().cloned().collect());
+        }
+        let crops_dir = self.dir.join(CROPS_DIR);
+        if !crops_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(crops_dir)?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".jpg"))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// The JPEG bytes of crop `name` and their scale, or `None` if there is no such crop.
+    pub fn read(&self, name: &str) -> Result<Option<(Vec<u8>, f32)>> {
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Ok(None);
+        }
+        let entry = match &self.index {
+            Some(index) => match index.entries.get(name) {
+                Some(entry) => *entry,
+                None => return Ok(None),
+            },
+            None => CropEntry {
+                offset: None,
+                len: 0,
+                scale: 1.0,
+            },
+        };
+
+        let data = match entry.offset {
+            Some(offset) => {
+                let mut pack = File::open(self.dir.join(PACK_FILE))?;
+                pack.seek(SeekFrom::Start(offset))?;
+                let mut data = vec![0u8; entry.len as usize];
+                pack.read_exact(&mut data)
+                    .with_context(|| format!("Truncated {} entry {}", PACK_FILE, name))?;
+                data
+            }
+            None => {
+                let path = self.dir.join(CROPS_DIR).join(name);
+                if !path.exists() {
+                    return Ok(None);
+                }
+                fs::read(path)?
+            }
+        };
+        Ok(Some((data, entry.scale)))
+    }
+}
+
+/// Removes the pack and index of an earlier run before new loose crops are written.
+pub fn reset(dir: &Path) -> Result<()> {
+    for name in [PACK_FILE, INDEX_FILE] {
+        let path = dir.join(name);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Bytes used by the crops in `dir`: loose files, pack and index.
+pub fn stored_bytes(dir: &Path) -> u64 {
+    let file_len = |path: PathBuf| fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let loose: u64 = fs::read_dir(dir.join(CROPS_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0);
+    loose + file_len(dir.join(PACK_FILE)) + file_len(dir.join(INDEX_FILE))
+}
+
+/// Frame id and region of a crop named `frame_{id:06}_{region}.jpg`
+pub(crate) fn parse_name(name: &str) -> Option<(usize, &str)> {
+    let (id, region) = name
+        .strip_prefix("frame_")?
+        .strip_suffix(".jpg")?
+        .split_once('_')?;
+    Some((id.parse().ok()?, region))
+}
+
+/// Sampled frames of the cliffs in points.csv
+fn load_cliff_frames(dir: &Path) -> Result<Vec<usize>> {
+    let path = dir.join("points.csv");
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("A cliff window needs {:?}", path))?;
+    Ok(content
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split(',').next()?.parse().ok())
+        .collect())
+}
+
+/// Whether `frame` is at most `window` sampled frames from one of `cliffs` (sorted)
+fn near_cliff(frame: usize, cliffs: &[usize], window: f64) -> bool {
+    let i = cliffs.partition_point(|&c| c < frame);
+    let distance = |c: usize| (c as f64 - frame as f64).abs();
+    cliffs.get(i).is_some_and(|&c| distance(c) <= window)
+        || (i > 0 && distance(cliffs[i - 1]) <= window)
+}
+
+/// Re-encodes a JPEG so its longer side is at most `max_side`; `None` if it already is.
+fn downscale(data: &[u8], max_side: i32) -> Result<Option<(Vec<u8>, f32)>> {
+    let img = imgcodecs::imdecode(&Vector::<u8>::from_slice(data), imgcodecs::IMREAD_COLOR)?;
+    let longer = img.cols().max(img.rows());
+    if longer <= max_side || longer == 0 {
+        return Ok(None);
+    }
+    let factor = max_side as f64 / longer as f64;
+    let size = Size::new(
+        ((img.cols() as f64 * factor).round() as i32).max(1),
+        ((img.rows() as f64 * factor).round() as i32).max(1),
+    );
+    let mut resized = Mat::default();
+    imgproc::resize(&img, &mut resized, size, 0.0, 0.0, imgproc::INTER_AREA)?;
+    let mut buf = Vector::<u8>::new();
+    imgcodecs::imencode(".jpg", &resized, &mut buf, &Vector::new())?;
+    Ok(Some((buf.to_vec(), factor as f32)))
+}
+
+/// Applies `retention` to the crops in `dir` and writes crops.idx.
+pub fn compact(
+    dir: &Path,
+    retention: &CropRetention,
+    sample_rate: f64,
+) -> Result<CompactionReport> {
+    let store = CropStore::open(dir)?;
+    let names = store.names()?;
+    let mut report = CompactionReport {
+        bytes_before: stored_bytes(dir),
+        ..Default::default()
+    };
+
+    let mut cliffs = match retention.cliff_window_secs {
+        Some(_) => load_cliff_frames(dir)?,
+        None => Vec::new(),
+    };
+    cliffs.sort_unstable();
+    let window = retention.cliff_window_secs.map(|secs| secs * sample_rate);
+
+    let crops_dir = dir.join(CROPS_DIR);
+    let pack_tmp = dir.join(format!("{}.tmp", PACK_FILE));
+    let mut pack = if retention.pack {
+        Some(tar::Builder::new(File::create(&pack_tmp)?))
+    } else {
+        None
+    };
+    let mut index = CropIndex::default();
+    let mut dropped = Vec::new();
+
+    for name in names {
+        let (data, scale) = match store.read(&name)? {
+            Some(crop) => crop,
+            None => continue,
+        };
+        let parsed = parse_name(&name);
+
+        let keep = match (window, parsed) {
+            (Some(window), Some((frame, _))) => near_cliff(frame, &cliffs, window),
+            _ => true,
+        };
+        if !keep {
+            report.removed += 1;
+            dropped.push(name);
+            continue;
+        }
+
+        let mut crop = (data, scale);
+        let mut rewritten = false;
+        if let (Some(max_side), Some((_, "overview"))) = (retention.overview_max_side, parsed) {
+            if let Some((data, factor)) = downscale(&crop.0, max_side)? {
+                crop = (data, scale * factor);
+                rewritten = true;
+                report.downscaled += 1;
+            }
+        }
+        let (data, scale) = crop;
+
+        let offset = match pack.as_mut() {
+            Some(builder) => Some(append_to_pack(builder, &name, &data)?),
+            None => {
+                // Crops that come out of a pack, or were downscaled, become loose files again
+                if rewritten || !crops_dir.join(&name).exists() {
+                    fs::create_dir_all(&crops_dir)?;
+                    fs::write(crops_dir.join(&name), &data)?;
+                }
+                None
+            }
+        };
+        index.entries.insert(
+            name,
+            CropEntry {
+                offset,
+                len: data.len() as u64,
+                scale,
+            },
+        );
+        report.kept += 1;
+    }
+
+    match pack {
+        Some(builder) => {
+            builder.into_inner()?.sync_all()?;
+            fs::rename(&pack_tmp, dir.join(PACK_FILE))?;
+            if crops_dir.exists() {
+                fs::remove_dir_all(&crops_dir)?;
+            }
+        }
+        None => {
+            for name in &dropped {
+                let path = crops_dir.join(name);
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+            let pack_path = dir.join(PACK_FILE);
+            if pack_path.exists() {
+                fs::remove_file(pack_path)?;
+            }
+        }
+    }
+
+    index.save(dir)?;
+    Manifest::record(dir, INDEX_FILE, None)?;
+    if retention.pack {
+        Manifest::record(dir, PACK_FILE, None)?;
+    }
+    report.bytes_after = stored_bytes(dir);
+    Ok(report)
+}
+
+/// Appends one crop to the pack and returns the byte offset of its data.
+fn append_to_pack(builder: &mut tar::Builder<File>, name: &str, data: &[u8]) -> Result<u64> {
+    let mut header = tar::Header::new_ustar();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    builder.append_data(&mut header, name, data)?;
+    // Data is padded to 512-byte blocks and ends where the archive now ends
+    let end = builder.get_mut().stream_position()?;
+    Ok(end - (data.len() as u64).div_ceil(512) * 512)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_crops_keep_only_the_cliff_window() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join(CROPS_DIR)).unwrap();
+        fs::write(dir.join("points.csv"), "frame_index,is_cliff\n100,1\n").unwrap();
+        for frame in [10, 95, 104, 200] {
+            let name = format!("frame_{:06}_left.jpg", frame);
+            fs::write(dir.join(CROPS_DIR).join(name), vec![frame as u8; 700]).unwrap();
+        }
+
+        let retention = CropRetention {
+            cliff_window_secs: Some(5.0),
+            overview_max_side: None,
+            pack: true,
+        };
+        let report = compact(dir, &retention, 1.0).unwrap();
+        assert_eq!((report.kept, report.removed), (2, 2));
+        assert!(!dir.join(CROPS_DIR).exists());
+
+        let store = CropStore::open(dir).unwrap();
+        assert_eq!(
+            store.names().unwrap(),
+            ["frame_000095_left.jpg", "frame_000104_left.jpg"]
+        );
+        let (data, scale) = store.read("frame_000104_left.jpg").unwrap().unwrap();
+        assert_eq!((data, scale), (vec![104u8; 700], 1.0));
+        assert!(store.read("frame_000010_left.jpg").unwrap().is_none());
+
+        // The pack is a regular tar
+        let mut archive = tar::Archive::new(File::open(dir.join(PACK_FILE)).unwrap());
+        assert_eq!(archive.entries().unwrap().count(), 2);
+    }
+}
@@ -1,21 +1,44 @@
 use crate::detection;
-use crate::detection::slicing::{generate_tiles, nms, HbbWrapper, SliceConfig};
+use crate::detection::cascade::{
+    CascadeConfig, CascadeState, CoarseDetection, TileAction, TileKey,
+};
+use crate::detection::pose::PoseConfig;
+use crate::detection::slicing::{
+    generate_planned_tiles, player_height_prior, suppress, HbbWrapper, PlayerScaleTracker,
+    SliceConfig, Tile, TilingPolicy,
+};
+use crate::detection::{Detection, Detector};
 use crate::geometry::transform_ez_to_overview;
 use crate::pipeline::types::{
-    BBox, CropResult, DetectedFrame, DetectionSummary, EnrichedDetection, PreprocessedFrame,
-    ProcessingState,
+    BBox, CropData, CropResult, DetectedFrame, DetectionSummary, EnrichedDetection,
+    PreprocessedFrame, ProcessingState,
 };
 use anyhow::Result;
 use crossbeam::channel::{Receiver, Sender};
+use opencv::core::Mat;
 use opencv::prelude::MatTraitConst;
 use std::sync::Arc;
 use std::time::Instant;
+use ultimate_event_detection::{pose_features, Aabb, StanceConfig};
 use usls::Hbb;
 
+/// COCO-80 class ids kept from the detector
+const PERSON_CLASS_ID: usize = 0;
+const DISC_CLASS_ID: usize = 29;
+
 /// Parameters for the detection worker to avoid too many arguments clippy warning.
 pub struct DetectionParams {
     pub min_conf: f32,
+    /// Confidence threshold for discs, which are small and blurred in flight
+    pub disc_min_conf: f32,
     pub slice_config: SliceConfig,
+    pub tiling: TilingPolicy,
+    /// Coarse-to-fine cascade; `None` runs every tile on every frame
+    pub cascade: Option<CascadeConfig>,
+    /// Pose stage on end-zone crops; `None` skips it
+    pub pose: Option<PoseConfig>,
+    /// Line-up geometry for the stance features of the pose stage
+    pub stance: StanceConfig,
     pub regions_to_detect: Option<Vec<String>>,
 }
 
@@ -34,6 +57,21 @@ pub fn detection_worker(
         "Detection worker started with CoreML GPU pipeline and slice_config: {:?}",
         params.slice_config
     );
+    tracing::info!(
+        "Tiling policy: {:?}, cascade: {:?}",
+        params.tiling,
+        params.cascade
+    );
+
+    let pose_detector = match &params.pose {
+        Some(config) => Some(detection::pose::create_pose_detector(config)?),
+        None => None,
+    };
+
+    // Recent player heights per crop, for adaptive tile sizes
+    let mut scale_tracker = PlayerScaleTracker::default();
+    // Last fine detections per tile, reused for static regions in cascade mode
+    let mut cascade_state = CascadeState::<Hbb>::new();
 
     for frame in rx {
         // Exit immediately if stop_processing was called (feature and finalize workers do the same)
@@ -53,12 +91,38 @@ pub fn detection_worker(
             .unwrap_or(&default_targets);
         let start_inst = Instant::now();
 
+        let overview_info: Option<(usize, BBox, f32, f32)> =
+            frame.crops.iter().enumerate().find_map(|(i, c)| {
+                if c.suffix == "overview" {
+                    let size = c.image.size().ok()?;
+                    Some((i, c.source_bbox, size.width as f32, size.height as f32))
+                } else {
+                    None
+                }
+            });
+
+        // 0. Cascade: one downscaled pass over the overview decides which tiles to run
+        let coarse = match (params.cascade, overview_info) {
+            (Some(config), Some((ov_index, ..))) if slicing_enabled => Some(coarse_pass(
+                detector.as_ref(),
+                &frame.crops[ov_index].image,
+                params.slice_config.tile_size,
+                &config,
+            )?),
+            _ => None,
+        };
+
         // 1. Tile Generation Phase: Collect tiles from all crops
         struct QueuedTile {
             crop_index: usize,
-            tile: crate::detection::slicing::Tile,
+            tile: Tile,
+            /// Cascade cache entry to fill with this tile's detections
+            cache_key: Option<(TileKey, usize)>,
         }
         let mut all_queued_tiles = Vec::new();
+        let mut tile_sizes_by_crop = Vec::new();
+        let mut detections_by_crop = vec![Vec::new(); frame.crops.len()];
+        let mut tiles_skipped = 0;
 
         for (crop_index, crop) in frame.crops.iter().enumerate() {
             let regions_to_tile = if crop.suffix == "overview" {
@@ -94,77 +158,108 @@ pub fn detection_worker(
             };
 
             if slicing_enabled {
-                let tiles = generate_tiles(
+                // Far crops have small players: size their tiles from what this crop has
+                // shown so far, or from the polygon's on-screen area before that
+                let player_height = scale_tracker.median(&crop.suffix).or_else(|| {
+                    player_height_prior(&crop.original_polygon, crop.suffix != "overview")
+                });
+                let plan = params
+                    .tiling
+                    .plan(params.slice_config.tile_size, player_height);
+                tile_sizes_by_crop.push((crop.suffix.clone(), plan.tile_size));
+
+                let tiles = generate_planned_tiles(
                     &crop.image,
                     &params.slice_config,
+                    plan,
                     regions_to_tile.as_deref(),
                 )?;
                 for tile in tiles {
-                    all_queued_tiles.push(QueuedTile { crop_index, tile });
+                    let (coarse, config) = match (&coarse, &params.cascade) {
+                        (Some(coarse), Some(config)) => (coarse, config),
+                        _ => {
+                            all_queued_tiles.push(QueuedTile {
+                                crop_index,
+                                tile,
+                                cache_key: None,
+                            });
+                            continue;
+                        }
+                    };
+
+                    let key = TileKey {
+                        crop: crop.suffix.clone(),
+                        x_offset: tile.x_offset,
+                        y_offset: tile.y_offset,
+                        tile_size: plan.tile_size,
+                    };
+                    let bounds = overview_bounds(&tile, crop, overview_info);
+                    match cascade_state.plan_tile(&key, bounds, coarse, config) {
+                        TileAction::Reuse(previous) => {
+                            tiles_skipped += 1;
+                            detections_by_crop[crop_index].extend(previous);
+                        }
+                        TileAction::Refine { coarse_count } => {
+                            all_queued_tiles.push(QueuedTile {
+                                crop_index,
+                                tile,
+                                cache_key: Some((key, coarse_count)),
+                            });
+                        }
+                    }
                 }
             } else {
                 // Standard detection: one "fake" tile covering the whole image
                 all_queued_tiles.push(QueuedTile {
                     crop_index,
-                    tile: crate::detection::slicing::Tile {
+                    tile: Tile {
                         image: crop.image.clone(),
                         x_offset: 0,
                         y_offset: 0,
                         original_width: crop.image.cols(),
                         original_height: crop.image.rows(),
+                        scale: 1.0,
                     },
+                    cache_key: None,
                 });
             }
         }
 
         tracing::debug!(
-            "Detection worker: {} total tiles for frame {}",
+            "Detection worker: {} total tiles for frame {} ({} reused)",
             all_queued_tiles.len(),
-            frame.id
+            frame.id,
+            tiles_skipped
         );
 
         // 2. Inference Phase: Run detector on all tiles
-        let mut detections_by_crop = vec![Vec::new(); frame.crops.len()];
-        if !all_queued_tiles.is_empty() {
-            for queued in all_queued_tiles.into_iter() {
-                let detections = detector.detect(&queued.tile.image)?;
-
-                for det in detections {
-                    if det.confidence < params.min_conf {
-                        continue;
-                    }
-                    // Only keep COCO-80 class `0`, which maps to `person`
-                    if det.class_id != Some(0) {
-                        continue;
-                    }
-
-                    // Detections are in normalized coordinates [0,1] relative to the model input size.
-                    // D-FINE model expects 640x640 input. Map coordinates back to crop pixel space.
-                    // ASSUMPTION: If the model is changed to a different input size, these
-                    // calculations must be updated accordingly.
-                    const MODEL_SIZE: f32 = 640.0;
-
-                    let x1 = det.x_min * MODEL_SIZE + queued.tile.x_offset as f32;
-                    let y1 = det.y_min * MODEL_SIZE + queued.tile.y_offset as f32;
-                    let x2 = det.x_max * MODEL_SIZE + queued.tile.x_offset as f32;
-                    let y2 = det.y_max * MODEL_SIZE + queued.tile.y_offset as f32;
-
-                    // Create usls::Hbb with detection info for downstream processing
-                    let mut hbb = Hbb::default()
-                        .with_xyxy(x1, y1, x2, y2)
-                        .with_confidence(det.confidence);
-
-                    if let Some(class_id) = det.class_id {
-                        hbb = hbb.with_id(class_id);
-                    }
-                    if let Some(class_name) = &det.class_name {
-                        hbb = hbb.with_name(class_name.as_str());
-                    }
+        let tiles_run = all_queued_tiles.len();
+        for queued in all_queued_tiles.into_iter() {
+            let detections = detector.detect(&queued.tile.image)?;
+
+            let tile_hbbs: Vec<Hbb> = detections
+                .iter()
+                // Only keep people and discs (COCO-80 `person` and `frisbee`)
+                .filter(|det| match det.class_id {
+                    Some(PERSON_CLASS_ID) => det.confidence >= params.min_conf,
+                    Some(DISC_CLASS_ID) => det.confidence >= params.disc_min_conf,
+                    _ => false,
+                })
+                .map(|det| to_crop_hbb(det, &queued.tile))
+                .collect();
 
-                    detections_by_crop[queued.crop_index].push(hbb);
-                }
+            if let Some((key, coarse_count)) = queued.cache_key {
+                // A disc in flight does not stay put, so only people are reused
+                let people = tile_hbbs
+                    .iter()
+                    .filter(|d| d.id() != Some(DISC_CLASS_ID))
+                    .cloned()
+                    .collect();
+                cascade_state.store(key, people, coarse_count);
             }
+            detections_by_crop[queued.crop_index].extend(tile_hbbs);
         }
+        cascade_state.finish_frame();
 
         // Debug: log class distribution across all detections for this frame
         if tracing::enabled!(tracing::Level::DEBUG) {
@@ -190,29 +285,29 @@ pub fn detection_worker(
 
         // 3. Re-assembly Phase: Create CropResults and merge EZ detections into overview
         let mut results = Vec::with_capacity(frame.crops.len());
-        let overview_info: Option<(usize, BBox, f32, f32)> =
-            frame.crops.iter().enumerate().find_map(|(i, c)| {
-                if c.suffix == "overview" {
-                    let size = c.image.size().ok()?;
-                    Some((i, c.source_bbox, size.width as f32, size.height as f32))
-                } else {
-                    None
-                }
-            });
 
         // Initialize CropResults and track NMS statistics
         let mut nms_stats_by_crop = Vec::new();
+        let mut discs_by_crop = Vec::with_capacity(frame.crops.len());
         for (i, crop) in frame.crops.iter().enumerate() {
+            // Discs are tracked separately and never counted as players
+            let (disc_hbbs, person_hbbs): (Vec<Hbb>, Vec<Hbb>) =
+                std::mem::take(&mut detections_by_crop[i])
+                    .into_iter()
+                    .partition(|d| d.id() == Some(DISC_CLASS_ID));
+            discs_by_crop.push(disc_hbbs);
+
             // Convert usls::Hbb to HbbWrapper for unified NMS
-            let wrapped_detections: Vec<HbbWrapper> = detections_by_crop[i]
-                .clone()
-                .into_iter()
-                .map(HbbWrapper::from)
-                .collect();
+            let wrapped_detections: Vec<HbbWrapper> =
+                person_hbbs.into_iter().map(HbbWrapper::from).collect();
 
-            let (nms_results, nms_stat) =
-                nms(wrapped_detections, params.slice_config.nms_iou_threshold);
+            let (nms_results, nms_stat) = suppress(
+                wrapped_detections,
+                params.slice_config.nms_iou_threshold,
+                params.slice_config.nms_strategy,
+            );
             nms_stats_by_crop.push((crop.suffix.clone(), nms_stat));
+            scale_tracker.record(&crop.suffix, nms_results.iter().map(|d| d.0.height()));
 
             // Convert back from HbbWrapper to usls::Hbb
             let nms_hbbs: Vec<usls::Hbb> =
@@ -298,10 +393,13 @@ pub fn detection_worker(
                 ov_result.detections.extend(merged_ez_detections);
 
                 // FINAL NMS to remove duplicates between overview-field and EZ-highres detections
-                // Use unified NMS function with diagnostic logging
+                // Uses the merge strategy (e.g. WBF fuses both views of a player into one box)
                 let iou_threshold = params.slice_config.nms_iou_threshold;
-                let (filtered_detections, nms_stat) =
-                    nms(ov_result.detections.clone(), iou_threshold);
+                let (filtered_detections, nms_stat) = suppress(
+                    ov_result.detections.clone(),
+                    iou_threshold,
+                    params.slice_config.merge_strategy,
+                );
                 merge_nms_stat = Some(nms_stat);
                 ov_result.detections = filtered_detections;
 
@@ -312,6 +410,32 @@ pub fn detection_worker(
             }
         }
 
+        // 5. Disc Phase: disc sightings from every crop, in overview pixels
+        let discs = match overview_info {
+            Some(overview_info) => merge_discs(
+                &frame.crops,
+                discs_by_crop,
+                overview_info,
+                &params.slice_config,
+            )?,
+            None => Vec::new(),
+        };
+
+        // 6. Pose Phase: stance features from the end-zone crops
+        let mut left_pose = None;
+        let mut right_pose = None;
+        if let Some(pose_detector) = &pose_detector {
+            for crop in &frame.crops {
+                let slot = match crop.suffix.as_str() {
+                    "left" => &mut left_pose,
+                    "right" => &mut right_pose,
+                    _ => continue,
+                };
+                let poses = pose_detector.detect_poses(&crop.image)?;
+                *slot = Some(pose_features(&poses, &params.stance));
+            }
+        }
+
         let duration_ms = start_inst.elapsed().as_secs_f64() * 1000.0;
         state.update_stage("detect", 1, duration_ms);
 
@@ -340,6 +464,12 @@ pub fn detection_worker(
                 _ => {}
             }
         }
+        let tile_size = |suffix: &str| {
+            tile_sizes_by_crop
+                .iter()
+                .find(|(s, _)| s == suffix)
+                .map(|(_, size)| *size)
+        };
 
         // Count detections in each region
         let mut left_kept = 0;
@@ -369,6 +499,11 @@ pub fn detection_worker(
             left_kept,
             right_kept,
             field_kept,
+            overview_tile_size: tile_size("overview"),
+            left_tile_size: tile_size("left"),
+            right_tile_size: tile_size("right"),
+            tiles_run,
+            tiles_skipped,
         };
 
         if tx
@@ -378,17 +513,27 @@ pub fn detection_worker(
                 left_count: 0.0,
                 right_count: 0.0,
                 field_count: 0.0,
+                expected: Default::default(),
                 pre_point_score: 0.0,
                 is_cliff: false,
                 left_emptied_first: false,
                 right_emptied_first: false,
                 maybe_false_positive: false,
+                pull_side_confidence: 0.0,
                 com_x: None,
                 com_y: None,
                 std_dev: None,
                 com_delta_x: None,
                 com_delta_y: None,
                 std_dev_delta: None,
+                excluded: Default::default(),
+                discs,
+                disc_from_left: false,
+                disc_from_right: false,
+                left_lined_up: left_pose.map(|p| p.lined_up),
+                right_lined_up: right_pose.map(|p| p.lined_up),
+                left_throwing: left_pose.map(|p| p.throwing),
+                right_throwing: right_pose.map(|p| p.throwing),
                 detection_summary: Some(detection_summary),
             })
             .is_err()
@@ -409,3 +554,164 @@ pub fn detection_worker(
 
     Ok(())
 }
+
+/// Map a detection, normalized to the tile image, back to crop pixels, undoing
+/// any resize of adaptively sized or downscaled tiles.
+fn to_crop_hbb(det: &Detection, tile: &Tile) -> Hbb {
+    let tile_w = tile.image.cols() as f32 / tile.scale;
+    let tile_h = tile.image.rows() as f32 / tile.scale;
+
+    let x1 = det.x_min * tile_w + tile.x_offset as f32;
+    let y1 = det.y_min * tile_h + tile.y_offset as f32;
+    let x2 = det.x_max * tile_w + tile.x_offset as f32;
+    let y2 = det.y_max * tile_h + tile.y_offset as f32;
+
+    // Create usls::Hbb with detection info for downstream processing
+    let mut hbb = Hbb::default()
+        .with_xyxy(x1, y1, x2, y2)
+        .with_confidence(det.confidence);
+
+    if let Some(class_id) = det.class_id {
+        hbb = hbb.with_id(class_id);
+    }
+    if let Some(class_name) = &det.class_name {
+        hbb = hbb.with_name(class_name.as_str());
+    }
+    hbb
+}
+
+/// Map each crop's disc detections to overview pixels and drop the duplicates
+/// seen by overlapping tiles or by both an end-zone crop and the overview.
+fn merge_discs(
+    crops: &[CropData],
+    discs_by_crop: Vec<Vec<Hbb>>,
+    overview_info: (usize, BBox, f32, f32),
+    slice_config: &SliceConfig,
+) -> Result<Vec<EnrichedDetection>> {
+    let (ov_index, ov_bbox, ov_w, ov_h) = overview_info;
+    let mut discs = Vec::new();
+    for (i, (crop, hbbs)) in crops.iter().zip(discs_by_crop).enumerate() {
+        let size = crop.image.size()?;
+        for d in hbbs {
+            let bbox = BBox {
+                x: d.xmin(),
+                y: d.ymin(),
+                w: d.width(),
+                h: d.height(),
+            };
+            let bbox = if i == ov_index {
+                bbox
+            } else {
+                transform_ez_to_overview(
+                    &bbox,
+                    &crop.source_bbox,
+                    size.width as f32,
+                    size.height as f32,
+                    &ov_bbox,
+                    ov_w,
+                    ov_h,
+                )
+            };
+            discs.push(EnrichedDetection {
+                bbox,
+                confidence: d.confidence().unwrap_or(0.0),
+                class_id: DISC_CLASS_ID,
+                class_name: d.name().map(|s| s.to_string()),
+                in_end_zone: false,
+                in_field: false,
+            });
+        }
+    }
+
+    let (discs, _) = suppress(
+        discs,
+        slice_config.nms_iou_threshold,
+        slice_config.merge_strategy,
+    );
+    Ok(discs)
+}
+
+/// Coarse pass of the cascade: the whole overview, downscaled to fit the model
+/// input, in a single inference.
+fn coarse_pass(
+    detector: &dyn Detector,
+    overview: &Mat,
+    input_size: u32,
+    config: &CascadeConfig,
+) -> Result<Vec<CoarseDetection>> {
+    let size = overview.size()?;
+    let longest = size.width.max(size.height).max(1);
+    let scale = (input_size as f32 / longest as f32).min(1.0);
+
+    let image = if scale < 1.0 {
+        let mut resized = Mat::default();
+        opencv::imgproc::resize(
+            overview,
+            &mut resized,
+            opencv::core::Size::new(
+                ((size.width as f32 * scale).round() as i32).max(1),
+                ((size.height as f32 * scale).round() as i32).max(1),
+            ),
+            0.0,
+            0.0,
+            opencv::imgproc::INTER_AREA,
+        )?;
+        resized
+    } else {
+        overview.clone()
+    };
+    let tile = Tile {
+        image,
+        x_offset: 0,
+        y_offset: 0,
+        original_width: size.width,
+        original_height: size.height,
+        scale,
+    };
+
+    Ok(detector
+        .detect(&tile.image)?
+        .iter()
+        .filter(|det| {
+            det.confidence >= config.min_confidence && det.class_id == Some(PERSON_CLASS_ID)
+        })
+        .map(|det| {
+            let hbb = to_crop_hbb(det, &tile);
+            CoarseDetection {
+                bounds: Aabb::from_xywh(hbb.xmin(), hbb.ymin(), hbb.width(), hbb.height()),
+                confidence: det.confidence,
+                height_px: hbb.height() * scale,
+            }
+        })
+        .collect())
+}
+
+/// A tile's area in overview pixels, where the coarse pass reports detections
+fn overview_bounds(
+    tile: &Tile,
+    crop: &CropData,
+    overview_info: Option<(usize, BBox, f32, f32)>,
+) -> Option<Aabb> {
+    let tile_bbox = BBox {
+        x: tile.x_offset as f32,
+        y: tile.y_offset as f32,
+        w: tile.original_width as f32,
+        h: tile.original_height as f32,
+    };
+    let bbox = if crop.suffix == "overview" {
+        tile_bbox
+    } else {
+        let (_, ov_bbox, ov_w, ov_h) = overview_info?;
+        let size = crop.image.size().ok()?;
+        transform_ez_to_overview(
+            &tile_bbox,
+            &crop.source_bbox,
+            size.width as f32,
+            size.height as f32,
+            &ov_bbox,
+            ov_w,
+            ov_h,
+        )
+    };
+    Some(Aabb::from_xywh(bbox.x, bbox.y, bbox.w, bbox.h))
+}
@@ -0,0 +1,233 @@
+//! Append-only, indexed store of per-frame detections.
+//!
+//! Replaces the single `detections.json` document, which had to be rewritten in
+//! full while processing and parsed in full for every annotated crop:
+//!
+//! - `detections.jsonl`: a `{"version":3}` header line, then one
+//!   `CompactFrameData` per line, appended as frames are finalized.
+//! - `detections.idx`: one little-endian `u64` per frame id, holding the byte
+//!   offset of that frame's line plus one (`0` = no such frame). Looking up a
+//!   frame is one seek into the index and one into the data.
+//!
+//! Runs processed before the store still have v2 `detections.json` (or legacy
+//! `pull_detections.json`); the run migrations in `run_artifacts` convert them
+//! with `migrate` at server startup or on import.
+
+use crate::pipeline::types::{CompactDetectionFile, CompactFrameData, DetectedFrame};
+use anyhow::{Context as _, Result};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+pub const DATA_FILE: &str = "detections.jsonl";
+pub const INDEX_FILE: &str = "detections.idx";
+pub const STORE_VERSION: u32 = 3;
+
+/// Pre-store detection files, newest format first
+pub const LEGACY_FILES: [&str; 2] = ["detections.json", "pull_detections.json"];
+
+const INDEX_ENTRY: u64 = std::mem::size_of::<u64>() as u64;
+
+/// Appends finalized frames to a run's detections store.
+pub struct DetectionsWriter {
+    data: BufWriter<File>,
+    index: BufWriter<File>,
+    /// Byte length of the data file
+    data_len: u64,
+    /// Number of index entries written, i.e. one past the highest frame id
+    index_len: u64,
+    frames: usize,
+}
+
+impl DetectionsWriter {
+    /// Creates an empty store in `dir`, replacing any previous one.
+    pub fn create(dir: &Path) -> Result<Self> {
+        Self::create_at(&dir.join(DATA_FILE), &dir.join(INDEX_FILE))
+    }
+
+    fn create_at(data_path: &Path, index_path: &Path) -> Result<Self> {
+        let mut data = BufWriter::new(
+            File::create(data_path).with_context(|| format!("Failed to create {:?}", data_path))?,
+        );
+        let index = BufWriter::new(
+            File::create(index_path)
+                .with_context(|| format!("Failed to create {:?}", index_path))?,
+        );
+        let header = format!("{{\"version\":{}}}\n", STORE_VERSION);
+        data.write_all(header.as_bytes())?;
+        Ok(Self {
+            data,
+            index,
+            data_len: header.len() as u64,
+            index_len: 0,
+            frames: 0,
+        })
+    }
+
+    /// Appends a frame. Frame ids must increase; gaps are left empty in the index.
+    pub fn append(&mut self, frame: &CompactFrameData) -> Result<()> {
+        let id = frame.id as u64;
+        if id < self.index_len {
+            anyhow::bail!(
+                "Frame {} appended out of order (after frame {})",
+                id,
+                self.index_len - 1
+            );
+        }
+
+        let mut line = serde_json::to_vec(frame)?;
+        line.push(b'\n');
+        self.data.write_all(&line)?;
+
+        for _ in self.index_len..id {
+            self.index.write_all(&0u64.to_le_bytes())?;
+        }
+        self.index.write_all(&(self.data_len + 1).to_le_bytes())?;
+
+        self.index_len = id + 1;
+        self.data_len += line.len() as u64;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Makes appended frames visible to readers. Data is flushed before the
+    /// index, so the index never points past the end of the data.
+    pub fn flush(&mut self) -> Result<()> {
+        self.data.flush()?;
+        self.index.flush()?;
+        Ok(())
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+}
+
+/// Random access to a run's detections store.
+pub struct DetectionsReader {
+    data: BufReader<File>,
+    index: File,
+}
+
+impl DetectionsReader {
+    /// Opens the store in `dir`. Returns `None` if the run has no detections.
+    pub fn open(dir: &Path) -> Result<Option<Self>> {
+        let index_path = dir.join(INDEX_FILE);
+        if !index_path.exists() {
+            return Ok(None);
+        }
+
+        let data = File::open(dir.join(DATA_FILE))?;
+        let index = File::open(&index_path)?;
+        Ok(Some(Self {
+            data: BufReader::new(data),
+            index,
+        }))
+    }
+
+    /// The detections of frame `frame_id`, or `None` if it was never stored
+    /// (or is still being written).
+    pub fn get(&mut self, frame_id: usize) -> Result<Option<CompactFrameData>> {
+        let position = frame_id as u64 * INDEX_ENTRY;
+        if position + INDEX_ENTRY > self.index.metadata()?.len() {
+            return Ok(None);
+        }
+        let mut entry = [0u8; INDEX_ENTRY as usize];
+        self.index.seek(SeekFrom::Start(position))?;
+        self.index.read_exact(&mut entry)?;
+        let offset = match u64::from_le_bytes(entry) {
+            0 => return Ok(None),
+            offset => offset - 1,
+        };
+
+        self.data.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        self.data.read_line(&mut line)?;
+        if !line.ends_with('\n') {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&line)?))
+    }
+}
+
+/// Converts a v2 compact `detections.json`, or the legacy list of
+/// `DetectedFrame`s, into a store in `dir`. The source file is left in place.
+/// Returns the number of frames migrated.
+pub fn migrate(json_path: &Path, dir: &Path) -> Result<usize> {
+    let content = fs::read_to_string(json_path)?;
+    let mut frames = match serde_json::from_str::<CompactDetectionFile>(&content) {
+        Ok(file) => file.frames,
+        Err(_) => serde_json::from_str::<Vec<DetectedFrame>>(&content)
+            .with_context(|| format!("Unrecognized detections file {:?}", json_path))?
+            .iter()
+            .map(crate::pipeline::finalize::convert_to_compact)
+            .collect(),
+    };
+    frames.sort_by_key(|f| f.id);
+    frames.dedup_by_key(|f| f.id);
+
+    // Build under temporary names so readers never see a half-written store;
+    // the index appears last, and its presence marks the store complete
+    let tmp = |name: &str| -> PathBuf { dir.join(format!("{}.tmp", name)) };
+    let mut writer = DetectionsWriter::create_at(&tmp(DATA_FILE), &tmp(INDEX_FILE))?;
+    for frame in &frames {
+        writer.append(frame)?;
+    }
+    writer.flush()?;
+    drop(writer);
+    fs::rename(tmp(DATA_FILE), dir.join(DATA_FILE))?;
+    fs::rename(tmp(INDEX_FILE), dir.join(INDEX_FILE))?;
+
+    Ok(frames.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn frame(id: usize) -> CompactFrameData {
+        CompactFrameData {
+            id,
+            crops: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn reads_frames_by_id() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut writer = DetectionsWriter::create(dir).unwrap();
+        for id in [0, 1, 4, 7] {
+            writer.append(&frame(id)).unwrap();
+        }
+        assert!(writer.append(&frame(5)).is_err());
+        writer.flush().unwrap();
+
+        let mut reader = DetectionsReader::open(dir).unwrap().unwrap();
+        assert_eq!(reader.get(4).unwrap().unwrap().id, 4);
+        assert_eq!(reader.get(0).unwrap().unwrap().id, 0);
+        assert!(reader.get(3).unwrap().is_none());
+        assert!(reader.get(100).unwrap().is_none());
+    }
+
+    #[test]
+    fn migrates_compact_json() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let mut file = CompactDetectionFile::new();
+        file.frames = vec![frame(2), frame(0)];
+        fs::write(
+            dir.join("detections.json"),
+            serde_json::to_string(&file).unwrap(),
+        )
+        .unwrap();
+
+        assert!(DetectionsReader::open(dir).unwrap().is_none());
+        assert_eq!(migrate(&dir.join("detections.json"), dir).unwrap(), 2);
+        let mut reader = DetectionsReader::open(dir).unwrap().unwrap();
+        assert_eq!(reader.get(2).unwrap().unwrap().id, 2);
+        assert!(reader.get(1).unwrap().is_none());
+        assert!(dir.join(INDEX_FILE).exists());
+    }
+}
@@ -1,14 +1,17 @@
-use crate::config::DetectorConfig;
-use crate::scoring::{calculate_deltas, calculate_frame_metrics, FrameHistory};
+use crate::run_context::{TeamSizeEstimateInfo, TEAM_SIZE_ESTIMATE_FILE};
+use crate::scoring::{calculate_deltas, calculate_frame_metrics, track_disc, FrameHistory};
 use ultimate_event_detection::{
-    detect_pull_side, CliffDetector, CliffDetectorConfig, EndZoneOccupancy, PullSide,
+    estimate_pull_side, estimate_team_size, BystanderFilter, CliffDetector, DiscTracker,
+    EndZoneOccupancy, PullSide, PullSideSample, RawCounts,
 };
 use anyhow::Result;
 use crossbeam::channel::{Receiver, Sender};
 use std::collections::BTreeMap;
+use std::io::Write;
 use std::sync::Arc;
 use std::time::Instant;
 
+use crate::pipeline::parquet_export::{self, ParquetTable};
 use crate::pipeline::types::{DetectedFrame, ProcessingState};
 
 // Re-export FeatureConfig for backward compatibility with callers using crate::pipeline::feature::FeatureConfig
@@ -20,8 +23,9 @@ pub use crate::scoring::FeatureConfig;
 /// 1. Calculates normalized player counts per endzone (left/right/field)
 /// 2. Computes pre-point scores using heuristics
 /// 3. Detects point-start transitions (cliffs) using smoothing and plateau detection
-/// 4. Applies side heuristics to determine which team pulled
-/// 5. Writes incremental CSV exports (features.csv and points.csv)
+/// 4. Estimates which team pulled (occupancy slopes, CoM motion, field growth)
+/// 5. Writes incremental CSV exports (features.csv and points.csv) and their Parquet
+///    counterparts (features.parquet and cliffs.parquet)
 ///
 /// The worker uses lookahead/lookback buffering to ensure accurate cliff detection
 /// and heuristic analysis before finalizing each frame.
@@ -31,36 +35,23 @@ pub fn feature_worker(
     config: FeatureConfig,
     state: Arc<ProcessingState>,
 ) -> Result<()> {
-    use std::io::Write;
-
-    let features_path = config.output_dir.join("features.csv");
-    let points_path = config.output_dir.join("points.csv");
     tracing::info!(
         "Feature worker started. output_dir: {:?}",
         config.output_dir
     );
 
-    let mut features_csv = std::fs::File::create(&features_path)?;
-    writeln!(
-        features_csv,
-        "frame_index,left_count,right_count,field_count,pre_point_score,is_cliff,com_x,com_y,distribution_std_dev,com_delta_x,com_delta_y,std_dev_delta"
-    )?;
-
-    let mut points_csv = std::fs::File::create(&points_path)?;
-    writeln!(
-        points_csv,
-        "frame_index,is_cliff,left_side_emptied_first,right_side_emptied_first"
-    )?;
+    let mut outputs = FeatureOutputs::create(&config)?;
 
     let mut input_buffer: BTreeMap<usize, DetectedFrame> = BTreeMap::new();
     let mut next_input_id = 0;
     let mut lookahead_buffer: Vec<DetectedFrame> = Vec::new();
     let mut history_buffer: Vec<FrameHistory> = Vec::new();
+    let mut raw_counts: Vec<RawCounts> = Vec::new();
 
-    // Load detector config from file (falls back to defaults if file missing)
-    let detector_config = DetectorConfig::from_file("detector.config.yaml");
-    let cliff_config = CliffDetectorConfig::from(detector_config);
+    let cliff_config = config.detector.cliff_config_for(config.game_format);
     let mut cliff_state = CliffDetector::new(cliff_config);
+    let mut bystanders = config.bystanders.clone().map(BystanderFilter::new);
+    let mut disc_tracker = config.disc_tracking.clone().map(DiscTracker::new);
 
     for frame in rx {
         let start_inst = Instant::now();
@@ -72,8 +63,12 @@ pub fn feature_worker(
         input_buffer.insert(frame.id, frame);
 
         while let Some(mut current_frame) = input_buffer.remove(&next_input_id) {
-            let (_left_raw, _right_raw, _field_raw, _pre_point_score, _com_x_opt, _com_y_opt) =
-                calculate_frame_metrics(&mut current_frame, &config);
+            let (left_raw, right_raw, field_raw, _pre_point_score, _com_x_opt, _com_y_opt) =
+                calculate_frame_metrics(&mut current_frame, &config, bystanders.as_mut());
+            raw_counts.push(RawCounts { left: left_raw, right: right_raw, field: field_raw });
+            if let Some(tracker) = disc_tracker.as_mut() {
+                track_disc(&mut current_frame, &config, tracker);
+            }
 
             // Calculate deltas
             calculate_deltas(&mut current_frame, history_buffer.last());
@@ -82,9 +77,12 @@ pub fn feature_worker(
             history_buffer.push(FrameHistory {
                 left_count: current_frame.left_count,
                 right_count: current_frame.right_count,
+                field_count: current_frame.field_count,
                 com_x: current_frame.com_x,
                 com_y: current_frame.com_y,
                 std_dev: current_frame.std_dev,
+                com_delta_x: current_frame.com_delta_x,
+                disc_from: disc_side(&current_frame),
             });
 
             // Run cliff detector
@@ -114,14 +112,32 @@ pub fn feature_worker(
                     let start_idx = frame.id.saturating_sub(config.lookback_frames);
                     let end_idx = (frame.id + config.lookahead_frames).min(history_buffer.len().saturating_sub(1));
 
-                    let window: Vec<(usize, EndZoneOccupancy)> = (start_idx..=end_idx)
+                    let window: Vec<PullSideSample> = (start_idx..=end_idx)
                         .map(|i| {
                             let h = &history_buffer[i];
-                            (i, EndZoneOccupancy { left: h.left_count, right: h.right_count, field: 0.0 })
+                            PullSideSample {
+                                frame_index: i,
+                                occupancy: EndZoneOccupancy {
+                                    left: h.left_count,
+                                    right: h.right_count,
+                                    field: h.field_count,
+                                },
+                                com_delta_x: h.com_delta_x,
+                                disc_from: h.disc_from.clone(),
+                            }
                         })
                         .collect();
 
-                    match detect_pull_side(&window, 2) {
+                    let estimate = estimate_pull_side(&window, frame.id, &config.pull_side);
+                    tracing::debug!(
+                        frame_id = frame.id,
+                        side = ?estimate.side,
+                        confidence = estimate.confidence,
+                        evidence = ?estimate.evidence,
+                        "pull-side estimate"
+                    );
+                    frame.pull_side_confidence = estimate.confidence;
+                    match estimate.side {
                         PullSide::Left => frame.left_emptied_first = true,
                         PullSide::Right => frame.right_emptied_first = true,
                         PullSide::Tie => {
@@ -132,34 +148,7 @@ pub fn feature_worker(
                     }
                 }
 
-                // Write to CSV files
-                writeln!(
-                    features_csv,
-                    "{},{:.5},{:.5},{:.3},{:.3},{},{:.5},{:.5},{:.5},{:.5},{:.5},{:.5}",
-                    frame.id,
-                    frame.left_count,
-                    frame.right_count,
-                    frame.field_count,
-                    frame.pre_point_score,
-                    if frame.is_cliff { 1 } else { 0 },
-                    frame.com_x.unwrap_or(-1.0),
-                    frame.com_y.unwrap_or(-1.0),
-                    frame.std_dev.unwrap_or(-1.0),
-                    frame.com_delta_x.unwrap_or(0.0),
-                    frame.com_delta_y.unwrap_or(0.0),
-                    frame.std_dev_delta.unwrap_or(0.0),
-                )?;
-
-                if frame.is_cliff {
-                    writeln!(
-                        points_csv,
-                        "{},{},{},{}",
-                        frame.id,
-                        if frame.is_cliff { 1 } else { 0 },
-                        if frame.left_emptied_first { 1 } else { 0 },
-                        if frame.right_emptied_first { 1 } else { 0 }
-                    )?;
-                }
+                outputs.write(&frame)?;
 
                 let duration_ms = start_inst.elapsed().as_secs_f64() * 1000.0;
                 state.update_stage("feature", 1, duration_ms);
@@ -181,8 +170,12 @@ pub fn feature_worker(
         if let Some(mut current_frame) = input_buffer.remove(&next_input_id) {
             // Process the frame
             // Calculate metrics
-            let (_left_raw, _right_raw, _field_raw, _pre_point_score, _com_x_opt, _com_y_opt) =
-                calculate_frame_metrics(&mut current_frame, &config);
+            let (left_raw, right_raw, field_raw, _pre_point_score, _com_x_opt, _com_y_opt) =
+                calculate_frame_metrics(&mut current_frame, &config, bystanders.as_mut());
+            raw_counts.push(RawCounts { left: left_raw, right: right_raw, field: field_raw });
+            if let Some(tracker) = disc_tracker.as_mut() {
+                track_disc(&mut current_frame, &config, tracker);
+            }
 
             // Calculate deltas
             calculate_deltas(&mut current_frame, history_buffer.last());
@@ -190,9 +183,12 @@ pub fn feature_worker(
             history_buffer.push(FrameHistory {
                 left_count: current_frame.left_count,
                 right_count: current_frame.right_count,
+                field_count: current_frame.field_count,
                 com_x: current_frame.com_x,
                 com_y: current_frame.com_y,
                 std_dev: current_frame.std_dev,
+                com_delta_x: current_frame.com_delta_x,
+                disc_from: disc_side(&current_frame),
             });
             let cliff_results = cliff_state.push(current_frame.id, current_frame.pre_point_score);
             lookahead_buffer.push(current_frame);
@@ -214,10 +210,56 @@ pub fn feature_worker(
     while !lookahead_buffer.is_empty() {
         let frame = lookahead_buffer.remove(0);
 
-        // Write final frames to CSV
+        outputs.write(&frame)?;
+
+        let _ = tx_f.send(frame);
+    }
+
+    outputs.close()?;
+
+    // 3. Propose a team size from the pre-point line-ups seen in this run
+    record_team_size_estimate(&raw_counts, &config);
+
+    tracing::info!("Feature worker finished gracefully");
+    Ok(())
+}
+
+/// The feature worker's per-frame outputs: CSVs streamed as frames complete, and
+/// Parquet files of the same rows at full precision.
+struct FeatureOutputs {
+    features_csv: std::fs::File,
+    points_csv: std::fs::File,
+    features_parquet: ParquetTable,
+    cliffs_parquet: ParquetTable,
+}
+
+impl FeatureOutputs {
+    fn create(config: &FeatureConfig) -> Result<Self> {
+        let dir = &config.output_dir;
+        let mut features_csv = std::fs::File::create(dir.join("features.csv"))?;
+        writeln!(
+            features_csv,
+            "frame_index,left_count,right_count,field_count,pre_point_score,is_cliff,com_x,com_y,distribution_std_dev,com_delta_x,com_delta_y,std_dev_delta,excluded_polygon,excluded_stationary,excluded_perspective,left_lined_up,right_lined_up,left_throwing,right_throwing,disc_from_left,disc_from_right,left_expected,right_expected,field_expected,left_variance,right_variance,field_variance"
+        )?;
+
+        let mut points_csv = std::fs::File::create(dir.join("points.csv"))?;
         writeln!(
+            points_csv,
+            "frame_index,is_cliff,left_side_emptied_first,right_side_emptied_first,pull_side_confidence"
+        )?;
+
+        Ok(Self {
             features_csv,
-            "{},{:.3},{:.3},{:.3},{:.3},{},{:.1},{:.1},{:.1},{:.1},{:.1},{:.1}",
+            points_csv,
+            features_parquet: parquet_export::features_table(dir, &config.run_metadata)?,
+            cliffs_parquet: parquet_export::cliffs_table(dir, &config.run_metadata)?,
+        })
+    }
+
+    fn write(&mut self, frame: &DetectedFrame) -> Result<()> {
+        writeln!(
+            self.features_csv,
+            "{},{:.5},{:.5},{:.3},{:.3},{},{:.5},{:.5},{:.5},{:.5},{:.5},{:.5},{},{},{},{:.3},{:.3},{},{},{},{},{:.5},{:.5},{:.5},{:.5},{:.5},{:.5}",
             frame.id,
             frame.left_count,
             frame.right_count,
@@ -230,22 +272,84 @@ pub fn feature_worker(
             frame.com_delta_x.unwrap_or(0.0),
             frame.com_delta_y.unwrap_or(0.0),
             frame.std_dev_delta.unwrap_or(0.0),
+            frame.excluded.polygon,
+            frame.excluded.stationary,
+            frame.excluded.perspective,
+            frame.left_lined_up.unwrap_or(-1.0),
+            frame.right_lined_up.unwrap_or(-1.0),
+            frame.left_throwing.map_or(-1, |t| t as i32),
+            frame.right_throwing.map_or(-1, |t| t as i32),
+            if frame.disc_from_left { 1 } else { 0 },
+            if frame.disc_from_right { 1 } else { 0 },
+            frame.expected.left,
+            frame.expected.right,
+            frame.expected.field,
+            frame.expected.left_variance,
+            frame.expected.right_variance,
+            frame.expected.field_variance,
         )?;
+        self.features_parquet
+            .push(parquet_export::feature_row(frame))?;
 
         if frame.is_cliff {
             writeln!(
-                points_csv,
-                "{},{},{},{}",
+                self.points_csv,
+                "{},{},{},{},{:.3}",
                 frame.id,
                 if frame.is_cliff { 1 } else { 0 },
                 if frame.left_emptied_first { 1 } else { 0 },
-                if frame.right_emptied_first { 1 } else { 0 }
+                if frame.right_emptied_first { 1 } else { 0 },
+                frame.pull_side_confidence
             )?;
+            self.cliffs_parquet.push(parquet_export::cliff_row(frame))?;
         }
+        Ok(())
+    }
 
-        let _ = tx_f.send(frame);
+    fn close(self) -> Result<()> {
+        self.features_parquet.close()?;
+        self.cliffs_parquet.close()
     }
+}
 
-    tracing::info!("Feature worker finished gracefully");
-    Ok(())
+/// Estimate the team size from raw counts and store it next to the run's metadata,
+/// replacing the previous run's estimate.
+fn record_team_size_estimate(raw_counts: &[RawCounts], config: &FeatureConfig) {
+    let estimate = match estimate_team_size(raw_counts, &config.team_size_estimation) {
+        Some(estimate) => estimate,
+        None => {
+            tracing::info!("Team-size estimate: no pre-point line-ups found");
+            let stale = config.output_dir.join(TEAM_SIZE_ESTIMATE_FILE);
+            if stale.exists() {
+                if let Err(e) = std::fs::remove_file(stale) {
+                    tracing::error!("Failed to remove stale team-size estimate: {}", e);
+                }
+            }
+            return;
+        }
+    };
+
+    if estimate.team_size != config.game_format.team_size() {
+        tracing::warn!(
+            configured = config.game_format.team_size(),
+            estimated = estimate.team_size,
+            confidence = estimate.confidence,
+            "Estimated team size disagrees with the run's game format"
+        );
+    }
+
+    if let Err(e) = TeamSizeEstimateInfo::from(&estimate).save(&config.output_dir) {
+        tracing::error!("Failed to save team-size estimate: {}", e);
+    }
+}
+
+/// End zone a tracked disc flight on this frame started from.
+fn disc_side(frame: &DetectedFrame) -> Option<PullSide> {
+    if frame.disc_from_left {
+        Some(PullSide::Left)
+    } else if frame.disc_from_right {
+        Some(PullSide::Right)
+    } else {
+        None
+    }
 }
@@ -1,6 +1,10 @@
+use crate::pipeline::crop_cache;
+use crate::pipeline::crop_store::{self, CROPS_DIR};
+use crate::pipeline::detections_store::{DetectionsWriter, DATA_FILE};
+use crate::pipeline::parquet_export;
 use crate::pipeline::types::{
-    polygon_to_compact, CompactCropData, CompactDetection, CompactDetectionFile, CompactFrameData,
-    CompactRegion, DetectedFrame, ProcessingState,
+    polygon_to_compact, CompactCropData, CompactDetection, CompactFrameData, CompactRegion,
+    DetectedFrame, ProcessingState,
 };
 use anyhow::Result;
 use crossbeam::channel::Receiver;
@@ -159,7 +163,7 @@ pub fn draw_annotations(
 }
 
 /// Convert a DetectedFrame to CompactFrameData for optimized JSON output
-fn convert_to_compact(frame: &DetectedFrame) -> CompactFrameData {
+pub fn convert_to_compact(frame: &DetectedFrame) -> CompactFrameData {
     let mut crops = HashMap::new();
 
     for result in &frame.results {
@@ -216,14 +220,20 @@ pub fn finalize_worker(
     rx: Receiver<DetectedFrame>,
     output_dir: PathBuf,
     save_crops: bool,
+    run_metadata: HashMap<String, String>,
     state: Arc<ProcessingState>,
 ) -> Result<()> {
-    let crops_dir = output_dir.join("crops");
+    let crops_dir = output_dir.join(CROPS_DIR);
+    // Crops regenerated for the previous processing may no longer match
+    crop_cache::clear(&output_dir)?;
     if save_crops {
+        // A pack compacted from an earlier run would shadow the new crops
+        crop_store::reset(&output_dir)?;
         let _ = fs::create_dir_all(&crops_dir);
     }
 
-    let mut compact_file = CompactDetectionFile::new();
+    let mut detections = DetectionsWriter::create(&output_dir)?;
+    let mut detections_parquet = parquet_export::detections_table(&output_dir, &run_metadata)?;
 
     // Create CSV writer for detection summaries
     let summary_path = output_dir.join("detection_summary.csv");
@@ -252,6 +262,18 @@ pub fn finalize_worker(
         "left_region_kept",
         "right_region_kept",
         "field_region_kept",
+        "nms_strategy",
+        "merge_strategy",
+        "overview_decayed",
+        "left_decayed",
+        "right_decayed",
+        "merge_decayed",
+        "merge_fused",
+        "overview_tile_size",
+        "left_tile_size",
+        "right_tile_size",
+        "tiles_run",
+        "tiles_skipped",
     ])?;
 
     tracing::info!(
@@ -260,8 +282,6 @@ pub fn finalize_worker(
         save_crops,
     );
 
-    let target_filename = "detections.json";
-
     for frame in rx {
         let start_inst = Instant::now();
 
@@ -323,6 +343,39 @@ pub fn finalize_worker(
                 &summary.left_kept.to_string(),
                 &summary.right_kept.to_string(),
                 &summary.field_kept.to_string(),
+                &overview
+                    .or(left)
+                    .or(right)
+                    .map(|s| s.strategy.clone())
+                    .unwrap_or_default(),
+                &merge.map(|s| s.strategy.clone()).unwrap_or_default(),
+                &overview
+                    .map(|s| s.decayed_count.to_string())
+                    .unwrap_or_default(),
+                &left
+                    .map(|s| s.decayed_count.to_string())
+                    .unwrap_or_default(),
+                &right
+                    .map(|s| s.decayed_count.to_string())
+                    .unwrap_or_default(),
+                &merge
+                    .map(|s| s.decayed_count.to_string())
+                    .unwrap_or_default(),
+                &merge.map(|s| s.fused_count.to_string()).unwrap_or_default(),
+                &summary
+                    .overview_tile_size
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                &summary
+                    .left_tile_size
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                &summary
+                    .right_tile_size
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                &summary.tiles_run.to_string(),
+                &summary.tiles_skipped.to_string(),
             ])?;
         }
 
@@ -340,38 +393,34 @@ pub fn finalize_worker(
             }
         }
 
-        // Convert to compact format
-        let compact_frame = convert_to_compact(&frame);
-
-        compact_file.frames.push(compact_frame);
+        // Convert to compact format and append to the detections store
+        detections.append(&convert_to_compact(&frame))?;
+        for row in parquet_export::detection_rows(&frame) {
+            detections_parquet.push(row)?;
+        }
 
         let duration_ms = start_inst.elapsed().as_secs_f64() * 1000.0;
         state.update_stage("finalize", 1, duration_ms);
 
-        // Periodically save
-        if !compact_file.frames.is_empty() && compact_file.frames.len().is_multiple_of(25) {
-            let results_path = output_dir.join(target_filename);
-            let json = serde_json::to_string(&compact_file).unwrap_or_default();
-            let _ = fs::write(results_path, json);
+        // Periodically make the appended frames visible to the dashboard
+        if detections.frames().is_multiple_of(25) {
+            detections.flush()?;
         }
     }
 
+    detections.flush()?;
     tracing::info!(
-        "Finalize worker finished processing {} frames. Saving {}...",
-        compact_file.frames.len(),
-        target_filename
+        "Finalize worker finished processing {} frames. Saved {:?}",
+        detections.frames(),
+        output_dir.join(DATA_FILE)
     );
 
-    // Save final detections in compact format
-    let results_path = output_dir.join(target_filename);
-    let json = serde_json::to_string_pretty(&compact_file)?;
-    fs::write(&results_path, json)?;
-    tracing::info!("Saved final {} to {:?}", target_filename, results_path);
-
     // Flush detection summary CSV
     summary_writer.flush()?;
     tracing::info!("Saved detection summary to {:?}", summary_path);
 
+    detections_parquet.close()?;
+
     state.is_complete.store(true, Ordering::Relaxed);
     state.is_active.store(false, Ordering::Relaxed);
 
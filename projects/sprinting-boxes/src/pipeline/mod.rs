@@ -1,9 +1,15 @@
 // Video processing pipeline workers
 
 pub mod crop;
+pub mod crop_cache;
+pub mod crop_store;
 pub mod detection_worker;
+pub mod detections_store;
 pub mod feature;
 pub mod finalize;
 pub mod orchestrator;
+pub mod parquet_export;
+pub mod preprocess;
 pub mod reader;
+pub mod snapshot;
 pub mod types;
@@ -3,6 +3,9 @@
 // Coordinates reader and crop workers, tracks processing state,
 // and provides SSE progress streaming.
 
+use crate::config::DETECTOR_CONFIG_FILE;
+use crate::detection::DETECTOR_MODEL_PATH;
+use crate::pipeline::snapshot::{ModelInfo, ProcessingSnapshot};
 pub use crate::pipeline::types::ProcessingState;
 use crate::run_context::RunContext;
 use crate::video::VideoReader;
@@ -25,7 +28,12 @@ pub struct DetectionControl {
     pub result_tx:
         Arc<RwLock<Option<crossbeam::channel::Sender<crate::pipeline::types::DetectedFrame>>>>,
     pub min_conf: f32,
+    pub disc_min_conf: f32,
     pub slice_conf: crate::detection::slicing::SliceConfig,
+    pub tiling: crate::detection::slicing::TilingPolicy,
+    pub cascade: Option<crate::detection::cascade::CascadeConfig>,
+    pub pose: Option<crate::detection::pose::PoseConfig>,
+    pub stance: ultimate_event_detection::StanceConfig,
     pub target_count: std::sync::Arc<std::sync::atomic::AtomicUsize>,
     pub regions_to_detect: Option<Vec<String>>,
 }
@@ -47,7 +55,8 @@ pub struct CropControl {
     pub result_tx:
         Arc<RwLock<Option<crossbeam::channel::Sender<crate::pipeline::types::PreprocessedFrame>>>>,
     pub configs: Arc<Vec<crate::pipeline::types::CropConfig>>,
-    pub enable_clahe: bool,
+    pub preprocess: Arc<crate::run_artifacts::PreprocessConfig>,
+    pub lens: Option<crate::video::lens::LensProfile>,
     pub target_count: std::sync::Arc<std::sync::atomic::AtomicUsize>,
 }
 
@@ -128,6 +137,8 @@ pub fn start_processing(
     let crops = run_context.load_crop_configs()?;
     let pipeline_configs: Vec<crate::pipeline::types::CropConfig> = (&crops).into();
     let configs = Arc::new(pipeline_configs);
+    let preprocess = run_context.load_preprocess_config()?;
+    preprocess.validate()?;
 
     if !video_path.exists() {
         return Err(anyhow::anyhow!("Video file NOT FOUND at: {:?}", video_path));
@@ -170,9 +181,13 @@ pub fn start_processing(
         total_units,
     ));
 
-    // Detection config (use function argument)
-    let min_conf = 0.5;
-    let slice_config = crate::detection::slicing::SliceConfig::new(640, 0.2);
+    // Detection config; people are kept down to the lowest `counting` threshold,
+    // and the feature worker applies each region's own
+    let min_conf = run_context.counting.detector_min_conf();
+    let disc_min_conf = run_context.disc_tracking.min_conf;
+    let slice_config = crate::detection::slicing::SliceConfig::new(640, 0.2)
+        .with_strategies(run_context.nms.strategy, run_context.nms.merge_strategy);
+    let stance = run_context.stance.config_for(run_context.game_format);
 
     // Channels
     // Single reader: the bottleneck is the crop/detect workers downstream, not decoding.
@@ -212,11 +227,52 @@ pub fn start_processing(
         ])
     };
 
+    // Record the effective parameters before any worker starts
+    let detector_config = crate::config::DetectorConfig::from_file(DETECTOR_CONFIG_FILE);
+    let mut models = vec![ModelInfo::new("detector", DETECTOR_MODEL_PATH)];
+    if let Some(pose) = &run_context.pose {
+        models.push(ModelInfo::new("pose", &pose.model_path));
+    }
+    let snapshot = ProcessingSnapshot {
+        captured_at: chrono::Utc::now(),
+        producer_version: env!("CARGO_PKG_VERSION").to_string(),
+        git_version: crate::pipeline::snapshot::git_version(),
+        backend: backend.to_string(),
+        fast,
+        regions_to_detect: regions_to_detect.clone().unwrap_or_default(),
+        sample_rate,
+        game_format: run_context.game_format.as_str().to_string(),
+        min_conf,
+        disc_min_conf,
+        slice: slice_config.clone(),
+        tiling: run_context.tiling,
+        cascade: run_context.cascade,
+        pose: run_context.pose.clone(),
+        lens: run_context.lens,
+        counting: run_context.counting.clone(),
+        pull_side: run_context.pull_side.clone(),
+        stance: crate::scoring::StanceSettings::from(&stance),
+        disc_tracking: run_context.disc_tracking.clone(),
+        preprocess: preprocess.clone(),
+        detector_config: detector_config.clone(),
+        detector_config_path: std::fs::canonicalize(DETECTOR_CONFIG_FILE)
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned()),
+        models,
+        crops_hash: crate::run_artifacts::config_hash(&[&serde_json::to_vec(&crops)?]),
+    };
+    snapshot.save(&run_context.output_dir)?;
+
     let detect_control = Arc::new(DetectionControl {
         source_rx: rx_c.clone(),
         result_tx: Arc::new(RwLock::new(Some(tx_d))),
         min_conf,
+        disc_min_conf,
         slice_conf: slice_config,
+        tiling: run_context.tiling,
+        cascade: run_context.cascade,
+        pose: run_context.pose.clone(),
+        stance: stance.clone(),
         target_count: target_detect.clone(),
         regions_to_detect,
     });
@@ -225,7 +281,8 @@ pub fn start_processing(
         source_rx: rx_v.clone(),
         result_tx: Arc::new(RwLock::new(Some(tx_c.clone()))),
         configs: configs.clone(),
-        enable_clahe: true,
+        preprocess: Arc::new(preprocess.clone()),
+        lens: run_context.lens,
         target_count: target_crop.clone(),
     });
 
@@ -249,13 +306,32 @@ pub fn start_processing(
     let (tx_f, rx_f) = crossbeam::channel::bounded(8);
     let state_feat = state.clone();
     let output_dir_feat = run_context.output_dir.clone();
-    let team_size = run_context.team_size as usize;
+    let game_format = run_context.game_format;
+    let counting = run_context.counting.clone();
+    let pull_side = ultimate_event_detection::PullSideConfig::from(&run_context.pull_side);
+    let disc_tracking = run_context.disc_tracking.config_for(sample_rate);
+    let run_metadata = crate::pipeline::parquet_export::run_metadata(&run_context);
+    let run_metadata_f = run_metadata.clone();
+    // Letterbox padding moves the overview's regions; use each frame's own then
+    let regions = match preprocess.letterbox("overview") {
+        Some(_) => None,
+        None => crate::geometry::PreparedRegions::from_crop_configs(&configs),
+    };
     thread::spawn(move || {
         let config = crate::pipeline::feature::FeatureConfig {
-            team_size,
+            game_format,
             lookback_frames: 10,
             lookahead_frames: 15,
             output_dir: output_dir_feat,
+            pull_side,
+            team_size_estimation: ultimate_event_detection::TeamSizeConfig::default(),
+            regions,
+            stance,
+            disc_tracking: Some(disc_tracking),
+            bystanders: Some(ultimate_event_detection::BystanderConfig::default()),
+            counting,
+            run_metadata,
+            detector: detector_config,
         };
         if let Err(e) = crate::pipeline::feature::feature_worker(rx_d, tx_f, config, state_feat) {
             tracing::error!("Feature worker failed: {}", e);
@@ -265,16 +341,33 @@ pub fn start_processing(
     // Spawn 5: Finalize
     let state_f = state.clone();
     let output_dir = run_context.output_dir.clone();
+    let config_hash = run_context.config_hash();
+    let crop_retention = run_context.crop_retention.clone();
+    let sample_rate = run_context.sample_rate;
     let save_visuals = std::env::var("SAVE_VISUAL_CROPS")
         .map(|v| v == "true" || v == "1")
         .unwrap_or(true);
 
     thread::spawn(move || {
-        if let Err(e) =
-            crate::pipeline::finalize::finalize_worker(rx_f, output_dir, save_visuals, state_f)
-        {
+        if let Err(e) = crate::pipeline::finalize::finalize_worker(
+            rx_f,
+            output_dir.clone(),
+            save_visuals,
+            run_metadata_f,
+            state_f,
+        ) {
             tracing::error!("Finalize worker failed: {}", e);
         }
+        // The feature worker has closed its outputs by the time finalize drains
+        if let Err(e) = crate::run_artifacts::record_pipeline_outputs(&output_dir, &config_hash) {
+            tracing::warn!("Failed to record pipeline outputs in the manifest: {}", e);
+        }
+        if save_visuals && !crop_retention.keeps_everything() {
+            match crate::pipeline::crop_store::compact(&output_dir, &crop_retention, sample_rate) {
+                Ok(report) => tracing::info!("Compacted crops: {:?}", report),
+                Err(e) => tracing::warn!("Failed to compact crops: {}", e),
+            }
+        }
     });
 
     // Spawn 6: Supervisor (handles stage completion and channel closing)
@@ -373,7 +466,12 @@ fn spawn_detection_worker(state: Arc<ProcessingState>, control: Arc<DetectionCon
     thread::spawn(move || {
         let params = crate::pipeline::detection_worker::DetectionParams {
             min_conf: control.min_conf,
+            disc_min_conf: control.disc_min_conf,
             slice_config: control.slice_conf.clone(),
+            tiling: control.tiling,
+            cascade: control.cascade,
+            pose: control.pose.clone(),
+            stance: control.stance.clone(),
             regions_to_detect: control.regions_to_detect.clone(),
         };
 
@@ -403,7 +501,8 @@ fn spawn_crop_worker(state: Arc<ProcessingState>, control: Arc<CropControl>) {
             control.source_rx.clone(),
             tx_c,
             control.configs.clone(),
-            control.enable_clahe,
+            control.preprocess.clone(),
+            control.lens,
             state.clone(),
             control.target_count.clone(),
         );
@@ -521,7 +620,12 @@ mod tests {
             source_rx: rx_c,
             result_tx: Arc::new(RwLock::new(Some(tx_d))),
             min_conf: 0.5,
+            disc_min_conf: 0.3,
             slice_conf: SliceConfig::new(416, 0.2),
+            tiling: Default::default(),
+            cascade: None,
+            pose: None,
+            stance: Default::default(),
             target_count: target_detect.clone(),
             regions_to_detect: None,
         });
@@ -530,7 +634,8 @@ mod tests {
             source_rx: rx_v,
             result_tx: Arc::new(RwLock::new(Some(tx_c))),
             configs: Arc::new(vec![]),
-            enable_clahe: true,
+            preprocess: Arc::new(Default::default()),
+            lens: None,
             target_count: target_crop.clone(),
         });
 
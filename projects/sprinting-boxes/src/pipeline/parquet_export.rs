@@ -0,0 +1,394 @@
+//! Typed Parquet exports of per-frame features, detections and cliffs.
+//!
+//! Written alongside the CSVs from the same values, without rounding:
+//! `features.parquet`, `detections.parquet` (one row per box) and `cliffs.parquet`.
+//! Missing values are nulls rather than the CSVs' `-1` sentinels. Every file's
+//! schema carries the run's metadata (see `run_metadata`), so a file copied out of
+//! its run directory still says where it came from.
+
+use crate::pipeline::types::DetectedFrame;
+use crate::run_context::RunContext;
+use anyhow::Result;
+use arrow::array::{ArrayRef, BooleanArray, Float32Array, StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows buffered before they are written out as one row group
+const ROW_GROUP_ROWS: usize = 8192;
+
+/// Prefix of the run metadata keys in each file's schema
+const METADATA_PREFIX: &str = "sprinting_boxes.";
+
+/// One cell of a row
+pub enum Value {
+    UInt64(u64),
+    Float32(Option<f32>),
+    Boolean(Option<bool>),
+    Utf8(Option<String>),
+}
+
+impl From<usize> for Value {
+    fn from(v: usize) -> Self {
+        Value::UInt64(v as u64)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Self {
+        Value::Float32(Some(v))
+    }
+}
+
+impl From<Option<f32>> for Value {
+    fn from(v: Option<f32>) -> Self {
+        Value::Float32(v)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Boolean(Some(v))
+    }
+}
+
+impl From<Option<bool>> for Value {
+    fn from(v: Option<bool>) -> Self {
+        Value::Boolean(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Utf8(Some(v.to_string()))
+    }
+}
+
+/// Buffered values of one column
+enum Column {
+    UInt64(Vec<u64>),
+    Float32(Vec<Option<f32>>),
+    Boolean(Vec<Option<bool>>),
+    Utf8(Vec<Option<String>>),
+}
+
+impl Column {
+    fn for_field(field: &Field) -> Result<Self> {
+        Ok(match field.data_type() {
+            DataType::UInt64 => Column::UInt64(Vec::new()),
+            DataType::Float32 => Column::Float32(Vec::new()),
+            DataType::Boolean => Column::Boolean(Vec::new()),
+            DataType::Utf8 => Column::Utf8(Vec::new()),
+            other => anyhow::bail!("Unsupported column type {} for {}", other, field.name()),
+        })
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (Column::UInt64(_), Value::UInt64(_))
+                | (Column::Float32(_), Value::Float32(_))
+                | (Column::Boolean(_), Value::Boolean(_))
+                | (Column::Utf8(_), Value::Utf8(_))
+        )
+    }
+
+    fn push(&mut self, value: Value) {
+        match (self, value) {
+            (Column::UInt64(values), Value::UInt64(v)) => values.push(v),
+            (Column::Float32(values), Value::Float32(v)) => values.push(v),
+            (Column::Boolean(values), Value::Boolean(v)) => values.push(v),
+            (Column::Utf8(values), Value::Utf8(v)) => values.push(v),
+            _ => unreachable!("checked by accepts"),
+        }
+    }
+
+    fn take(&mut self) -> ArrayRef {
+        match self {
+            Column::UInt64(values) => Arc::new(UInt64Array::from(std::mem::take(values))),
+            Column::Float32(values) => Arc::new(Float32Array::from(std::mem::take(values))),
+            Column::Boolean(values) => Arc::new(BooleanArray::from(std::mem::take(values))),
+            Column::Utf8(values) => Arc::new(StringArray::from(std::mem::take(values))),
+        }
+    }
+}
+
+/// A Parquet file written row by row, a row group at a time. The file is only
+/// readable once `close` has written its footer.
+pub struct ParquetTable {
+    schema: SchemaRef,
+    writer: ArrowWriter<File>,
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+impl ParquetTable {
+    pub fn create(
+        path: &Path,
+        fields: Vec<Field>,
+        metadata: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let columns = fields
+            .iter()
+            .map(Column::for_field)
+            .collect::<Result<Vec<_>>>()?;
+        let schema = Arc::new(Schema::new(fields).with_metadata(metadata.clone()));
+
+        // Also in the Parquet key-value metadata, for readers that ignore the Arrow schema
+        let key_values = metadata
+            .iter()
+            .map(|(k, v)| KeyValue::new(k.clone(), v.clone()))
+            .collect();
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .set_key_value_metadata(Some(key_values))
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props))?;
+
+        Ok(Self {
+            schema,
+            writer,
+            columns,
+            rows: 0,
+        })
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) -> Result<()> {
+        if row.len() != self.columns.len() {
+            anyhow::bail!(
+                "Row has {} values for {} columns",
+                row.len(),
+                self.columns.len()
+            );
+        }
+        // Check the whole row first, so a bad row leaves the columns the same length
+        let fields = self.schema.fields();
+        for ((column, value), field) in self.columns.iter().zip(&row).zip(fields) {
+            if !column.accepts(value) {
+                anyhow::bail!("Value does not match the type of column {}", field.name());
+            }
+        }
+        for (column, value) in self.columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+        self.rows += 1;
+        if self.rows >= ROW_GROUP_ROWS {
+            self.write_rows()?;
+        }
+        Ok(())
+    }
+
+    fn write_rows(&mut self) -> Result<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let arrays = self.columns.iter_mut().map(Column::take).collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+        self.writer.write(&batch)?;
+        self.writer.flush()?;
+        self.rows = 0;
+        Ok(())
+    }
+
+    pub fn close(mut self) -> Result<()> {
+        self.write_rows()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+/// Run metadata for the exports' schemas: the whole `metadata.json` plus the
+/// fields needed to turn frame indices into times.
+pub fn run_metadata(run_context: &RunContext) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    let mut insert = |key: &str, value: String| {
+        metadata.insert(format!("{}{}", METADATA_PREFIX, key), value);
+    };
+    insert("run_id", run_context.run_id.clone());
+    insert("display_name", run_context.display_name.clone());
+    insert("team_size", run_context.game_format.team_size().to_string());
+    insert("sample_rate", run_context.sample_rate.to_string());
+    insert("fps", run_context.fps.to_string());
+    insert(
+        "metadata",
+        serde_json::to_string(run_context).unwrap_or_default(),
+    );
+    metadata
+}
+
+pub fn features_table(dir: &Path, metadata: &HashMap<String, String>) -> Result<ParquetTable> {
+    let f32_field = |name: &str, nullable: bool| Field::new(name, DataType::Float32, nullable);
+    let bool_field = |name: &str, nullable: bool| Field::new(name, DataType::Boolean, nullable);
+    let fields = vec![
+        Field::new("frame_index", DataType::UInt64, false),
+        f32_field("left_count", false),
+        f32_field("right_count", false),
+        f32_field("field_count", false),
+        f32_field("pre_point_score", false),
+        bool_field("is_cliff", false),
+        f32_field("com_x", true),
+        f32_field("com_y", true),
+        f32_field("distribution_std_dev", true),
+        f32_field("com_delta_x", true),
+        f32_field("com_delta_y", true),
+        f32_field("std_dev_delta", true),
+        Field::new("excluded_polygon", DataType::UInt64, false),
+        Field::new("excluded_stationary", DataType::UInt64, false),
+        Field::new("excluded_perspective", DataType::UInt64, false),
+        f32_field("left_lined_up", true),
+        f32_field("right_lined_up", true),
+        bool_field("left_throwing", true),
+        bool_field("right_throwing", true),
+        bool_field("disc_from_left", false),
+        bool_field("disc_from_right", false),
+        f32_field("left_expected", false),
+        f32_field("right_expected", false),
+        f32_field("field_expected", false),
+        f32_field("left_variance", false),
+        f32_field("right_variance", false),
+        f32_field("field_variance", false),
+    ];
+    ParquetTable::create(&dir.join("features.parquet"), fields, metadata)
+}
+
+pub fn feature_row(frame: &DetectedFrame) -> Vec<Value> {
+    vec![
+        frame.id.into(),
+        frame.left_count.into(),
+        frame.right_count.into(),
+        frame.field_count.into(),
+        frame.pre_point_score.into(),
+        frame.is_cliff.into(),
+        frame.com_x.into(),
+        frame.com_y.into(),
+        frame.std_dev.into(),
+        frame.com_delta_x.into(),
+        frame.com_delta_y.into(),
+        frame.std_dev_delta.into(),
+        frame.excluded.polygon.into(),
+        frame.excluded.stationary.into(),
+        frame.excluded.perspective.into(),
+        frame.left_lined_up.into(),
+        frame.right_lined_up.into(),
+        frame.left_throwing.into(),
+        frame.right_throwing.into(),
+        frame.disc_from_left.into(),
+        frame.disc_from_right.into(),
+        frame.expected.left.into(),
+        frame.expected.right.into(),
+        frame.expected.field.into(),
+        frame.expected.left_variance.into(),
+        frame.expected.right_variance.into(),
+        frame.expected.field_variance.into(),
+    ]
+}
+
+pub fn cliffs_table(dir: &Path, metadata: &HashMap<String, String>) -> Result<ParquetTable> {
+    let fields = vec![
+        Field::new("frame_index", DataType::UInt64, false),
+        Field::new("left_side_emptied_first", DataType::Boolean, false),
+        Field::new("right_side_emptied_first", DataType::Boolean, false),
+        Field::new("pull_side_confidence", DataType::Float32, false),
+        Field::new("maybe_false_positive", DataType::Boolean, false),
+    ];
+    ParquetTable::create(&dir.join("cliffs.parquet"), fields, metadata)
+}
+
+pub fn cliff_row(frame: &DetectedFrame) -> Vec<Value> {
+    vec![
+        frame.id.into(),
+        frame.left_emptied_first.into(),
+        frame.right_emptied_first.into(),
+        frame.pull_side_confidence.into(),
+        frame.maybe_false_positive.into(),
+    ]
+}
+
+pub fn detections_table(dir: &Path, metadata: &HashMap<String, String>) -> Result<ParquetTable> {
+    let fields = vec![
+        Field::new("frame_index", DataType::UInt64, false),
+        Field::new("crop", DataType::Utf8, false),
+        Field::new("class", DataType::Utf8, true),
+        Field::new("x", DataType::Float32, false),
+        Field::new("y", DataType::Float32, false),
+        Field::new("w", DataType::Float32, false),
+        Field::new("h", DataType::Float32, false),
+        Field::new("confidence", DataType::Float32, false),
+        Field::new("in_end_zone", DataType::Boolean, false),
+        Field::new("in_field", DataType::Boolean, false),
+    ];
+    ParquetTable::create(&dir.join("detections.parquet"), fields, metadata)
+}
+
+/// One row per person box in each crop, then one per disc (in overview pixels).
+pub fn detection_rows(frame: &DetectedFrame) -> Vec<Vec<Value>> {
+    let people = frame
+        .results
+        .iter()
+        .flat_map(|r| r.detections.iter().map(move |d| (r.suffix.as_str(), d)));
+    let discs = frame.discs.iter().map(|d| ("overview", d));
+    people
+        .chain(discs)
+        .map(|(crop, d)| {
+            vec![
+                frame.id.into(),
+                crop.into(),
+                Value::Utf8(d.class_name.clone()),
+                d.bbox.x.into(),
+                d.bbox.y.into(),
+                d.bbox.w.into(),
+                d.bbox.h.into(),
+                d.confidence.into(),
+                d.in_end_zone.into(),
+                d.in_field.into(),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn writes_typed_rows_with_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("table.parquet");
+        let metadata = HashMap::from([(format!("{}run_id", METADATA_PREFIX), "r1".to_string())]);
+        let fields = vec![
+            Field::new("frame_index", DataType::UInt64, false),
+            Field::new("com_x", DataType::Float32, true),
+        ];
+
+        let mut table = ParquetTable::create(&path, fields, &metadata).unwrap();
+        table
+            .push(vec![0usize.into(), Some(0.25f32).into()])
+            .unwrap();
+        table.push(vec![1usize.into(), None::<f32>.into()]).unwrap();
+        assert!(table.push(vec![2usize.into()]).is_err());
+        assert!(table.push(vec![true.into(), 0.5f32.into()]).is_err());
+        table.close().unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(builder.schema().metadata(), &metadata);
+        let batches: Vec<_> = builder.build().unwrap().collect::<Result<_, _>>().unwrap();
+        let com_x = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(com_x.value(0), 0.25);
+        assert!(com_x.is_null(1));
+    }
+}
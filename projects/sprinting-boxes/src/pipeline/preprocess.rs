@@ -0,0 +1,305 @@
+//! Crop-stage image preprocessing.
+//!
+//! Each crop runs through the chain configured for it in `preprocess.json`
+//! (see `PreprocessConfig`) before detection. Dusk games and backlit fields
+//! need more than the CLAHE pass that used to be hardwired here.
+
+use crate::pipeline::types::BBox;
+use crate::run_artifacts::PreprocessStep;
+use anyhow::Result;
+use opencv::prelude::*;
+use opencv::{core, imgproc, photo};
+
+/// Gray used for letterbox padding, as in the YOLO input pipeline
+const PAD_VALUE: f64 = 114.0;
+
+/// Lower bound on the haze transmission, so dense haze is not amplified into noise
+const MIN_TRANSMISSION: f64 = 0.1;
+
+/// Where a letterboxed crop's content sits in the padded image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Letterbox {
+    /// Padded image size, i.e. the model input
+    pub width: i32,
+    pub height: i32,
+    /// Resized content size
+    pub content_w: i32,
+    pub content_h: i32,
+    /// Offset of the content in the padded image
+    pub pad_x: i32,
+    pub pad_y: i32,
+}
+
+impl Letterbox {
+    /// Fit a `src_w` x `src_h` image into `width` x `height`, centered.
+    pub fn fit(src_w: i32, src_h: i32, width: i32, height: i32) -> Self {
+        let scale = (width as f32 / src_w.max(1) as f32).min(height as f32 / src_h.max(1) as f32);
+        let content_w = ((src_w as f32 * scale).round() as i32).clamp(1, width);
+        let content_h = ((src_h as f32 * scale).round() as i32).clamp(1, height);
+        Self {
+            width,
+            height,
+            content_w,
+            content_h,
+            pad_x: (width - content_w) / 2,
+            pad_y: (height - content_h) / 2,
+        }
+    }
+
+    /// The normalized frame area the padded image spans, given the area its
+    /// content was cropped from. Crop geometry stays consistent when it is
+    /// computed against this bbox instead of the crop config's.
+    pub fn expand(&self, content: &BBox) -> BBox {
+        let px_w = content.w / self.content_w as f32;
+        let px_h = content.h / self.content_h as f32;
+        BBox {
+            x: content.x - self.pad_x as f32 * px_w,
+            y: content.y - self.pad_y as f32 * px_h,
+            w: self.width as f32 * px_w,
+            h: self.height as f32 * px_h,
+        }
+    }
+}
+
+/// Run a preprocessing chain on a crop. Returns the processed image and, when
+/// the chain ends in a letterbox, where the content sits in it.
+pub fn apply_chain(
+    img: &core::Mat,
+    steps: &[PreprocessStep],
+) -> Result<(core::Mat, Option<Letterbox>)> {
+    let mut out = img.clone();
+    let mut letterbox = None;
+    for step in steps {
+        out = match *step {
+            PreprocessStep::Clahe {
+                clip_limit,
+                tile_grid,
+            } => clahe(&out, clip_limit, tile_grid)?,
+            PreprocessStep::Gamma { gamma } => gamma_correct(&out, gamma)?,
+            PreprocessStep::WhiteBalance => white_balance(&out)?,
+            PreprocessStep::Denoise { strength } => denoise(&out, strength)?,
+            PreprocessStep::Sharpen { amount, sigma } => sharpen(&out, amount, sigma)?,
+            PreprocessStep::Dehaze { strength, window } => dehaze(&out, strength, window)?,
+            PreprocessStep::Letterbox { width, height } => {
+                let size = out.size()?;
+                let placement = Letterbox::fit(size.width, size.height, width, height);
+                letterbox = Some(placement);
+                pad_to(&out, &placement)?
+            }
+        };
+    }
+    Ok((out, letterbox))
+}
+
+/// Apply CLAHE (Contrast Limited Adaptive Histogram Equalization) to enhance visibility
+/// of dark objects in shadows. This helps detect people in dark uniforms.
+fn clahe(img: &core::Mat, clip_limit: f64, tile_grid: i32) -> Result<core::Mat> {
+    let mut lab = core::Mat::default();
+    imgproc::cvt_color(
+        img,
+        &mut lab,
+        imgproc::COLOR_BGR2Lab,
+        0,
+        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+
+    let mut channels = core::Vector::<core::Mat>::new();
+    core::split(&lab, &mut channels)?;
+
+    let mut clahe = imgproc::create_clahe(clip_limit, core::Size::new(tile_grid, tile_grid))?;
+    let mut l_enhanced = core::Mat::default();
+    clahe.apply(&channels.get(0)?, &mut l_enhanced)?;
+
+    channels.set(0, l_enhanced)?;
+
+    let mut lab_enhanced = core::Mat::default();
+    core::merge(&channels, &mut lab_enhanced)?;
+
+    let mut result = core::Mat::default();
+    imgproc::cvt_color(
+        &lab_enhanced,
+        &mut result,
+        imgproc::COLOR_Lab2BGR,
+        0,
+        core::AlgorithmHint::ALGO_HINT_DEFAULT,
+    )?;
+
+    Ok(result)
+}
+
+/// Gamma curve through a lookup table; `gamma < 1` brightens shadows.
+fn gamma_correct(img: &core::Mat, gamma: f64) -> Result<core::Mat> {
+    let table: Vec<u8> = (0..256)
+        .map(|v| ((v as f64 / 255.0).powf(gamma) * 255.0).round() as u8)
+        .collect();
+    let lut = core::Mat::from_slice(&table)?.try_clone()?;
+    let mut result = core::Mat::default();
+    core::lut(img, &lut, &mut result)?;
+    Ok(result)
+}
+
+/// Gray-world white balance: scale each channel so its mean matches the
+/// overall mean. Removes the orange cast of low sun and sodium floodlights.
+fn white_balance(img: &core::Mat) -> Result<core::Mat> {
+    let means = core::mean(img, &core::no_array())?;
+    let gray = (means[0] + means[1] + means[2]) / 3.0;
+
+    let mut channels = core::Vector::<core::Mat>::new();
+    core::split(img, &mut channels)?;
+    let mut balanced = core::Vector::<core::Mat>::new();
+    for (i, channel) in channels.iter().enumerate() {
+        let gain = if means[i] > 0.0 { gray / means[i] } else { 1.0 };
+        let mut scaled = core::Mat::default();
+        channel.convert_to(&mut scaled, -1, gain, 0.0)?;
+        balanced.push(scaled);
+    }
+
+    let mut result = core::Mat::default();
+    core::merge(&balanced, &mut result)?;
+    Ok(result)
+}
+
+/// Non-local means denoising of luminance and color. The slowest step by far
+/// (tens of ms per megapixel), so keep it for grainy low-light footage.
+fn denoise(img: &core::Mat, strength: f32) -> Result<core::Mat> {
+    let mut result = core::Mat::default();
+    photo::fast_nl_means_denoising_colored(img, &mut result, strength, strength, 7, 21)?;
+    Ok(result)
+}
+
+/// Unsharp mask: `img + amount * (img - blur(img))`.
+fn sharpen(img: &core::Mat, amount: f64, sigma: f64) -> Result<core::Mat> {
+    let mut blurred = core::Mat::default();
+    imgproc::gaussian_blur_def(img, &mut blurred, core::Size::new(0, 0), sigma)?;
+    let mut result = core::Mat::default();
+    core::add_weighted(img, 1.0 + amount, &blurred, -amount, 0.0, &mut result, -1)?;
+    Ok(result)
+}
+
+/// Dark-channel-prior dehazing (He et al.). Haze lifts the darkest channel of
+/// every patch; the lift estimates how much airlight to subtract per pixel.
+fn dehaze(img: &core::Mat, strength: f64, window: i32) -> Result<core::Mat> {
+    let mut channels = core::Vector::<core::Mat>::new();
+    core::split(img, &mut channels)?;
+
+    // Dark channel: per-pixel minimum over channels, then over the patch
+    let mut min_bg = core::Mat::default();
+    core::min(&channels.get(0)?, &channels.get(1)?, &mut min_bg)?;
+    let mut min_bgr = core::Mat::default();
+    core::min(&min_bg, &channels.get(2)?, &mut min_bgr)?;
+    let kernel =
+        imgproc::get_structuring_element_def(imgproc::MORPH_RECT, core::Size::new(window, window))?;
+    let mut dark = core::Mat::default();
+    imgproc::erode_def(&min_bgr, &mut dark, &kernel)?;
+
+    // Airlight: the color at the haziest point
+    let mut max_loc = core::Point::default();
+    core::min_max_loc(
+        &dark,
+        None,
+        None,
+        None,
+        Some(&mut max_loc),
+        &core::no_array(),
+    )?;
+    let airlight = *img.at_2d::<core::Vec3b>(max_loc.y, max_loc.x)?;
+    let brightest = airlight.0.iter().copied().max().unwrap_or(255).max(1) as f64;
+
+    // Transmission: 1 - strength * dark / airlight, kept away from zero
+    let mut transmission = core::Mat::default();
+    dark.convert_to(&mut transmission, core::CV_32F, -strength / brightest, 1.0)?;
+    let mut clamped = core::Mat::default();
+    core::max(
+        &transmission,
+        &core::Scalar::all(MIN_TRANSMISSION),
+        &mut clamped,
+    )?;
+
+    // Scene radiance per channel: (I - A) / t + A
+    let mut restored = core::Vector::<core::Mat>::new();
+    for (i, channel) in channels.iter().enumerate() {
+        let a = airlight[i] as f64;
+        let mut shifted = core::Mat::default();
+        channel.convert_to(&mut shifted, core::CV_32F, 1.0, -a)?;
+        let mut divided = core::Mat::default();
+        core::divide2(&shifted, &clamped, &mut divided, 1.0, -1)?;
+        let mut out = core::Mat::default();
+        divided.convert_to(&mut out, core::CV_8U, 1.0, a)?;
+        restored.push(out);
+    }
+
+    let mut result = core::Mat::default();
+    core::merge(&restored, &mut result)?;
+    Ok(result)
+}
+
+/// Resize into the letterbox's content area and pad the rest with gray.
+fn pad_to(img: &core::Mat, letterbox: &Letterbox) -> Result<core::Mat> {
+    let mut resized = core::Mat::default();
+    imgproc::resize(
+        img,
+        &mut resized,
+        core::Size::new(letterbox.content_w, letterbox.content_h),
+        0.0,
+        0.0,
+        imgproc::INTER_AREA,
+    )?;
+
+    let mut result = core::Mat::default();
+    core::copy_make_border(
+        &resized,
+        &mut result,
+        letterbox.pad_y,
+        letterbox.height - letterbox.content_h - letterbox.pad_y,
+        letterbox.pad_x,
+        letterbox.width - letterbox.content_w - letterbox.pad_x,
+        core::BORDER_CONSTANT,
+        core::Scalar::all(PAD_VALUE),
+    )?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterbox_centers_content() {
+        // A wide end-zone crop into a square model input
+        let letterbox = Letterbox::fit(1280, 480, 640, 640);
+        assert_eq!((letterbox.content_w, letterbox.content_h), (640, 240));
+        assert_eq!((letterbox.pad_x, letterbox.pad_y), (0, 200));
+
+        // Upscaling keeps the aspect ratio too
+        let letterbox = Letterbox::fit(300, 400, 640, 640);
+        assert_eq!((letterbox.content_w, letterbox.content_h), (480, 640));
+        assert_eq!((letterbox.pad_x, letterbox.pad_y), (80, 0));
+    }
+
+    #[test]
+    fn expanded_bbox_maps_padded_pixels_to_the_frame() {
+        let letterbox = Letterbox::fit(1280, 480, 640, 640);
+        let content = BBox {
+            x: 0.1,
+            y: 0.5,
+            w: 0.4,
+            h: 0.15,
+        };
+        let padded = letterbox.expand(&content);
+
+        // The content's corners land where the crop config put them
+        let to_frame = |px: f32, py: f32| {
+            (
+                padded.x + px / letterbox.width as f32 * padded.w,
+                padded.y + py / letterbox.height as f32 * padded.h,
+            )
+        };
+        let (x0, y0) = to_frame(letterbox.pad_x as f32, letterbox.pad_y as f32);
+        let (x1, y1) = to_frame(
+            (letterbox.pad_x + letterbox.content_w) as f32,
+            (letterbox.pad_y + letterbox.content_h) as f32,
+        );
+        assert!((x0 - 0.1).abs() < 1e-6 && (y0 - 0.5).abs() < 1e-6);
+        assert!((x1 - 0.5).abs() < 1e-6 && (y1 - 0.65).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,268 @@
+//! Reproducibility record of a processing run.
+//!
+//! `start_processing` writes the effective parameters to `processing_config.json`
+//! before any worker starts: detection thresholds and slicing, crop preprocessing,
+//! cliff detector settings, the models with their checksums, and the build that
+//! ran them. Two runs' records can be compared with `diff`.
+
+use crate::config::DetectorConfig;
+use crate::detection::cascade::CascadeConfig;
+use crate::detection::pose::PoseConfig;
+use crate::detection::slicing::{SliceConfig, TilingPolicy};
+use crate::run_artifacts::{Manifest, PreprocessConfig};
+use crate::scoring::{CountingConfig, DiscTrackingSettings, PullSideSettings, StanceSettings};
+use crate::video::lens::LensProfile;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use walkdir::WalkDir;
+
+pub const SNAPSHOT_FILE: &str = "processing_config.json";
+
+/// Fields that differ between any two runs and say nothing about their configuration
+const IGNORED_IN_DIFF: [&str; 1] = ["captured_at"];
+
+/// A model file (or CoreML package directory) used by the run
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    /// "detector" or "pose"
+    pub role: String,
+    /// Path as configured, relative to the working directory
+    pub path: String,
+    /// SHA-256 of the file, or of a package's files in path order; `None` if
+    /// the model could not be read
+    pub sha256: Option<String>,
+}
+
+impl ModelInfo {
+    pub fn new(role: &str, path: &str) -> Self {
+        let sha256 = match checksum(Path::new(path)) {
+            Ok(sha256) => Some(sha256),
+            Err(e) => {
+                tracing::warn!("Failed to checksum {} model {}: {}", role, path, e);
+                None
+            }
+        };
+        Self {
+            role: role.to_string(),
+            path: path.to_string(),
+            sha256,
+        }
+    }
+}
+
+/// Effective processing parameters of a run, as stored in processing_config.json
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProcessingSnapshot {
+    pub captured_at: DateTime<Utc>,
+    /// sprinting-boxes version and `git describe` of the build
+    pub producer_version: String,
+    pub git_version: Option<String>,
+    /// Video reader backend ("ffmpeg" or "opencv")
+    pub backend: String,
+    /// End-zone-only detection
+    pub fast: bool,
+    pub regions_to_detect: Vec<String>,
+    pub sample_rate: f64,
+    pub game_format: String,
+    pub min_conf: f32,
+    pub disc_min_conf: f32,
+    pub slice: SliceConfig,
+    pub tiling: TilingPolicy,
+    pub cascade: Option<CascadeConfig>,
+    pub pose: Option<PoseConfig>,
+    pub lens: Option<LensProfile>,
+    pub counting: CountingConfig,
+    #[serde(default)]
+    pub pull_side: PullSideSettings,
+    /// Stance config of the pose stage after applying the run's overrides to
+    /// the game format's, with every field filled in
+    #[serde(default)]
+    pub stance: StanceSettings,
+    /// Disc tracking settings as in `metadata.json`, before scaling to `sample_rate`
+    #[serde(default)]
+    pub disc_tracking: DiscTrackingSettings,
+    pub preprocess: PreprocessConfig,
+    /// Cliff detector settings, and the file they were read from (`None` for the
+    /// built-in defaults). Only applied to 7s; other formats use their own.
+    pub detector_config: DetectorConfig,
+    pub detector_config_path: Option<String>,
+    pub models: Vec<ModelInfo>,
+    /// Hash of crops.json (see `run_artifacts::config_hash`)
+    pub crops_hash: String,
+}
+
+impl ProcessingSnapshot {
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::write(dir.join(SNAPSHOT_FILE), serde_json::to_string_pretty(self)?)?;
+        Manifest::record(dir, SNAPSHOT_FILE, None)
+    }
+
+    /// Loads the record in `dir`; `None` for runs processed before records existed.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(SNAPSHOT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+}
+
+/// `git describe` of the build, if it was built from a git checkout
+pub fn git_version() -> Option<String> {
+    option_env!("SPRINTING_BOXES_GIT_VERSION").map(str::to_string)
+}
+
+/// One differing parameter between two records
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// Dotted path of the parameter, e.g. `slice.nms_strategy` or `models.0.sha256`
+    pub path: String,
+    /// Value in the first and second record; `null` where a record lacks it
+    pub left: serde_json::Value,
+    pub right: serde_json::Value,
+}
+
+/// The parameters that differ between two records, in path order.
+pub fn diff(left: &ProcessingSnapshot, right: &ProcessingSnapshot) -> Result<Vec<ConfigChange>> {
+    let mut changes = Vec::new();
+    diff_values(
+        "",
+        &serde_json::to_value(left)?,
+        &serde_json::to_value(right)?,
+        &mut changes,
+    );
+    changes.retain(|c| !IGNORED_IN_DIFF.contains(&c.path.as_str()));
+    Ok(changes)
+}
+
+fn diff_values(
+    path: &str,
+    left: &serde_json::Value,
+    right: &serde_json::Value,
+    changes: &mut Vec<ConfigChange>,
+) {
+    use serde_json::Value;
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            let mut keys: Vec<&String> = l.keys().chain(r.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let l = l.get(key).unwrap_or(&Value::Null);
+                let r = r.get(key).unwrap_or(&Value::Null);
+                diff_values(&child(key), l, r, changes);
+            }
+        }
+        (Value::Array(l), Value::Array(r)) => {
+            for i in 0..l.len().max(r.len()) {
+                let l = l.get(i).unwrap_or(&Value::Null);
+                let r = r.get(i).unwrap_or(&Value::Null);
+                diff_values(&child(&i.to_string()), l, r, changes);
+            }
+        }
+        (l, r) if l != r => changes.push(ConfigChange {
+            path: path.to_string(),
+            left: l.clone(),
+            right: r.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// SHA-256 of a file, or of every file under a directory (relative path, then
+/// contents, in path order).
+fn checksum(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    if path.is_dir() {
+        for entry in WalkDir::new(path).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let relative = entry.path().strip_prefix(path)?;
+                hasher.update(relative.to_string_lossy().as_bytes());
+                hash_file(entry.path(), &mut hasher)?;
+            }
+        }
+    } else {
+        hash_file(path, &mut hasher)?;
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn hash_file(path: &Path, hasher: &mut Sha256) -> Result<()> {
+    let mut file = fs::File::open(path)?;
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> ProcessingSnapshot {
+        ProcessingSnapshot {
+            captured_at: Utc::now(),
+            producer_version: "0.1.0".to_string(),
+            git_version: None,
+            backend: "ffmpeg".to_string(),
+            fast: false,
+            regions_to_detect: vec!["overview".to_string(), "left".to_string()],
+            sample_rate: 1.0,
+            game_format: "sevens".to_string(),
+            min_conf: 0.5,
+            disc_min_conf: 0.3,
+            slice: SliceConfig::new(640, 0.2),
+            tiling: TilingPolicy::default(),
+            cascade: None,
+            pose: None,
+            lens: None,
+            counting: CountingConfig::default(),
+            pull_side: PullSideSettings::default(),
+            stance: StanceSettings::default(),
+            disc_tracking: DiscTrackingSettings::default(),
+            preprocess: PreprocessConfig::default(),
+            detector_config: DetectorConfig::default(),
+            detector_config_path: None,
+            models: vec![],
+            crops_hash: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_parameters() {
+        let left = snapshot();
+        let mut right = snapshot();
+        assert!(diff(&left, &right).unwrap().is_empty());
+
+        right.min_conf = 0.4;
+        right.regions_to_detect.pop();
+        right.detector_config.min_gap = 30;
+        let paths: Vec<String> = diff(&left, &right)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.path)
+            .collect();
+        assert_eq!(
+            paths,
+            ["detector_config.min_gap", "min_conf", "regions_to_detect.1"]
+        );
+    }
+}
@@ -5,6 +5,7 @@ use serde_json;
 use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::RwLock;
+use ultimate_event_detection::ExpectedCount;
 
 pub use crate::run_artifacts::{BBox, Point};
 
@@ -251,6 +252,17 @@ impl From<&CropsConfig> for Vec<CropConfig> {
                 },
             ],
         }];
+        // People inside these are detected but never counted
+        configs[0]
+            .regions
+            .extend(crops.exclusion_polygons.iter().map(|polygon| {
+                let polygon: Vec<Point> = polygon.iter().map(convert_point).collect();
+                RegionalPolygon {
+                    name: crate::geometry::EXCLUSION_REGION.to_string(),
+                    polygon: polygon.clone(),
+                    effective_polygon: polygon,
+                }
+            }));
 
         // Add left end-zone crop if configured
         if let Some(ref left_ez) = crops.left_end_zone {
@@ -385,6 +397,89 @@ pub struct CropResult {
     pub regions: Vec<RegionalPolygon>, // NEW: regions for downstream processing
 }
 
+impl CropResult {
+    /// Copy with the crop-local pixel coordinates (boxes and polygons) scaled by
+    /// `factor`, for drawing on a resized crop. `bbox` describes the crop itself
+    /// (its pixel size in the pipeline, its normalized source box when loaded from
+    /// `detections.jsonl`) rather than anything drawn on it, and is kept as is.
+    pub fn scaled(&self, factor: f32) -> Self {
+        let scale_polygon = |polygon: &[Point]| -> Vec<Point> {
+            polygon
+                .iter()
+                .map(|p| Point {
+                    x: p.x * factor,
+                    y: p.y * factor,
+                })
+                .collect()
+        };
+        Self {
+            suffix: self.suffix.clone(),
+            detections: self
+                .detections
+                .iter()
+                .map(|d| EnrichedDetection {
+                    bbox: BBox {
+                        x: d.bbox.x * factor,
+                        y: d.bbox.y * factor,
+                        w: d.bbox.w * factor,
+                        h: d.bbox.h * factor,
+                    },
+                    ..d.clone()
+                })
+                .collect(),
+            original_polygon: scale_polygon(&self.original_polygon),
+            effective_polygon: scale_polygon(&self.effective_polygon),
+            bbox: self.bbox,
+            image: None,
+            regions: self
+                .regions
+                .iter()
+                .map(|r| RegionalPolygon {
+                    name: r.name.clone(),
+                    polygon: scale_polygon(&r.polygon),
+                    effective_polygon: scale_polygon(&r.effective_polygon),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// People left out of a frame's counts, by reason
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ExcludedCounts {
+    /// Inside a user-drawn exclusion polygon
+    pub polygon: usize,
+    /// Standing in the same spot for most of the game so far
+    pub stationary: usize,
+    /// Box height inconsistent with the perspective learned from players
+    pub perspective: usize,
+}
+
+/// Expected player counts per region and their variances, normalized by team
+/// size like the integer counts
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ExpectedCounts {
+    pub left: f32,
+    pub right: f32,
+    pub field: f32,
+    pub left_variance: f32,
+    pub right_variance: f32,
+    pub field_variance: f32,
+}
+
+impl ExpectedCounts {
+    pub fn from_counts(left: ExpectedCount, right: ExpectedCount, field: ExpectedCount) -> Self {
+        Self {
+            left: left.expected,
+            right: right.expected,
+            field: field.expected,
+            left_variance: left.variance,
+            right_variance: right.variance,
+            field_variance: field.variance,
+        }
+    }
+}
+
 /// A frame after detection has been run
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DetectedFrame {
@@ -394,12 +489,18 @@ pub struct DetectedFrame {
     pub left_count: f32,
     pub right_count: f32,
     pub field_count: f32,
+    /// Uncertainty-aware counts the pre-point score is computed from
+    #[serde(default)]
+    pub expected: ExpectedCounts,
     pub pre_point_score: f32,
     pub is_cliff: bool,
     // Heuristic results
     pub left_emptied_first: bool,
     pub right_emptied_first: bool,
     pub maybe_false_positive: bool,
+    /// Confidence in [0, 1] of the pull-side estimate (cliff frames only)
+    #[serde(default)]
+    pub pull_side_confidence: f32,
     // CoM and StdDev features
     pub com_x: Option<f32>,
     pub com_y: Option<f32>,
@@ -407,6 +508,27 @@ pub struct DetectedFrame {
     pub com_delta_x: Option<f32>,
     pub com_delta_y: Option<f32>,
     pub std_dev_delta: Option<f32>,
+    /// Detections inside counting regions that were not counted
+    #[serde(default)]
+    pub excluded: ExcludedCounts,
+    /// Disc detections in overview pixels; never counted as players
+    #[serde(default)]
+    pub discs: Vec<EnrichedDetection>,
+    /// A disc is in flight, thrown from the left or right end zone
+    #[serde(default)]
+    pub disc_from_left: bool,
+    #[serde(default)]
+    pub disc_from_right: bool,
+    /// Ready-stance line-up score per end zone in [0, 1]; `None` without the pose stage
+    #[serde(default)]
+    pub left_lined_up: Option<f32>,
+    #[serde(default)]
+    pub right_lined_up: Option<f32>,
+    /// Someone in the left or right end zone is throwing; `None` without the pose stage
+    #[serde(default)]
+    pub left_throwing: Option<bool>,
+    #[serde(default)]
+    pub right_throwing: Option<bool>,
     // NMS statistics (optional, for debugging)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detection_summary: Option<DetectionSummary>,
@@ -477,6 +599,13 @@ pub trait BoundingBox {
     fn confidence(&self) -> f32;
 }
 
+/// Bounding boxes that can be rebuilt with new geometry and confidence
+/// Needed by strategies that decay scores (Soft-NMS) or fuse boxes (WBF)
+pub trait AdjustableBoundingBox: BoundingBox + Clone {
+    /// Copy of this box with the given geometry and confidence, other attributes unchanged
+    fn adjusted(&self, x: f32, y: f32, w: f32, h: f32, confidence: f32) -> Self;
+}
+
 /// Detailed statistics from NMS operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NmsStats {
@@ -488,6 +617,20 @@ pub struct NmsStats {
     pub close_but_kept_count: usize,
     /// Number of detections kept after NMS
     pub kept_count: usize,
+    /// Suppression strategy that produced these statistics (see `NmsStrategy::name`)
+    #[serde(default = "default_nms_strategy")]
+    pub strategy: String,
+    /// Soft-NMS only: kept detections whose confidence was decayed by an overlapping neighbour
+    #[serde(default)]
+    pub decayed_count: usize,
+    /// WBF only: kept detections fused from two or more input boxes
+    #[serde(default)]
+    pub fused_count: usize,
+}
+
+/// Statistics written before strategies were selectable came from hard NMS
+fn default_nms_strategy() -> String {
+    "hard".to_string()
 }
 
 /// Detection summary for a single frame
@@ -509,6 +652,21 @@ pub struct DetectionSummary {
     pub right_kept: usize,
     /// Number of detections kept in field region (after all NMS)
     pub field_kept: usize,
+    /// Tile edge in crop pixels used for the overview crop (None when not tiled)
+    #[serde(default)]
+    pub overview_tile_size: Option<u32>,
+    /// Tile edge in crop pixels used for the left end zone crop
+    #[serde(default)]
+    pub left_tile_size: Option<u32>,
+    /// Tile edge in crop pixels used for the right end zone crop
+    #[serde(default)]
+    pub right_tile_size: Option<u32>,
+    /// Tiles sent to the detector (excluding the cascade's coarse pass)
+    #[serde(default)]
+    pub tiles_run: usize,
+    /// Tiles whose previous detections were reused by the cascade
+    #[serde(default)]
+    pub tiles_skipped: usize,
 }
 
 impl BoundingBox for EnrichedDetection {
@@ -529,6 +687,16 @@ impl BoundingBox for EnrichedDetection {
     }
 }
 
+impl AdjustableBoundingBox for EnrichedDetection {
+    fn adjusted(&self, x: f32, y: f32, w: f32, h: f32, confidence: f32) -> Self {
+        Self {
+            bbox: BBox { x, y, w, h },
+            confidence,
+            ..self.clone()
+        }
+    }
+}
+
 /// Compact frame data - uses HashMap for crops instead of Vec
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactFrameData {
@@ -536,6 +704,77 @@ pub struct CompactFrameData {
     pub crops: std::collections::HashMap<String, CompactCropData>,
 }
 
+impl CompactFrameData {
+    /// Expands the stored crops back into `CropResult`s (without images or crop
+    /// polygons) for drawing annotations
+    pub fn into_crop_results(self) -> std::collections::HashMap<String, CropResult> {
+        let mut crops = std::collections::HashMap::new();
+        for (suffix, compact_crop) in self.crops {
+            let regions = if suffix == "overview" {
+                compact_crop.regions.clone()
+            } else {
+                None
+            };
+
+            let regions = if let Some(compact_regions) = regions {
+                compact_regions
+                    .iter()
+                    .map(|r| RegionalPolygon {
+                        name: r.name.clone(),
+                        polygon: compact_to_polygon(&r.polygon),
+                        effective_polygon: compact_to_polygon(&r.polygon),
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let detections = compact_crop
+                .detections
+                .iter()
+                .map(|d| EnrichedDetection {
+                    bbox: BBox {
+                        x: d.x,
+                        y: d.y,
+                        w: d.w,
+                        h: d.h,
+                    },
+                    confidence: d.confidence,
+                    class_id: 0,
+                    class_name: Some("person".to_string()),
+                    in_end_zone: d.in_end_zone,
+                    in_field: d.in_field,
+                })
+                .collect();
+
+            let source_bbox = if suffix == "left" || suffix == "right" {
+                compact_crop.source_bbox
+            } else {
+                None
+            };
+
+            crops.insert(
+                suffix.clone(),
+                CropResult {
+                    suffix,
+                    detections,
+                    original_polygon: Vec::new(),
+                    effective_polygon: Vec::new(),
+                    bbox: source_bbox.unwrap_or(BBox {
+                        x: 0.0,
+                        y: 0.0,
+                        w: 0.0,
+                        h: 0.0,
+                    }),
+                    image: None,
+                    regions,
+                },
+            );
+        }
+        crops
+    }
+}
+
 /// Compact detection file format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompactDetectionFile {
@@ -0,0 +1,357 @@
+// Team registry: the teams, seasons and tournaments games are played in
+//
+// `registry.json` in the output root is shared by all runs. A run refers to its
+// teams and tournament by id (`RunContext::game`) and keeps the free-text team
+// names as a copy, which is what runs without linked teams, and bundles taken
+// to another machine, fall back to. The audit, its exports and the run
+// catalogue take the names of linked teams from here.
+
+use crate::run_bundle::valid_run_id;
+use crate::run_context::RunContext;
+use anyhow::{Context as _, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+pub const REGISTRY_FILE: &str = "registry.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Registry {
+    #[serde(default)]
+    pub teams: Vec<Team>,
+    #[serde(default)]
+    pub seasons: Vec<Season>,
+    #[serde(default)]
+    pub tournaments: Vec<Tournament>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Team {
+    pub id: String,
+    pub name: String,
+    /// Other spellings of the name, matched against free-text names of unlinked runs
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Jersey colours, e.g. "#1f4e9c" or "navy"
+    #[serde(default)]
+    pub colors: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Season {
+    pub id: String,
+    pub name: String,
+    /// First and last day, inclusive
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tournament {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub season_id: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+/// The game a run records, in terms of the registry. Stored in `metadata.json`;
+/// nothing is linked by default.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GameInfo {
+    #[serde(default)]
+    pub light_team_id: Option<String>,
+    #[serde(default)]
+    pub dark_team_id: Option<String>,
+    #[serde(default)]
+    pub tournament_id: Option<String>,
+    /// Round within the tournament, e.g. "Pool A" or "Final"
+    #[serde(default)]
+    pub round: Option<String>,
+    #[serde(default)]
+    pub light_roster: Vec<RosterPlayer>,
+    #[serde(default)]
+    pub dark_roster: Vec<RosterPlayer>,
+}
+
+/// A player on a team's roster for one game
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RosterPlayer {
+    pub name: String,
+    #[serde(default)]
+    pub number: Option<u32>,
+}
+
+impl Registry {
+    /// Loads the registry of `output_root`; empty if there is none yet.
+    pub fn load(output_root: &Path) -> Result<Self> {
+        let path = output_root.join(REGISTRY_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid {:?}", path))
+    }
+
+    /// Loads the registry of the output root `run_context` lives in.
+    pub fn load_for_run(run_context: &RunContext) -> Result<Self> {
+        match run_context.output_dir.parent() {
+            Some(output_root) => Self::load(output_root),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, output_root: &Path) -> Result<()> {
+        let path = output_root.join(REGISTRY_FILE);
+        let tmp = output_root.join(format!(".{}.tmp", REGISTRY_FILE));
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn team(&self, id: &str) -> Option<&Team> {
+        self.teams.iter().find(|t| t.id == id)
+    }
+
+    pub fn tournament(&self, id: &str) -> Option<&Tournament> {
+        self.tournaments.iter().find(|t| t.id == id)
+    }
+
+    /// The team called `name`, by name or alias, ignoring case and surrounding spaces.
+    pub fn team_named(&self, name: &str) -> Option<&Team> {
+        let name = name.trim().to_lowercase();
+        self.teams.iter().find(|t| {
+            std::iter::once(&t.name)
+                .chain(&t.aliases)
+                .any(|n| n.trim().to_lowercase() == name)
+        })
+    }
+
+    /// Season of a game: its tournament's if that has one, otherwise the season
+    /// whose dates contain `date`.
+    pub fn season_of(&self, tournament_id: Option<&str>, date: NaiveDate) -> Option<&Season> {
+        let season_id = tournament_id
+            .and_then(|id| self.tournament(id))
+            .and_then(|t| t.season_id.as_deref());
+        match season_id {
+            Some(id) => self.seasons.iter().find(|s| s.id == id),
+            None => self
+                .seasons
+                .iter()
+                .find(|s| s.start <= date && date <= s.end),
+        }
+    }
+
+    /// Reasons the registry cannot be saved: unusable or duplicate ids, unknown
+    /// seasons, or a name claimed by two teams.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let ids = [
+            ("team", self.teams.iter().map(|t| &t.id).collect::<Vec<_>>()),
+            ("season", self.seasons.iter().map(|s| &s.id).collect()),
+            (
+                "tournament",
+                self.tournaments.iter().map(|t| &t.id).collect(),
+            ),
+        ];
+        for (kind, ids) in ids {
+            let mut seen = HashSet::new();
+            for id in ids {
+                if !valid_run_id(id) {
+                    problems.push(format!("Invalid {} id {:?}", kind, id));
+                } else if !seen.insert(id) {
+                    problems.push(format!("Duplicate {} id {}", kind, id));
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for team in &self.teams {
+            for name in std::iter::once(&team.name).chain(&team.aliases) {
+                if !names.insert(name.trim().to_lowercase()) {
+                    problems.push(format!("Team name {:?} is used more than once", name));
+                }
+            }
+        }
+        for season in &self.seasons {
+            if season.end < season.start {
+                problems.push(format!("Season {} ends before it starts", season.id));
+            }
+        }
+        for tournament in &self.tournaments {
+            if let Some(season_id) = &tournament.season_id {
+                if !self.seasons.iter().any(|s| &s.id == season_id) {
+                    problems.push(format!(
+                        "Tournament {} refers to unknown season {}",
+                        tournament.id, season_id
+                    ));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Reasons `game` cannot replace the `previous` links of a run: unknown ids,
+    /// or one team on both sides. Ids `previous` already had pass even if unknown,
+    /// as in runs from a bundle made where the registry has other teams.
+    pub fn game_problems(&self, game: &GameInfo, previous: &GameInfo) -> Vec<String> {
+        let mut problems = Vec::new();
+        let previous_teams = [&previous.light_team_id, &previous.dark_team_id];
+        for id in [&game.light_team_id, &game.dark_team_id]
+            .into_iter()
+            .flatten()
+        {
+            let kept = previous_teams.iter().any(|p| p.as_ref() == Some(id));
+            if self.team(id).is_none() && !kept {
+                problems.push(format!("Unknown team {}", id));
+            }
+        }
+        if game.light_team_id.is_some() && game.light_team_id == game.dark_team_id {
+            problems.push("The same team cannot play both sides".to_string());
+        }
+        if let Some(id) = &game.tournament_id {
+            if self.tournament(id).is_none() && previous.tournament_id.as_ref() != Some(id) {
+                problems.push(format!("Unknown tournament {}", id));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team(id: &str, name: &str, aliases: &[&str]) -> Team {
+        Team {
+            id: id.to_string(),
+            name: name.to_string(),
+            aliases: aliases.iter().map(|a| a.to_string()).collect(),
+            colors: Vec::new(),
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn teams_are_found_by_name_or_alias() {
+        let registry = Registry {
+            teams: vec![team("ravens", "Ravens", &["Rvn", "Ravens Ultimate"])],
+            ..Registry::default()
+        };
+        assert_eq!(registry.team_named(" ravens ").unwrap().id, "ravens");
+        assert_eq!(registry.team_named("RAVENS ULTIMATE").unwrap().id, "ravens");
+        assert!(registry.team_named("Owls").is_none());
+    }
+
+    #[test]
+    fn season_comes_from_tournament_before_dates() {
+        let registry = Registry {
+            seasons: vec![
+                Season {
+                    id: "2025".to_string(),
+                    name: "2025".to_string(),
+                    start: date("2025-01-01"),
+                    end: date("2025-12-31"),
+                },
+                Season {
+                    id: "2026".to_string(),
+                    name: "2026".to_string(),
+                    start: date("2026-01-01"),
+                    end: date("2026-12-31"),
+                },
+            ],
+            tournaments: vec![Tournament {
+                id: "nationals".to_string(),
+                name: "Nationals".to_string(),
+                season_id: Some("2025".to_string()),
+                location: None,
+            }],
+            ..Registry::default()
+        };
+        let day = date("2026-01-10");
+        assert_eq!(
+            registry.season_of(Some("nationals"), day).unwrap().id,
+            "2025"
+        );
+        assert_eq!(registry.season_of(None, day).unwrap().id, "2026");
+        assert!(registry.season_of(None, date("2024-06-01")).is_none());
+    }
+
+    #[test]
+    fn problems_report_clashing_ids_and_names() {
+        let registry = Registry {
+            teams: vec![
+                team("ravens", "Ravens", &[]),
+                team("ravens", "Owls", &["ravens"]),
+                team("../x", "X", &[]),
+            ],
+            tournaments: vec![Tournament {
+                id: "open".to_string(),
+                name: "Open".to_string(),
+                season_id: Some("1999".to_string()),
+                location: None,
+            }],
+            ..Registry::default()
+        };
+        assert_eq!(
+            registry.problems(),
+            [
+                "Duplicate team id ravens",
+                "Invalid team id \"../x\"",
+                "Team name \"ravens\" is used more than once",
+                "Tournament open refers to unknown season 1999",
+            ]
+        );
+
+        let game = GameInfo {
+            light_team_id: Some("ravens".to_string()),
+            dark_team_id: Some("ravens".to_string()),
+            tournament_id: Some("worlds".to_string()),
+            ..GameInfo::default()
+        };
+        assert_eq!(
+            registry.game_problems(&game, &GameInfo::default()),
+            [
+                "The same team cannot play both sides",
+                "Unknown tournament worlds",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_ids_already_linked_are_kept() {
+        // A run from a bundle refers to teams this registry has never seen
+        let registry = Registry {
+            teams: vec![team("ravens", "Ravens", &[])],
+            ..Registry::default()
+        };
+        let imported = GameInfo {
+            light_team_id: Some("owls".to_string()),
+            dark_team_id: Some("hawks".to_string()),
+            tournament_id: Some("worlds".to_string()),
+            ..GameInfo::default()
+        };
+        let edited = GameInfo {
+            light_team_id: Some("hawks".to_string()),
+            dark_team_id: Some("owls".to_string()),
+            round: Some("Final".to_string()),
+            ..imported.clone()
+        };
+        assert!(registry.game_problems(&edited, &imported).is_empty());
+
+        let relinked = GameInfo {
+            light_team_id: Some("eagles".to_string()),
+            ..imported.clone()
+        };
+        assert_eq!(
+            registry.game_problems(&relinked, &imported),
+            ["Unknown team eagles"]
+        );
+    }
+}
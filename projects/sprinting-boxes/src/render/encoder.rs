@@ -0,0 +1,148 @@
+//! H.264 MP4 writer for rendered frames.
+
+use crate::video::ffmpeg_reader::init_ffmpeg;
+use anyhow::{anyhow, Context, Result};
+use ffmpeg_next::{codec, encoder, format, frame, software::scaling, Packet, Rational};
+use opencv::core::{Mat, CV_8UC3};
+use opencv::prelude::*;
+use std::path::Path;
+
+/// Encodes BGR `Mat`s of a fixed size into an MP4 file at a constant frame rate.
+pub struct Mp4Encoder {
+    output: format::context::Output,
+    encoder: encoder::Video,
+    scaler: scaling::Context,
+    bgr: frame::Video,
+    yuv: frame::Video,
+    stream_index: usize,
+    encoder_time_base: Rational,
+    stream_time_base: Rational,
+    width: u32,
+    height: u32,
+    next_pts: i64,
+}
+
+impl Mp4Encoder {
+    /// Creates `path` for frames of `width` x `height`. YUV 4:2:0 needs even
+    /// dimensions, so an odd last row or column is dropped.
+    pub fn create(path: &Path, width: u32, height: u32, fps: f64) -> Result<Self> {
+        init_ffmpeg()?;
+        let width = width & !1;
+        let height = height & !1;
+
+        let mut output = format::output(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        // Builds without libx264 still have the native MPEG-4 encoder
+        let codec = encoder::find(codec::Id::H264)
+            .or_else(|| encoder::find(codec::Id::MPEG4))
+            .ok_or_else(|| anyhow!("No H.264 or MPEG-4 encoder available"))?;
+        let global_header = output
+            .format()
+            .flags()
+            .contains(format::Flags::GLOBAL_HEADER);
+
+        let mut stream = output.add_stream(codec)?;
+        let stream_index = stream.index();
+        let frame_rate = Rational::from(fps);
+        let encoder_time_base = frame_rate.invert();
+
+        let mut video = codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()?;
+        video.set_width(width);
+        video.set_height(height);
+        video.set_format(format::Pixel::YUV420P);
+        video.set_frame_rate(Some(frame_rate));
+        video.set_time_base(encoder_time_base);
+        if global_header {
+            video.set_flags(codec::Flags::GLOBAL_HEADER);
+        }
+        let encoder = video.open_as(codec)?;
+        stream.set_parameters(&encoder);
+        stream.set_time_base(encoder_time_base);
+
+        output.write_header()?;
+        // The muxer may have chosen its own time base
+        let stream_time_base = output
+            .stream(stream_index)
+            .ok_or_else(|| anyhow!("Output stream disappeared"))?
+            .time_base();
+
+        let scaler = scaling::Context::get(
+            format::Pixel::BGR24,
+            width,
+            height,
+            format::Pixel::YUV420P,
+            width,
+            height,
+            scaling::Flags::BILINEAR,
+        )?;
+
+        Ok(Self {
+            output,
+            encoder,
+            scaler,
+            bgr: frame::Video::new(format::Pixel::BGR24, width, height),
+            yuv: frame::Video::new(format::Pixel::YUV420P, width, height),
+            stream_index,
+            encoder_time_base,
+            stream_time_base,
+            width,
+            height,
+            next_pts: 0,
+        })
+    }
+
+    /// Appends one frame. `img` must be 8-bit BGR and at least the encoder's size.
+    pub fn write(&mut self, img: &Mat) -> Result<()> {
+        if img.typ() != CV_8UC3 {
+            return Err(anyhow!(
+                "Expected an 8-bit BGR frame, got type {}",
+                img.typ()
+            ));
+        }
+        if (img.cols() as u32) < self.width || (img.rows() as u32) < self.height {
+            return Err(anyhow!(
+                "Frame is {}x{}, encoder expects {}x{}",
+                img.cols(),
+                img.rows(),
+                self.width,
+                self.height
+            ));
+        }
+
+        let row_bytes = self.width as usize * 3;
+        let stride = self.bgr.stride(0);
+        let data = self.bgr.data_mut(0);
+        for y in 0..self.height as usize {
+            let src = img.ptr(y as i32)?;
+            let dst = &mut data[y * stride..y * stride + row_bytes];
+            // SAFETY: rows of a CV_8UC3 Mat at least `width` wide hold `row_bytes` bytes
+            unsafe { std::ptr::copy_nonoverlapping(src, dst.as_mut_ptr(), row_bytes) };
+        }
+
+        self.scaler.run(&self.bgr, &mut self.yuv)?;
+        self.yuv.set_pts(Some(self.next_pts));
+        self.next_pts += 1;
+        self.encoder.send_frame(&self.yuv)?;
+        self.write_packets()
+    }
+
+    /// Flushes the encoder and finalizes the file.
+    pub fn finish(mut self) -> Result<()> {
+        self.encoder.send_eof()?;
+        self.write_packets()?;
+        self.output.write_trailer()?;
+        Ok(())
+    }
+
+    fn write_packets(&mut self) -> Result<()> {
+        let mut packet = Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(self.stream_index);
+            packet.rescale_ts(self.encoder_time_base, self.stream_time_base);
+            packet.write_interleaved(&mut self.output)?;
+        }
+        Ok(())
+    }
+}
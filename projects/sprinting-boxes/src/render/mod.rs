@@ -0,0 +1,353 @@
+// Highlight rendering: writes annotated MP4s of the audited points
+//
+// A render runs in the background like processing does: `start_render` plans
+// the segments from the audit, and a worker thread reads the source video,
+// crops and annotates each frame and encodes it under the run's renders/
+// directory, reporting progress through a registered `RenderState`.
+
+pub mod encoder;
+pub mod overlay;
+pub mod segments;
+
+use crate::pipeline::crop::{crop_frame, RectifyMaps};
+use crate::pipeline::detections_store::DetectionsReader;
+use crate::pipeline::finalize::draw_annotations;
+use crate::pipeline::preprocess::apply_chain;
+use crate::pipeline::types::{BBox, CropResult};
+use crate::run_artifacts::{Manifest, PreprocessStep};
+use crate::run_context::RunContext;
+use crate::video::lens::LensProfile;
+use crate::video::opencv_reader::OpencvReader;
+use crate::video::VideoReader;
+use crate::web::audit::models::{AuditSettings, CliffData};
+use anyhow::Result;
+use encoder::Mp4Encoder;
+use segments::Segment;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Instant;
+
+/// Directory under the run directory that renders are written to
+pub const RENDERS_DIR: &str = "renders";
+
+// Global registry of renders, active or finished
+lazy_static::lazy_static! {
+    static ref RENDER_REGISTRY: RwLock<HashMap<String, Arc<RenderState>>> =
+        RwLock::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderMode {
+    /// One file per confirmed point
+    Points,
+    /// All points in a single file
+    Game,
+}
+
+/// What to render, as posted to the render endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub mode: RenderMode,
+    /// End each point where the line-up for the next one begins
+    pub skip_dead_time: bool,
+    /// Burn in team names, the score at the pull and the point number
+    pub scoreboard: bool,
+    /// Draw the detections and regions of the nearest sampled frame. The
+    /// overview crop is then shown with its preprocessing applied, as the detector saw it.
+    pub detections: bool,
+    /// Render whole source frames instead of the overview crop; not combinable with `detections`
+    pub full_frame: bool,
+    /// Seconds shown before each pull
+    pub lead_in_secs: f64,
+    /// Pre-point score from which a sampled frame counts as line-up
+    pub line_up_threshold: f32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::Points,
+            skip_dead_time: true,
+            scoreboard: true,
+            detections: false,
+            full_frame: false,
+            lead_in_secs: 5.0,
+            line_up_threshold: 0.5,
+        }
+    }
+}
+
+/// Progress of a render
+pub struct RenderState {
+    pub run_id: String,
+    /// Source frames across all segments
+    pub total_frames: usize,
+    pub total_segments: usize,
+    pub frames_rendered: AtomicUsize,
+    pub segments_rendered: AtomicUsize,
+    pub is_active: AtomicBool,
+    pub is_complete: AtomicBool,
+    pub error: RwLock<Option<String>>,
+    /// Finished files, relative to the run directory
+    pub outputs: RwLock<Vec<String>>,
+    pub start_time: Instant,
+}
+
+impl RenderState {
+    fn new(run_id: &str, segments: &[Segment], fps: f64) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            total_frames: segments.iter().map(|s| s.frame_count(fps)).sum(),
+            total_segments: segments.len(),
+            frames_rendered: AtomicUsize::new(0),
+            segments_rendered: AtomicUsize::new(0),
+            is_active: AtomicBool::new(true),
+            is_complete: AtomicBool::new(false),
+            error: RwLock::new(None),
+            outputs: RwLock::new(Vec::new()),
+            start_time: Instant::now(),
+        }
+    }
+
+    pub fn to_progress_json(&self) -> serde_json::Value {
+        let frames_rendered = self.frames_rendered.load(Ordering::Relaxed);
+        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let fps = if elapsed > 0.0 {
+            frames_rendered as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        serde_json::json!({
+            "run_id": self.run_id,
+            "total_frames": self.total_frames,
+            "frames_rendered": frames_rendered,
+            "total_segments": self.total_segments,
+            "segments_rendered": self.segments_rendered.load(Ordering::Relaxed),
+            "is_active": self.is_active.load(Ordering::Relaxed),
+            "is_complete": self.is_complete.load(Ordering::Relaxed),
+            "error": self.error.read().unwrap().clone(),
+            "outputs": self.outputs.read().unwrap().clone(),
+            "fps": fps,
+            "elapsed_secs": elapsed,
+        })
+    }
+}
+
+pub fn get_render_state(run_id: &str) -> Option<Arc<RenderState>> {
+    RENDER_REGISTRY.read().unwrap().get(run_id).cloned()
+}
+
+/// Asks a render to stop after its current frame; the file being written is discarded.
+pub fn stop_render(run_id: &str) -> bool {
+    match get_render_state(run_id) {
+        Some(state) => {
+            state.is_active.store(false, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Everything the worker needs, resolved before it starts
+struct RenderJob {
+    output_dir: PathBuf,
+    segments: Vec<Segment>,
+    options: RenderOptions,
+    settings: AuditSettings,
+    sample_rate: f64,
+    overview: BBox,
+    overview_steps: Vec<PreprocessStep>,
+    lens: Option<LensProfile>,
+    config_hash: String,
+}
+
+/// Start rendering the confirmed points of `cliffs` (as returned by `recalculate_audit`)
+pub fn start_render(
+    run_context: &RunContext,
+    video_root: &Path,
+    cliffs: &[CliffData],
+    settings: &AuditSettings,
+    sample_rate: f64,
+    options: RenderOptions,
+) -> Result<Arc<RenderState>> {
+    if let Some(state) = get_render_state(&run_context.run_id) {
+        if !state.is_complete.load(Ordering::Relaxed) {
+            anyhow::bail!("Run {} is already being rendered", run_context.run_id);
+        }
+    }
+    if options.detections && options.full_frame {
+        anyhow::bail!("Detections can only be drawn on the overview crop");
+    }
+    if options.detections && DetectionsReader::open(&run_context.output_dir)?.is_none() {
+        anyhow::bail!("Run {} has no detections to draw", run_context.run_id);
+    }
+
+    let video_path = run_context.resolve_video_path(video_root);
+    if !video_path.exists() {
+        return Err(anyhow::anyhow!("Video file NOT FOUND at: {:?}", video_path));
+    }
+    let reader = OpencvReader::new(video_path.to_str().unwrap(), sample_rate)?;
+    let fps = reader.source_fps()?;
+    let duration_secs = reader.frame_count()? as f64 / sample_rate;
+
+    let pre_point_scores = segments::load_pre_point_scores(&run_context.output_dir)?;
+    let segments = segments::plan_segments(
+        cliffs,
+        &pre_point_scores,
+        sample_rate,
+        duration_secs,
+        &options,
+    );
+    if segments.is_empty() {
+        anyhow::bail!(
+            "Run {} has no confirmed points to render",
+            run_context.run_id
+        );
+    }
+
+    let crops = run_context.load_crop_configs()?;
+    let preprocess = run_context.load_preprocess_config()?;
+    let job = RenderJob {
+        output_dir: run_context.output_dir.clone(),
+        overview_steps: preprocess.steps_for("overview").to_vec(),
+        overview: crops.overview.bbox,
+        lens: run_context.lens,
+        config_hash: run_context.config_hash(),
+        segments,
+        options,
+        settings: settings.clone(),
+        sample_rate,
+    };
+
+    let state = Arc::new(RenderState::new(&run_context.run_id, &job.segments, fps));
+    RENDER_REGISTRY
+        .write()
+        .unwrap()
+        .insert(run_context.run_id.clone(), state.clone());
+
+    let worker_state = state.clone();
+    thread::spawn(move || {
+        if let Err(e) = render_worker(&job, reader, &worker_state) {
+            tracing::error!("Render of {} failed: {:?}", worker_state.run_id, e);
+            *worker_state.error.write().unwrap() = Some(e.to_string());
+        }
+        worker_state.is_active.store(false, Ordering::Relaxed);
+        worker_state.is_complete.store(true, Ordering::Relaxed);
+    });
+
+    Ok(state)
+}
+
+/// A file being written, relative to the run directory
+struct OpenOutput {
+    name: String,
+    encoder: Mp4Encoder,
+}
+
+fn render_worker(job: &RenderJob, mut reader: OpencvReader, state: &RenderState) -> Result<()> {
+    let fps = reader.source_fps()?;
+    fs::create_dir_all(job.output_dir.join(RENDERS_DIR))?;
+    let mut detections = if job.options.detections {
+        DetectionsReader::open(&job.output_dir)?
+    } else {
+        None
+    };
+    let mut maps: Option<RectifyMaps> = None;
+    // Overview detections of the sampled frame last drawn
+    let mut annotation: Option<(usize, Option<CropResult>)> = None;
+    let mut output: Option<OpenOutput> = None;
+
+    for segment in &job.segments {
+        reader.seek_to_frame((segment.start_secs * fps).round() as usize)?;
+        for n in 0..segment.frame_count(fps) {
+            if !state.is_active.load(Ordering::Relaxed) {
+                if let Some(open) = output.take() {
+                    drop(open.encoder);
+                    let _ = fs::remove_file(job.output_dir.join(&open.name));
+                }
+                tracing::info!("Render of {} stopped", state.run_id);
+                return Ok(());
+            }
+            let frame = match reader.read_frame() {
+                Ok(frame) => frame,
+                // End of the video
+                Err(_) => break,
+            };
+
+            let mut view = if job.options.full_frame {
+                frame
+            } else {
+                crop_frame(&frame, &job.overview, job.lens.as_ref(), &mut maps)?
+            };
+
+            if let Some(detections) = detections.as_mut() {
+                view = apply_chain(&view, &job.overview_steps)?.0;
+                let secs = segment.start_secs + n as f64 / fps;
+                let unit = (secs * job.sample_rate).round() as usize;
+                if annotation.as_ref().map(|(u, _)| *u) != Some(unit) {
+                    let overview = detections
+                        .get(unit)?
+                        .and_then(|f| f.into_crop_results().remove("overview"));
+                    annotation = Some((unit, overview));
+                }
+                if let Some((_, Some(result))) = &annotation {
+                    view = draw_annotations(&view, result, None)?;
+                }
+            }
+
+            if job.options.scoreboard {
+                overlay::draw_scoreboard(&mut view, &job.settings, segment)?;
+            }
+
+            let open = match output.as_mut() {
+                Some(open) => open,
+                None => {
+                    let name = match job.options.mode {
+                        RenderMode::Points => {
+                            format!("{}/point_{:03}.mp4", RENDERS_DIR, segment.point)
+                        }
+                        RenderMode::Game => format!("{}/game.mp4", RENDERS_DIR),
+                    };
+                    let size = view.size()?;
+                    let encoder = Mp4Encoder::create(
+                        &job.output_dir.join(&name),
+                        size.width as u32,
+                        size.height as u32,
+                        fps,
+                    )?;
+                    output.insert(OpenOutput { name, encoder })
+                }
+            };
+            open.encoder.write(&view)?;
+            state.frames_rendered.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if job.options.mode == RenderMode::Points {
+            if let Some(open) = output.take() {
+                finish_output(job, open, state)?;
+            }
+        }
+        state.segments_rendered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if let Some(open) = output.take() {
+        finish_output(job, open, state)?;
+    }
+    Ok(())
+}
+
+fn finish_output(job: &RenderJob, open: OpenOutput, state: &RenderState) -> Result<()> {
+    open.encoder.finish()?;
+    Manifest::record(&job.output_dir, &open.name, Some(job.config_hash.clone()))?;
+    tracing::info!("Rendered {}", open.name);
+    state.outputs.write().unwrap().push(open.name);
+    Ok(())
+}
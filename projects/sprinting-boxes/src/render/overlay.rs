@@ -0,0 +1,77 @@
+//! Scoreboard burned into rendered frames.
+
+use super::segments::Segment;
+use crate::web::audit::models::AuditSettings;
+use anyhow::Result;
+use opencv::core::{Mat, Point, Rect, Scalar};
+use opencv::imgproc::{
+    get_text_size, put_text, rectangle, FILLED, FONT_HERSHEY_SIMPLEX, LINE_8, LINE_AA,
+};
+use opencv::prelude::MatTraitConst;
+
+/// Draws the score at the pull and the point number in the top-left corner,
+/// scaled to the frame height. Hershey fonts only cover ASCII, so other
+/// characters in team names show as '?'.
+pub fn draw_scoreboard(img: &mut Mat, settings: &AuditSettings, segment: &Segment) -> Result<()> {
+    let scale = (img.rows() as f64 / 720.0).max(0.5);
+    let thickness = (2.0 * scale).round().max(1.0) as i32;
+    let margin = (12.0 * scale).round() as i32;
+
+    let point = if segment.is_break {
+        format!("Point {} (break)", segment.point)
+    } else {
+        format!("Point {}", segment.point)
+    };
+    let lines = [
+        (
+            format!("{}  {}", settings.light_team_name, segment.score_light),
+            Scalar::new(255.0, 255.0, 255.0, 0.0),
+        ),
+        (
+            format!("{}  {}", settings.dark_team_name, segment.score_dark),
+            Scalar::new(160.0, 160.0, 160.0, 0.0),
+        ),
+        (point, Scalar::new(0.0, 200.0, 255.0, 0.0)), // Amber
+    ];
+
+    let mut text_width = 0;
+    let mut line_height = 0;
+    for (text, _) in &lines {
+        let mut baseline = 0;
+        let size = get_text_size(text, FONT_HERSHEY_SIMPLEX, scale, thickness, &mut baseline)?;
+        text_width = text_width.max(size.width);
+        line_height = line_height.max(size.height + baseline);
+    }
+    let line_height = line_height + margin / 2;
+
+    let panel = Rect::new(
+        margin,
+        margin,
+        text_width + 2 * margin,
+        line_height * lines.len() as i32 + margin,
+    );
+    rectangle(
+        img,
+        panel,
+        Scalar::new(0.0, 0.0, 0.0, 0.0),
+        FILLED,
+        LINE_8,
+        0,
+    )?;
+
+    for (i, (text, color)) in lines.iter().enumerate() {
+        let origin = Point::new(2 * margin, margin + line_height * (i as i32 + 1));
+        put_text(
+            img,
+            text,
+            origin,
+            FONT_HERSHEY_SIMPLEX,
+            scale,
+            *color,
+            thickness,
+            LINE_AA,
+            false,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Which stretches of the video a render covers.
+
+use super::RenderOptions;
+use crate::web::audit::models::CliffData;
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+/// The stretch of video showing one confirmed point
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// 1-based number of the point within the game
+    pub point: usize,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    /// Score at the pull
+    pub score_light: i32,
+    pub score_dark: i32,
+    pub is_break: bool,
+}
+
+impl Segment {
+    /// Number of source frames the segment spans at `fps`
+    pub fn frame_count(&self, fps: f64) -> usize {
+        ((self.end_secs - self.start_secs) * fps).round().max(0.0) as usize
+    }
+}
+
+/// Plans one segment per confirmed point of `cliffs` (as returned by
+/// `recalculate_audit`).
+///
+/// A point starts `lead_in_secs` before its pull and runs until the next point
+/// starts, or to the end of the video. With `skip_dead_time` it instead ends
+/// where the line-up for the next pull (or halftime) begins: the start of the run
+/// of sampled frames before it whose `pre_point_scores` reach `line_up_threshold`.
+pub fn plan_segments(
+    cliffs: &[CliffData],
+    pre_point_scores: &[f32],
+    sample_rate: f64,
+    duration_secs: f64,
+    options: &RenderOptions,
+) -> Vec<Segment> {
+    let boundaries: Vec<&CliffData> = cliffs
+        .iter()
+        .filter(|c| c.status == "Confirmed" || c.status == "Halftime")
+        .collect();
+    let point_start = |cliff: &CliffData| {
+        (cliff.frame_index as f64 / sample_rate - options.lead_in_secs).max(0.0)
+    };
+
+    let mut segments = Vec::new();
+    let mut point = 0;
+    for (i, cliff) in boundaries.iter().enumerate() {
+        if cliff.status != "Confirmed" {
+            continue;
+        }
+        point += 1;
+        let start_secs = point_start(cliff);
+        let end_secs = if options.skip_dead_time {
+            match boundaries.get(i + 1) {
+                Some(next) => {
+                    line_up_start(
+                        cliff.frame_index,
+                        next.frame_index,
+                        pre_point_scores,
+                        options.line_up_threshold,
+                    ) as f64
+                        / sample_rate
+                }
+                None => duration_secs,
+            }
+        } else {
+            match boundaries[i + 1..].iter().find(|c| c.status == "Confirmed") {
+                Some(next) => point_start(next),
+                None => duration_secs,
+            }
+        };
+        if end_secs <= start_secs {
+            continue;
+        }
+        segments.push(Segment {
+            point,
+            start_secs,
+            end_secs: end_secs.min(duration_secs),
+            score_light: cliff.score_light,
+            score_dark: cliff.score_dark,
+            is_break: cliff.is_break,
+        });
+    }
+    segments
+}
+
+/// First sampled frame of the line-up that ends at `next`, never before `after + 1`.
+/// `next` itself if the frames before it do not look like a line-up.
+fn line_up_start(after: usize, next: usize, pre_point_scores: &[f32], threshold: f32) -> usize {
+    if next > pre_point_scores.len() {
+        // No features for the frames before the next pull
+        return next;
+    }
+    let mut start = next;
+    while start > after + 1 && pre_point_scores[start - 1] >= threshold {
+        start -= 1;
+    }
+    start
+}
+
+/// Pre-point scores from the run's features.csv, indexed by sampled frame; empty
+/// if the run has no features.
+pub fn load_pre_point_scores(output_dir: &Path) -> Result<Vec<f32>> {
+    let path = output_dir.join("features.csv");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)?;
+    let mut scores = Vec::new();
+    for line in content.lines().skip(1) {
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < 5 {
+            continue;
+        }
+        let frame_index: usize = match parts[0].parse() {
+            Ok(frame_index) => frame_index,
+            Err(_) => continue,
+        };
+        if scores.len() <= frame_index {
+            scores.resize(frame_index + 1, 0.0);
+        }
+        scores[frame_index] = parts[4].parse().unwrap_or(0.0);
+    }
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cliff(frame_index: usize, status: &str, score_light: i32) -> CliffData {
+        let mut cliff = CliffData::from_detection(frame_index, String::new(), true, false);
+        cliff.status = status.to_string();
+        cliff.score_light = score_light;
+        cliff
+    }
+
+    #[test]
+    fn points_run_until_the_next_point_or_line_up() {
+        let cliffs = vec![
+            cliff(10, "Confirmed", 0),
+            cliff(30, "FalsePositive", 0),
+            cliff(60, "Confirmed", 1),
+            cliff(100, "Halftime", 2),
+            cliff(130, "Confirmed", 2),
+        ];
+        // Line-ups before the pulls at 60 and 100
+        let mut scores = vec![0.0; 150];
+        scores[50..60].iter_mut().for_each(|s| *s = 0.9);
+        scores[90..100].iter_mut().for_each(|s| *s = 0.9);
+        let mut options = RenderOptions {
+            lead_in_secs: 5.0,
+            skip_dead_time: false,
+            ..RenderOptions::default()
+        };
+
+        let bounds = |segments: Vec<Segment>| -> Vec<(usize, f64, f64, i32)> {
+            segments
+                .into_iter()
+                .map(|s| (s.point, s.start_secs, s.end_secs, s.score_light))
+                .collect()
+        };
+
+        let segments = plan_segments(&cliffs, &scores, 1.0, 200.0, &options);
+        assert_eq!(
+            bounds(segments),
+            [(1, 5.0, 55.0, 0), (2, 55.0, 125.0, 1), (3, 125.0, 200.0, 2)]
+        );
+
+        options.skip_dead_time = true;
+        let segments = plan_segments(&cliffs, &scores, 1.0, 200.0, &options);
+        assert_eq!(
+            bounds(segments),
+            [(1, 5.0, 50.0, 0), (2, 55.0, 90.0, 1), (3, 125.0, 200.0, 2)]
+        );
+    }
+}
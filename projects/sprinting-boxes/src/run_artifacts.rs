@@ -1,9 +1,62 @@
 // Run artifact struct definitions
 //
 // This module contains the struct definitions for artifacts that are persisted
-// as JSON files within a run's output directory.
+// as JSON files within a run's output directory, the manifest indexing them,
+// and the migrations that upgrade older run directories in place.
 
+use crate::pipeline::{crop_store, detections_store, snapshot};
+use crate::run_context::TEAM_SIZE_ESTIMATE_FILE;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+/// Per-run index of artifacts (see `Manifest`)
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Layout version of run directories written by this build; older runs are
+/// migrated up to it at server startup and on import (see `migrate_runs`)
+pub const LAYOUT_VERSION: u32 = 4;
+
+/// Every artifact this build writes. Format changes are layout migrations.
+pub const KNOWN_ARTIFACTS: &[&str] = &[
+    "metadata.json",
+    "field_boundaries.json",
+    "crops.json",
+    "preprocess.json",
+    "game_details.json",
+    "audit.json",
+    snapshot::SNAPSHOT_FILE,
+    "features.csv",
+    "points.csv",
+    "detection_summary.csv",
+    detections_store::DATA_FILE,
+    detections_store::INDEX_FILE,
+    "features.parquet",
+    "cliffs.parquet",
+    "detections.parquet",
+    TEAM_SIZE_ESTIMATE_FILE,
+];
+
+/// Artifacts written by a processing run
+pub const PIPELINE_ARTIFACTS: &[&str] = &[
+    "features.csv",
+    "points.csv",
+    "detection_summary.csv",
+    detections_store::DATA_FILE,
+    detections_store::INDEX_FILE,
+    "features.parquet",
+    "cliffs.parquet",
+    "detections.parquet",
+    TEAM_SIZE_ESTIMATE_FILE,
+];
+
+/// Serializes read-modify-write cycles of manifests and migrations
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
 
 /// A 2D point in normalized coordinates [0, 1]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -38,6 +91,9 @@ pub struct FieldBoundaries {
     pub right_end_zone: Vec<Point>,
     #[serde(default)]
     pub roi: Option<ROIDefinition>,
+    /// Areas whose people are never counted (benches, camera positions), ROI-relative
+    #[serde(default)]
+    pub exclusions: Vec<Vec<Point>>,
 }
 
 /// A single crop configuration for a boundary region
@@ -62,6 +118,54 @@ pub struct CropsConfig {
     pub left_end_zone_polygon: Vec<Point>,
     pub right_end_zone_polygon: Vec<Point>,
     pub field_polygon: Vec<Point>,
+    /// Exclusion polygons in global normalized coordinates
+    #[serde(default)]
+    pub exclusion_polygons: Vec<Vec<Point>>,
+}
+
+/// One image-processing step of the crop stage
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PreprocessStep {
+    /// Contrast-limited adaptive histogram equalization of the Lab lightness channel
+    Clahe { clip_limit: f64, tile_grid: i32 },
+    /// Gamma curve; values below 1 lift shadows
+    Gamma { gamma: f64 },
+    /// Gray-world white balance: scales each channel to the common mean
+    WhiteBalance,
+    /// Non-local means denoising; `strength` is the filter's `h`
+    Denoise { strength: f32 },
+    /// Unsharp mask adding `amount` of the difference to a Gaussian blur of `sigma` px
+    Sharpen { amount: f64, sigma: f64 },
+    /// Dark-channel-prior haze removal for low-contrast evening footage.
+    /// `strength` in [0, 1] is the share of haze removed; `window` is the patch size in px.
+    Dehaze { strength: f64, window: i32 },
+    /// Resize to fit `width` x `height` (the model input) and pad the rest with gray.
+    /// Only allowed as the last step.
+    Letterbox { width: i32, height: i32 },
+}
+
+/// Crop-stage preprocessing chains as defined in preprocess.json
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PreprocessConfig {
+    /// Chain for crops without their own entry in `crops`
+    pub steps: Vec<PreprocessStep>,
+    /// Chains for individual crops, keyed by crop name ("overview", "left", "right")
+    #[serde(default)]
+    pub crops: BTreeMap<String, Vec<PreprocessStep>>,
+}
+
+impl Default for PreprocessConfig {
+    /// CLAHE only, as applied before the chain was configurable
+    fn default() -> Self {
+        Self {
+            steps: vec![PreprocessStep::Clahe {
+                clip_limit: 2.0,
+                tile_grid: 8,
+            }],
+            crops: BTreeMap::new(),
+        }
+    }
 }
 
 impl FieldBoundaries {
@@ -81,4 +185,486 @@ impl FieldBoundaries {
             })
             .collect()
     }
+
+    /// Transforms points from global normalized to ROI-relative coordinates, the
+    /// inverse of [`Self::get_global_points`].
+    pub fn get_roi_points(&self, points: &[Point]) -> Vec<Point> {
+        points
+            .iter()
+            .map(|p| match self.roi {
+                Some(ref roi) => Point {
+                    x: (p.x - roi.x_normalized) / roi.width_normalized.max(f32::EPSILON),
+                    y: (p.y - roi.y_normalized) / roi.height_normalized.max(f32::EPSILON),
+                },
+                None => *p,
+            })
+            .collect()
+    }
+}
+
+impl PreprocessConfig {
+    /// The chain applied to the crop named `crop`.
+    pub fn steps_for(&self, crop: &str) -> &[PreprocessStep] {
+        self.crops.get(crop).unwrap_or(&self.steps)
+    }
+
+    /// Checks step parameters, and that a letterbox only ends a chain.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for steps in std::iter::once(&self.steps).chain(self.crops.values()) {
+            validate_steps(steps)?;
+        }
+        Ok(())
+    }
+
+    /// Model input size the crop named `crop` is letterboxed to, if any.
+    pub fn letterbox(&self, crop: &str) -> Option<(i32, i32)> {
+        match self.steps_for(crop).last() {
+            Some(PreprocessStep::Letterbox { width, height }) => Some((*width, *height)),
+            _ => None,
+        }
+    }
+}
+
+fn validate_steps(steps: &[PreprocessStep]) -> anyhow::Result<()> {
+    for (i, step) in steps.iter().enumerate() {
+        let valid = match *step {
+            PreprocessStep::Clahe {
+                clip_limit,
+                tile_grid,
+            } => clip_limit > 0.0 && tile_grid > 0,
+            PreprocessStep::Gamma { gamma } => gamma > 0.0,
+            PreprocessStep::WhiteBalance => true,
+            PreprocessStep::Denoise { strength } => strength >= 0.0,
+            PreprocessStep::Sharpen { amount, sigma } => amount >= 0.0 && sigma > 0.0,
+            PreprocessStep::Dehaze { strength, window } => {
+                (0.0..=1.0).contains(&strength) && window > 0
+            }
+            PreprocessStep::Letterbox { width, height } => {
+                if i + 1 != steps.len() {
+                    anyhow::bail!("Letterbox must be the last preprocessing step");
+                }
+                width > 0 && height > 0
+            }
+        };
+        if !valid {
+            anyhow::bail!(
+                "Invalid parameters for preprocessing step {}: {:?}",
+                i,
+                step
+            );
+        }
+    }
+    Ok(())
+}
+
+/// One artifact's entry in `manifest.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArtifactEntry {
+    /// Version of sprinting-boxes that wrote the artifact; "unknown" for artifacts
+    /// that predate the manifest
+    pub producer_version: String,
+    /// Hash of the configuration the artifact was derived from; `None` for user input
+    #[serde(default)]
+    pub config_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Index of a run's artifacts as stored in manifest.json
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    /// Layout version the run directory has been migrated to; 0 for runs that
+    /// predate the manifest
+    pub layout_version: u32,
+    /// Entries keyed by file name, relative to the run directory
+    pub artifacts: BTreeMap<String, ArtifactEntry>,
+}
+
+impl Manifest {
+    /// An empty manifest at the current layout, for a run that is just being created
+    pub fn current() -> Self {
+        Self {
+            layout_version: LAYOUT_VERSION,
+            artifacts: BTreeMap::new(),
+        }
+    }
+
+    /// Loads the manifest in `dir`, or an empty layout-0 one if there is none.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Saves the manifest, replacing the old one atomically.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let tmp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(tmp, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    /// Records that this build has just written the artifact `name` in `dir`.
+    pub fn record(dir: &Path, name: &str, config_hash: Option<String>) -> Result<()> {
+        let _lock = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut manifest = Self::load(dir)?;
+        manifest.artifacts.insert(
+            name.to_string(),
+            ArtifactEntry {
+                producer_version: env!("CARGO_PKG_VERSION").to_string(),
+                config_hash,
+                created_at: Utc::now(),
+            },
+        );
+        manifest.save(dir)
+    }
+}
+
+/// Records the outputs of a finished processing run that exist in `dir`.
+pub fn record_pipeline_outputs(dir: &Path, config_hash: &str) -> Result<()> {
+    for name in PIPELINE_ARTIFACTS {
+        if dir.join(name).exists() {
+            Manifest::record(dir, name, Some(config_hash.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Stable 64-bit FNV-1a hash of `parts`, as hex. Unlike `DefaultHasher`, the
+/// result does not change between Rust releases, so it can be stored.
+pub fn config_hash(parts: &[&[u8]]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in parts {
+        // Length prefix, so ["ab", "c"] and ["a", "bc"] differ
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(part.iter()) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// One upgrade step of a run directory, from layout `version - 1` to `version`
+struct Migration {
+    version: u32,
+    description: &'static str,
+    apply: fn(&Path, &mut Manifest) -> Result<()>,
+}
+
+/// Every migration, in order. Append new ones here and bump `LAYOUT_VERSION`.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "rename pull_points.csv to points.csv",
+        apply: rename_pull_points,
+    },
+    Migration {
+        version: 2,
+        description: "convert detections.json to the indexed detections store",
+        apply: convert_detections,
+    },
+    Migration {
+        version: 3,
+        description: "list pre-manifest artifacts in manifest.json",
+        apply: list_existing_artifacts,
+    },
+    Migration {
+        version: 4,
+        description: "move team_size_estimate out of metadata.json",
+        apply: split_team_size_estimate,
+    },
+];
+
+/// Upgrades the run directory `dir` to `LAYOUT_VERSION`, saving the manifest
+/// after each step so an interrupted migration resumes where it stopped.
+/// Returns the number of migrations applied.
+pub fn migrate_run(dir: &Path) -> Result<usize> {
+    let _lock = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = Manifest::load(dir)?;
+    if manifest.layout_version > LAYOUT_VERSION {
+        anyhow::bail!(
+            "Run {:?} has layout version {}, newer than this build's {}",
+            dir,
+            manifest.layout_version,
+            LAYOUT_VERSION
+        );
+    }
+
+    let mut applied = 0;
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|m| m.version > manifest.layout_version)
+    {
+        (migration.apply)(dir, &mut manifest)?;
+        manifest.layout_version = migration.version;
+        manifest.save(dir)?;
+        tracing::info!(
+            "Migrated {:?} to layout {}: {}",
+            dir,
+            migration.version,
+            migration.description
+        );
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+/// Migrates every run of the output root, logging the runs that fail. Called once
+/// at server startup, so loading a run never has to write to it.
+pub fn migrate_runs(output_root: &Path) -> Result<usize> {
+    let mut applied = 0;
+    if !output_root.exists() {
+        return Ok(applied);
+    }
+    for entry in fs::read_dir(output_root)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Hidden directories are imports in progress
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if !path.join("metadata.json").exists() {
+            continue;
+        }
+        match migrate_run(&path) {
+            Ok(count) => applied += count,
+            Err(e) => tracing::warn!("Failed to migrate run {:?}: {}", path, e),
+        }
+    }
+    Ok(applied)
+}
+
+/// Fails for a run directory written by a newer build, and warns about one that
+/// has not been migrated yet. Never writes.
+pub fn check_layout(dir: &Path) -> Result<()> {
+    let layout_version = Manifest::load(dir)?.layout_version;
+    if layout_version > LAYOUT_VERSION {
+        anyhow::bail!(
+            "Run {:?} has layout version {}, newer than this build's {}",
+            dir,
+            layout_version,
+            LAYOUT_VERSION
+        );
+    }
+    if layout_version < LAYOUT_VERSION {
+        tracing::warn!(
+            "Run {:?} has layout version {}; it is migrated at the next server start",
+            dir,
+            layout_version
+        );
+    }
+    Ok(())
+}
+
+fn rename_pull_points(dir: &Path, _: &mut Manifest) -> Result<()> {
+    let legacy = dir.join("pull_points.csv");
+    let current = dir.join("points.csv");
+    if legacy.exists() && !current.exists() {
+        fs::rename(legacy, current)?;
+    }
+    Ok(())
+}
+
+fn convert_detections(dir: &Path, _: &mut Manifest) -> Result<()> {
+    if dir.join(detections_store::INDEX_FILE).exists() {
+        return Ok(());
+    }
+    let legacy = detections_store::LEGACY_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists());
+    if let Some(path) = legacy {
+        detections_store::migrate(&path, dir)?;
+    }
+    Ok(())
+}
+
+fn list_existing_artifacts(dir: &Path, manifest: &mut Manifest) -> Result<()> {
+    for name in KNOWN_ARTIFACTS {
+        let path = dir.join(name);
+        if !path.exists() || manifest.artifacts.contains_key(*name) {
+            continue;
+        }
+        let created_at = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        manifest.artifacts.insert(
+            name.to_string(),
+            ArtifactEntry {
+                producer_version: "unknown".to_string(),
+                config_hash: None,
+                created_at,
+            },
+        );
+    }
+    Ok(())
+}
+
+fn split_team_size_estimate(dir: &Path, manifest: &mut Manifest) -> Result<()> {
+    let path = dir.join("metadata.json");
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    let estimate = match metadata.as_object_mut() {
+        Some(fields) => fields.remove("team_size_estimate"),
+        None => None,
+    };
+    let estimate = match estimate {
+        Some(estimate) => estimate,
+        None => return Ok(()),
+    };
+    if !estimate.is_null() && !dir.join(TEAM_SIZE_ESTIMATE_FILE).exists() {
+        fs::write(
+            dir.join(TEAM_SIZE_ESTIMATE_FILE),
+            serde_json::to_string_pretty(&estimate)?,
+        )?;
+        manifest.artifacts.insert(
+            TEAM_SIZE_ESTIMATE_FILE.to_string(),
+            ArtifactEntry {
+                producer_version: "unknown".to_string(),
+                config_hash: None,
+                created_at: Utc::now(),
+            },
+        );
+    }
+    fs::write(&path, serde_json::to_string_pretty(&metadata)?)?;
+    Ok(())
+}
+
+/// Disk space taken by a run directory
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DiskUsage {
+    pub total_bytes: u64,
+    /// Bytes of the saved crops (loose files, pack and index) and their number
+    pub crop_bytes: u64,
+    pub crop_count: usize,
+    /// Bytes under each top-level file or directory of the run
+    pub entries: BTreeMap<String, u64>,
+}
+
+/// Measures the run directory `dir`.
+pub fn disk_usage(dir: &Path) -> Result<DiskUsage> {
+    let mut usage = DiskUsage::default();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let bytes: u64 = WalkDir::new(entry.path())
+            .into_iter()
+            .flatten()
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.metadata().ok())
+            .map(|m| m.len())
+            .sum();
+        usage.total_bytes += bytes;
+        usage
+            .entries
+            .insert(entry.file_name().to_string_lossy().into_owned(), bytes);
+    }
+    usage.crop_bytes = crop_store::stored_bytes(dir);
+    usage.crop_count = crop_store::CropStore::open(dir)?.names()?.len();
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_legacy_run_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::write(dir.join("metadata.json"), "{}").unwrap();
+        fs::write(dir.join("pull_points.csv"), "frame_index\n").unwrap();
+
+        assert_eq!(migrate_run(dir).unwrap(), MIGRATIONS.len());
+        assert!(dir.join("points.csv").exists());
+        assert!(!dir.join("pull_points.csv").exists());
+
+        let manifest = Manifest::load(dir).unwrap();
+        assert_eq!(manifest.layout_version, LAYOUT_VERSION);
+        assert_eq!(manifest.artifacts["points.csv"].producer_version, "unknown");
+        assert!(manifest.artifacts.contains_key("metadata.json"));
+
+        Manifest::record(dir, "audit.json", None).unwrap();
+        assert_eq!(migrate_run(dir).unwrap(), 0);
+        let manifest = Manifest::load(dir).unwrap();
+        assert_eq!(
+            manifest.artifacts["audit.json"].producer_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    #[test]
+    fn moves_team_size_estimate_out_of_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let estimate = serde_json::json!({
+            "team_size": 5,
+            "confidence": 0.8,
+            "plateaus": 4,
+            "observed_count": 4.6,
+        });
+        let metadata =
+            serde_json::json!({ "display_name": "game", "team_size_estimate": estimate });
+        fs::write(dir.join("metadata.json"), metadata.to_string()).unwrap();
+
+        migrate_run(dir).unwrap();
+        let content = fs::read_to_string(dir.join("metadata.json")).unwrap();
+        let metadata: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(metadata, serde_json::json!({ "display_name": "game" }));
+
+        let content = fs::read_to_string(dir.join(TEAM_SIZE_ESTIMATE_FILE)).unwrap();
+        let moved: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(moved, estimate);
+        let manifest = Manifest::load(dir).unwrap();
+        assert!(manifest.artifacts.contains_key(TEAM_SIZE_ESTIMATE_FILE));
+    }
+
+    #[test]
+    fn migrates_every_run_but_imports_in_progress() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in ["game", ".import-game"] {
+            let dir = tmp.path().join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("metadata.json"), "{}").unwrap();
+            fs::write(dir.join("pull_points.csv"), "frame_index\n").unwrap();
+        }
+
+        assert_eq!(migrate_runs(tmp.path()).unwrap(), MIGRATIONS.len());
+        assert!(tmp.path().join("game/points.csv").exists());
+        assert!(tmp.path().join(".import-game/pull_points.csv").exists());
+        assert_eq!(migrate_runs(tmp.path()).unwrap(), 0);
+    }
+
+    #[test]
+    fn config_hash_is_stable() {
+        assert_eq!(config_hash(&[b"ab", b"c"]), config_hash(&[b"ab", b"c"]));
+        assert_ne!(config_hash(&[b"ab", b"c"]), config_hash(&[b"a", b"bc"]));
+        assert_eq!(config_hash(&[]), "cbf29ce484222325");
+    }
+
+    #[test]
+    fn roi_points_invert_global_points() {
+        let boundaries = FieldBoundaries {
+            field: Vec::new(),
+            left_end_zone: Vec::new(),
+            right_end_zone: Vec::new(),
+            roi: Some(ROIDefinition {
+                x_normalized: 0.2,
+                y_normalized: 0.1,
+                width_normalized: 0.5,
+                height_normalized: 0.8,
+            }),
+            exclusions: Vec::new(),
+        };
+        let points = [Point { x: 0.0, y: 1.0 }, Point { x: 0.4, y: 0.25 }];
+        let global = boundaries.get_global_points(&points);
+        assert!((global[1].x - 0.4).abs() < 1e-6 && (global[1].y - 0.3).abs() < 1e-6);
+        for (p, q) in points.iter().zip(boundaries.get_roi_points(&global)) {
+            assert!((p.x - q.x).abs() < 1e-6 && (p.y - q.y).abs() < 1e-6);
+        }
+    }
 }
@@ -0,0 +1,371 @@
+// Run bundles: a processed run packed into one .tar.zst file to hand to someone else
+//
+// A bundle holds `bundle.json`, then the run directory under `run/` and, if
+// requested, the source video under `video/`. Runs refer to their video by an
+// absolute path on the machine that created them, so metadata.json is bundled
+// with `original_name` reduced to the video's file name; import resolves it
+// against the recipient's video root.
+
+use crate::pipeline::{crop_cache, crop_store};
+use crate::render::RENDERS_DIR;
+use crate::run_artifacts::{migrate_run, Manifest, LAYOUT_VERSION};
+use crate::run_context::RunContext;
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const BUNDLE_EXTENSION: &str = "tar.zst";
+
+/// Format version of bundles written by this build
+pub const BUNDLE_VERSION: u32 = 1;
+
+const INFO_FILE: &str = "bundle.json";
+const RUN_PREFIX: &str = "run";
+const VIDEO_PREFIX: &str = "video";
+
+/// Run directory entries never bundled: caches and renders the recipient can recreate
+const EXCLUDED: &[&str] = &[crop_cache::CACHE_DIR, RENDERS_DIR];
+
+/// Saved crops, only bundled on request
+const CROP_ENTRIES: &[&str] = &[
+    crop_store::CROPS_DIR,
+    crop_store::PACK_FILE,
+    crop_store::INDEX_FILE,
+];
+
+/// What a bundle contains, stored as its first entry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BundleInfo {
+    pub bundle_version: u32,
+    /// Layout version of the bundled run directory
+    pub layout_version: u32,
+    pub producer_version: String,
+    pub exported_at: DateTime<Utc>,
+    pub run_id: String,
+    pub display_name: String,
+    /// File name of the source video, relative to the video root
+    pub video_name: String,
+    /// Size of the bundled video; `None` if the video is not in the bundle
+    pub video_bytes: Option<u64>,
+    pub includes_crops: bool,
+}
+
+/// What to put in a bundle besides the run's metadata and results
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct ExportOptions {
+    /// Saved crops (`crops/`, or `crops.tar` and `crops.idx`)
+    pub crops: bool,
+    /// The source video
+    pub video: bool,
+}
+
+/// Writes a bundle of the run to `out`.
+pub fn export<W: Write>(
+    run_context: &RunContext,
+    video_root: &Path,
+    options: ExportOptions,
+    out: W,
+) -> Result<BundleInfo> {
+    let video_path = run_context.resolve_video_path(video_root);
+    let video_name = video_file_name(&run_context.original_name)?;
+    let video_bytes = if options.video {
+        let metadata = fs::metadata(&video_path)
+            .with_context(|| format!("Video file NOT FOUND at: {:?}", video_path))?;
+        Some(metadata.len())
+    } else {
+        None
+    };
+
+    let info = BundleInfo {
+        bundle_version: BUNDLE_VERSION,
+        layout_version: Manifest::load(&run_context.output_dir)?.layout_version,
+        producer_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: Utc::now(),
+        run_id: run_context.run_id.clone(),
+        display_name: run_context.display_name.clone(),
+        video_name: video_name.clone(),
+        video_bytes,
+        includes_crops: options.crops,
+    };
+
+    let encoder = zstd::Encoder::new(out, 3)?;
+    let mut builder = tar::Builder::new(encoder);
+    append_bytes(&mut builder, INFO_FILE, &serde_json::to_vec_pretty(&info)?)?;
+
+    let mut metadata = run_context.clone();
+    metadata.original_name = video_name.clone();
+    append_bytes(
+        &mut builder,
+        &format!("{}/metadata.json", RUN_PREFIX),
+        &serde_json::to_vec_pretty(&metadata)?,
+    )?;
+
+    let dir = &run_context.output_dir;
+    let entries = WalkDir::new(dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() > 1 || bundled(entry.file_name(), options));
+    for entry in entries {
+        let entry = entry?;
+        let rel = entry.path().strip_prefix(dir)?;
+        let name = entry.file_name().to_string_lossy();
+        if !entry.file_type().is_file()
+            || rel == Path::new("metadata.json")
+            || name.ends_with(".tmp")
+        {
+            continue;
+        }
+        builder.append_path_with_name(entry.path(), Path::new(RUN_PREFIX).join(rel))?;
+    }
+
+    if options.video {
+        builder.append_path_with_name(&video_path, Path::new(VIDEO_PREFIX).join(&video_name))?;
+    }
+
+    builder.into_inner()?.finish()?.flush()?;
+    Ok(info)
+}
+
+/// Whether the top-level run directory entry `name` goes into a bundle
+fn bundled(name: &std::ffi::OsStr, options: ExportOptions) -> bool {
+    let name = name.to_string_lossy();
+    if EXCLUDED.contains(&name.as_ref()) {
+        return false;
+    }
+    options.crops || !CROP_ENTRIES.contains(&name.as_ref())
+}
+
+fn append_bytes<W: Write>(builder: &mut tar::Builder<W>, path: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+/// File name of the video at `original_name`
+fn video_file_name(original_name: &str) -> Result<String> {
+    Path::new(original_name)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow::anyhow!("Invalid video name: {}", original_name))
+}
+
+/// Reads the description at the start of the bundle at `path`.
+pub fn read_info(path: &Path) -> Result<BundleInfo> {
+    let mut archive = tar::Archive::new(zstd::Decoder::new(File::open(path)?)?);
+    let mut entry = match archive.entries()?.next() {
+        Some(entry) => entry?,
+        None => anyhow::bail!("Bundle is empty"),
+    };
+    if entry.path()? != Path::new(INFO_FILE) {
+        anyhow::bail!("Not a run bundle: {} is missing", INFO_FILE);
+    }
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    let info: BundleInfo = serde_json::from_str(&content)?;
+    if !is_file_name(&info.video_name) {
+        anyhow::bail!("Invalid video name in bundle: {}", info.video_name);
+    }
+    if info.bundle_version > BUNDLE_VERSION {
+        anyhow::bail!(
+            "Bundle version {} is newer than this build's {}",
+            info.bundle_version,
+            BUNDLE_VERSION
+        );
+    }
+    if info.layout_version > LAYOUT_VERSION {
+        anyhow::bail!(
+            "Bundled run has layout version {}, newer than this build's {}",
+            info.layout_version,
+            LAYOUT_VERSION
+        );
+    }
+    Ok(info)
+}
+
+/// Reasons the bundle described by `info` cannot be imported as `run_id`: an
+/// existing run of that id, or a different video of the same name in `video_root`.
+pub fn conflicts(
+    info: &BundleInfo,
+    output_root: &Path,
+    video_root: &Path,
+    run_id: &str,
+) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    if output_root.join(run_id).exists() {
+        conflicts.push(format!("Run {} already exists", run_id));
+    }
+    if let Some(video_bytes) = info.video_bytes {
+        let video_path = video_root.join(&info.video_name);
+        if let Ok(metadata) = fs::metadata(&video_path) {
+            if metadata.len() != video_bytes {
+                conflicts.push(format!(
+                    "A different video named {} already exists",
+                    info.video_name
+                ));
+            }
+        }
+    }
+    conflicts
+}
+
+/// Whether `run_id` can name a run directory
+pub fn valid_run_id(run_id: &str) -> bool {
+    is_file_name(run_id) && !run_id.starts_with('.')
+}
+
+/// Whether `name` is a single path component that stays in its directory
+fn is_file_name(name: &str) -> bool {
+    matches!(
+        Path::new(name).components().collect::<Vec<_>>().as_slice(),
+        [Component::Normal(_)]
+    )
+}
+
+/// Imports the bundle at `path` as run `run_id` of `output_root`, extracting a
+/// bundled video into `video_root` unless it is already there.
+pub fn import(
+    path: &Path,
+    output_root: &Path,
+    video_root: &Path,
+    run_id: &str,
+) -> Result<RunContext> {
+    if !valid_run_id(run_id) {
+        anyhow::bail!("Invalid run id: {}", run_id);
+    }
+    let info = read_info(path)?;
+    if let Some(conflict) = conflicts(&info, output_root, video_root, run_id).first() {
+        anyhow::bail!("{}", conflict);
+    }
+
+    // Hidden until complete, so a failed import never shows up as a run
+    let staging = output_root.join(format!(".import-{}", run_id));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    let result = unpack(path, &info, &staging, video_root)
+        .and_then(|_| finish_import(&info, &staging, output_root, video_root, run_id));
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+fn unpack(path: &Path, info: &BundleInfo, staging: &Path, video_root: &Path) -> Result<()> {
+    let mut archive = tar::Archive::new(zstd::Decoder::new(File::open(path)?)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        if entry_path == Path::new(INFO_FILE) {
+            continue;
+        }
+        // Links could point the entries after them outside the run
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            anyhow::bail!("Unexpected entry {:?} in bundle", entry_path);
+        }
+        if let Ok(rel) = entry_path.strip_prefix(RUN_PREFIX) {
+            let dest = staging.join(relative(rel)?);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(&dest)?;
+        } else if let Ok(rel) = entry_path.strip_prefix(VIDEO_PREFIX) {
+            if rel != Path::new(&info.video_name) {
+                anyhow::bail!("Unexpected video {:?} in bundle", rel);
+            }
+            let dest = video_root.join(rel);
+            if dest.exists() {
+                // Same name and size, checked by `conflicts`
+                tracing::info!("Keeping existing video {:?}", dest);
+                continue;
+            }
+            let tmp = video_root.join(format!(".{}.tmp", info.video_name));
+            entry.unpack(&tmp)?;
+            fs::rename(tmp, dest)?;
+        } else {
+            anyhow::bail!("Unexpected entry {:?} in bundle", entry_path);
+        }
+    }
+    Ok(())
+}
+
+/// `path` if it stays inside the directory it is joined to
+fn relative(path: &Path) -> Result<PathBuf> {
+    if path.as_os_str().is_empty() || !path.components().all(|c| matches!(c, Component::Normal(_)))
+    {
+        anyhow::bail!("Unsafe path {:?} in bundle", path);
+    }
+    Ok(path.to_path_buf())
+}
+
+fn finish_import(
+    info: &BundleInfo,
+    staging: &Path,
+    output_root: &Path,
+    video_root: &Path,
+    run_id: &str,
+) -> Result<RunContext> {
+    // Bundles of older builds come in at their own layout
+    migrate_run(staging)?;
+    let mut run_context = RunContext::load(staging)?;
+    run_context.run_id = run_id.to_string();
+    // Absolute like `create_run` stores it once the video is in place
+    let video_path = video_root.join(&info.video_name);
+    run_context.original_name = match fs::canonicalize(&video_path) {
+        Ok(absolute_path) => absolute_path.to_string_lossy().into_owned(),
+        Err(_) => info.video_name.clone(),
+    };
+    run_context.save()?;
+
+    let output_dir = output_root.join(run_id);
+    if output_dir.exists() {
+        anyhow::bail!("Run {} already exists", run_id);
+    }
+    fs::rename(staging, &output_dir)?;
+    run_context.output_dir = output_dir;
+    tracing::info!(
+        "Imported run {} (exported as {} on {})",
+        run_id,
+        info.run_id,
+        info.exported_at
+    );
+    Ok(run_context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_ids_name_a_single_visible_directory() {
+        assert!(valid_run_id("game_2024-06-01"));
+        assert!(!valid_run_id(""));
+        assert!(!valid_run_id(".import-game"));
+        assert!(!valid_run_id("../game"));
+        assert!(!valid_run_id("a/b"));
+        assert!(!valid_run_id("/game"));
+    }
+
+    #[test]
+    fn bundle_skips_caches_and_optional_crops() {
+        let options = ExportOptions::default();
+        assert!(bundled("audit.json".as_ref(), options));
+        assert!(!bundled(crop_cache::CACHE_DIR.as_ref(), options));
+        assert!(!bundled(crop_store::PACK_FILE.as_ref(), options));
+        let options = ExportOptions {
+            crops: true,
+            video: false,
+        };
+        assert!(bundled(crop_store::CROPS_DIR.as_ref(), options));
+        assert!(!bundled(RENDERS_DIR.as_ref(), options));
+    }
+}
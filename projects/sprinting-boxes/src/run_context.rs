@@ -1,8 +1,17 @@
+use crate::detection::cascade::CascadeConfig;
+use crate::detection::pose::PoseConfig;
+use crate::detection::slicing::{NmsConfig, TilingPolicy};
+use crate::pipeline::crop_store::CropRetention;
+use crate::registry::{GameInfo, Registry};
+use crate::run_artifacts::{check_layout, config_hash};
+use crate::scoring::{CountingConfig, DiscTrackingSettings, PullSideSettings, StanceSettings};
+use crate::video::lens::LensProfile;
 use anyhow::{Context as _, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use ultimate_event_detection::{GameFormat, TeamSizeEstimate};
 use walkdir::WalkDir;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -11,9 +20,48 @@ pub struct RunContext {
     pub display_name: String,
     pub created_at: DateTime<Utc>,
     pub run_id: String,
-    pub team_size: u32,
+    /// Game format, stored as `game_format` plus the `team_size` it implies. Older
+    /// runs only stored `team_size`; their format is inferred from it.
+    #[serde(flatten, with = "game_format_serde")]
+    pub game_format: GameFormat,
+    /// How detection tiles are sized per crop. Older runs use fixed 640 px tiles.
+    #[serde(default)]
+    pub tiling: TilingPolicy,
+    /// How overlapping detections are resolved per crop and in the overview merge.
+    #[serde(default)]
+    pub nms: NmsConfig,
+    /// Coarse-to-fine detection cascade; off when absent.
+    #[serde(default)]
+    pub cascade: Option<CascadeConfig>,
+    /// Pose stage on end-zone crops; off when absent.
+    #[serde(default)]
+    pub pose: Option<PoseConfig>,
+    /// Lens correction for wide-angle, fisheye and 360° sources; crops and
+    /// polygons live in the rectified frame when set.
+    #[serde(default)]
+    pub lens: Option<LensProfile>,
+    /// Per-region confidence thresholds and calibration for player counts.
+    #[serde(default)]
+    pub counting: CountingConfig,
+    /// Thresholds and weights of the pull-side estimate at each cliff.
+    #[serde(default)]
+    pub pull_side: PullSideSettings,
+    /// Overrides of the pose stage's stance limits and weights; unset fields
+    /// follow the game format.
+    #[serde(default)]
+    pub stance: StanceSettings,
+    /// Disc confidence floor and trajectory limits, in seconds of video.
+    #[serde(default)]
+    pub disc_tracking: DiscTrackingSettings,
+    /// Which saved crops to keep once processing finishes; all by default.
+    #[serde(default)]
+    pub crop_retention: CropRetention,
     pub light_team_name: String,
     pub dark_team_name: String,
+    /// Registry teams, tournament and rosters of the game. The names above are
+    /// a copy, used for teams that are not linked.
+    #[serde(default)]
+    pub game: GameInfo,
     pub tags: Vec<String>,
     #[serde(default = "default_sample_rate")]
     pub sample_rate: f64,
@@ -35,10 +83,92 @@ pub struct RunContext {
     pub output_dir: PathBuf,
 }
 
+/// Team size inferred from pre-point line-ups by the last processing run, kept
+/// out of `metadata.json` so the feature worker never rewrites the run's settings
+pub const TEAM_SIZE_ESTIMATE_FILE: &str = "team_size_estimate.json";
+
+/// Serializable copy of `TeamSizeEstimate` stored in `team_size_estimate.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TeamSizeEstimateInfo {
+    pub team_size: u32,
+    pub confidence: f32,
+    pub plateaus: usize,
+    pub observed_count: f32,
+}
+
+impl From<&TeamSizeEstimate> for TeamSizeEstimateInfo {
+    fn from(estimate: &TeamSizeEstimate) -> Self {
+        Self {
+            team_size: estimate.team_size,
+            confidence: estimate.confidence,
+            plateaus: estimate.plateaus,
+            observed_count: estimate.observed_count,
+        }
+    }
+}
+
+impl TeamSizeEstimateInfo {
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(dir.join(TEAM_SIZE_ESTIMATE_FILE), content)?;
+        Ok(())
+    }
+
+    /// Loads the estimate of the run in `dir`; `None` if the last processing run
+    /// found no line-ups or the run was never processed.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(TEAM_SIZE_ESTIMATE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+}
+
 fn default_sample_rate() -> f64 {
     1.0
 }
 
+/// Serializes `GameFormat` as its lowercase name ("sevens", "fours", ...) next to
+/// the team size it implies, so readers of `metadata.json` get both.
+mod game_format_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use ultimate_event_detection::GameFormat;
+
+    #[derive(Serialize)]
+    struct Written {
+        team_size: u32,
+        game_format: &'static str,
+    }
+
+    #[derive(Deserialize)]
+    struct Stored {
+        #[serde(default)]
+        team_size: Option<u32>,
+        #[serde(default)]
+        game_format: Option<String>,
+    }
+
+    pub fn serialize<S: Serializer>(format: &GameFormat, serializer: S) -> Result<S::Ok, S::Error> {
+        Written {
+            team_size: format.team_size(),
+            game_format: format.as_str(),
+        }
+        .serialize(serializer)
+    }
+
+    /// An explicit format wins; a bare `team_size` picks the closest format.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GameFormat, D::Error> {
+        let stored = Stored::deserialize(deserializer)?;
+        match (stored.game_format, stored.team_size) {
+            (Some(format), _) => format.parse().map_err(serde::de::Error::custom),
+            (None, Some(team_size)) => Ok(GameFormat::from_team_size(team_size)),
+            (None, None) => Ok(GameFormat::default()),
+        }
+    }
+}
+
 impl RunContext {
     /// Creates a new `RunContext` instance with default values.
     pub fn new(video_name: &str, run_id: &str, output_dir: PathBuf) -> Self {
@@ -47,9 +177,20 @@ impl RunContext {
             display_name: run_id.to_string(),
             created_at: Utc::now(),
             run_id: run_id.to_string(),
-            team_size: 7,
+            game_format: GameFormat::default(),
+            tiling: TilingPolicy::default(),
+            nms: NmsConfig::default(),
+            cascade: None,
+            pose: None,
+            lens: None,
+            counting: CountingConfig::default(),
+            pull_side: PullSideSettings::default(),
+            stance: StanceSettings::default(),
+            disc_tracking: DiscTrackingSettings::default(),
+            crop_retention: CropRetention::default(),
             light_team_name: "Light".to_string(),
             dark_team_name: "Dark".to_string(),
+            game: GameInfo::default(),
             tags: Vec::new(),
             sample_rate: 1.0,
             total_frames: 0,
@@ -63,14 +204,71 @@ impl RunContext {
         }
     }
 
+    /// Names of the light and dark team: the registry's for linked teams, the
+    /// stored ones otherwise.
+    pub fn team_names(&self, registry: &Registry) -> (String, String) {
+        let name = |id: &Option<String>, stored: &String| {
+            id.as_deref()
+                .and_then(|id| registry.team(id))
+                .map_or_else(|| stored.clone(), |team| team.name.clone())
+        };
+        (
+            name(&self.game.light_team_id, &self.light_team_name),
+            name(&self.game.dark_team_id, &self.dark_team_name),
+        )
+    }
+
+    /// Loads the run whose `metadata.json` lives in `output_dir`. Read-only: runs
+    /// are migrated at server startup and on import (see `run_artifacts::migrate_runs`).
+    pub fn load(output_dir: &Path) -> Result<Self> {
+        check_layout(output_dir)?;
+        let content = fs::read_to_string(output_dir.join("metadata.json"))?;
+        let mut run_context: RunContext = serde_json::from_str(&content)?;
+        run_context.output_dir = output_dir.to_path_buf();
+        Ok(run_context)
+    }
+
+    /// Adopts a team-size estimate, switching the game format when the estimate
+    /// disagrees with the current one.
+    pub fn accept_team_size_estimate(&mut self, estimate: &TeamSizeEstimateInfo) {
+        if self.game_format.team_size() != estimate.team_size {
+            self.game_format = GameFormat::from_team_size(estimate.team_size);
+        }
+    }
+
     /// Saves the metadata to `metadata.json` in the output directory.
     pub fn save(&self) -> Result<()> {
         let metadata_path = self.output_dir.join("metadata.json");
         let content = serde_json::to_string_pretty(self)?;
         fs::write(metadata_path, content)?;
+        Manifest::record(&self.output_dir, "metadata.json", None)?;
         Ok(())
     }
 
+    /// Hash of everything that shapes a processing run's outputs: the processing
+    /// settings in `metadata.json`, `crops.json` and `preprocess.json`.
+    pub fn config_hash(&self) -> String {
+        let settings = serde_json::json!({
+            "game_format": self.game_format.as_str(),
+            "sample_rate": self.sample_rate,
+            "tiling": self.tiling,
+            "nms": self.nms,
+            "cascade": self.cascade,
+            "pose": self.pose,
+            "lens": self.lens,
+            "counting": self.counting,
+            "pull_side": self.pull_side,
+            "stance": self.stance,
+            "disc_tracking": self.disc_tracking,
+        });
+        let read = |name: &str| fs::read(self.output_dir.join(name)).unwrap_or_default();
+        config_hash(&[
+            settings.to_string().as_bytes(),
+            &read("crops.json"),
+            &read("preprocess.json"),
+        ])
+    }
+
     /// Resolves the absolute path to the video file, handling potential path mismatches.
     pub fn resolve_video_path(&self, video_root: &Path) -> PathBuf {
         let original_path = Path::new(&self.original_name);
@@ -169,7 +367,10 @@ pub struct RunDependency {
 }
 
 // Re-export artifact types from the dedicated module
-pub use crate::run_artifacts::{BBox, CropConfigData, CropsConfig, FieldBoundaries, Point};
+pub use crate::run_artifacts::{
+    BBox, CropConfigData, CropsConfig, FieldBoundaries, Manifest, Point, PreprocessConfig,
+    PreprocessStep,
+};
 
 impl RunContext {
     /// Loads field boundaries from the run's field_boundaries.json.
@@ -184,10 +385,17 @@ impl RunContext {
     pub fn compute_and_save_crop_configs(&self) -> Result<CropsConfig> {
         let boundaries = self.load_field_boundaries()?;
 
-        // Get global polygons for all zones
-        let field_global = boundaries.get_global_points(&boundaries.field);
-        let left_global = boundaries.get_global_points(&boundaries.left_end_zone);
-        let right_global = boundaries.get_global_points(&boundaries.right_end_zone);
+        // Get global polygons for all zones, in the rectified frame if there is a lens
+        let global = |points: &[Point]| {
+            crate::geometry::rectify_polygon(
+                &boundaries.get_global_points(points),
+                self.lens.as_ref(),
+            )
+            .ok_or_else(|| anyhow::anyhow!("Boundary point outside the lens profile's view"))
+        };
+        let field_global = global(&boundaries.field)?;
+        let left_global = global(&boundaries.left_end_zone)?;
+        let right_global = global(&boundaries.right_end_zone)?;
 
         // Parameters
         const CROP_PADDING: f32 = 0.01; // 1% crop padding
@@ -249,11 +457,22 @@ impl RunContext {
             left_end_zone_polygon: left_effective,
             right_end_zone_polygon: right_effective,
             field_polygon: field_global,
+            exclusion_polygons: boundaries
+                .exclusions
+                .iter()
+                .map(|polygon| global(polygon))
+                .collect::<Result<_>>()?,
         };
 
         let crops_path = self.output_dir.join("crops.json");
         let content = serde_json::to_string_pretty(&crops)?;
         fs::write(crops_path, content)?;
+        let source = serde_json::to_vec(&(&boundaries, &self.lens))?;
+        Manifest::record(
+            &self.output_dir,
+            "crops.json",
+            Some(config_hash(&[&source])),
+        )?;
 
         Ok(crops)
     }
@@ -265,6 +484,27 @@ impl RunContext {
         let crops: CropsConfig = serde_json::from_str(&content)?;
         Ok(crops)
     }
+
+    /// Loads the crop preprocessing chains from preprocess.json, or the default
+    /// (CLAHE only) if none were saved.
+    pub fn load_preprocess_config(&self) -> Result<PreprocessConfig> {
+        let path = self.output_dir.join("preprocess.json");
+        if !path.exists() {
+            return Ok(PreprocessConfig::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        let config: PreprocessConfig = serde_json::from_str(&content)?;
+        Ok(config)
+    }
+
+    /// Saves the crop preprocessing chains to preprocess.json, next to crops.json.
+    pub fn save_preprocess_config(&self, config: &PreprocessConfig) -> Result<()> {
+        config.validate()?;
+        let path = self.output_dir.join("preprocess.json");
+        fs::write(path, serde_json::to_string_pretty(config)?)?;
+        Manifest::record(&self.output_dir, "preprocess.json", None)?;
+        Ok(())
+    }
 }
 
 /// Lists all video files (MP4, WebM) within the specified root directory, returning paths relative to video_root.
@@ -367,6 +607,8 @@ pub fn create_run(output_root: &Path, video_root: &Path, video_name: &str) -> Re
     }
 
     fs::create_dir_all(&output_dir)?;
+    // A new run starts at the current layout, with nothing to migrate
+    Manifest::current().save(&output_dir)?;
 
     // Resolve absolute path to video
     let full_path = video_root.join(video_name);
@@ -391,8 +633,38 @@ pub fn create_run(output_root: &Path, video_root: &Path, video_name: &str) -> Re
     Ok(run_context)
 }
 
-/// Scans the output root for existing runs and returns their metadata.
+/// Returns the metadata of the runs in the output root, from the run catalogue
+/// (see `catalogue`) or, if that is unusable, by scanning the directory.
 pub fn list_runs(output_root: &Path) -> Result<Vec<(String, RunContext)>> {
+    match crate::catalogue::list_runs(output_root) {
+        Ok(runs) => Ok(runs),
+        Err(e) => {
+            tracing::warn!(
+                "Run catalogue unavailable, scanning {:?}: {}",
+                output_root,
+                e
+            );
+            scan_runs(output_root)
+        }
+    }
+}
+
+/// Loads run `run_id` of the output root; `None` if there is no such run.
+pub fn find_run(output_root: &Path, run_id: &str) -> Result<Option<RunContext>> {
+    if !crate::run_bundle::valid_run_id(run_id) {
+        return Ok(None);
+    }
+    let path = output_root.join(run_id);
+    if !path.join("metadata.json").exists() {
+        return Ok(None);
+    }
+    let mut run_context = RunContext::load(&path)?;
+    run_context.run_id = run_id.to_string();
+    Ok(Some(run_context))
+}
+
+/// Scans the output root for existing runs and returns their metadata.
+fn scan_runs(output_root: &Path) -> Result<Vec<(String, RunContext)>> {
     let mut outputs = Vec::new();
 
     if !output_root.exists() {
@@ -402,12 +674,14 @@ pub fn list_runs(output_root: &Path) -> Result<Vec<(String, RunContext)>> {
     for entry in fs::read_dir(output_root)? {
         let entry = entry?;
         let path = entry.path();
+        // Hidden directories are imports in progress
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
         if path.is_dir() {
             let metadata_path = path.join("metadata.json");
             if metadata_path.exists() {
-                let content = fs::read_to_string(metadata_path)?;
-                let mut run_context: RunContext = serde_json::from_str(&content)?;
-                run_context.output_dir = path.clone();
+                let mut run_context = RunContext::load(&path)?;
                 let name = path
                     .file_name()
                     .and_then(|s| s.to_str())
@@ -424,3 +698,46 @@ pub fn list_runs(output_root: &Path) -> Result<Vec<(String, RunContext)>> {
 
     Ok(outputs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(edit: impl FnOnce(&mut serde_json::Map<String, serde_json::Value>)) -> RunContext {
+        let run_context = RunContext::new("game.mp4", "game", PathBuf::new());
+        let mut value = serde_json::to_value(&run_context).unwrap();
+        edit(value.as_object_mut().unwrap());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn team_size_follows_the_game_format() {
+        let mut run_context = RunContext::new("game.mp4", "game", PathBuf::new());
+        run_context.game_format = GameFormat::Beach;
+        let value = serde_json::to_value(&run_context).unwrap();
+        assert_eq!(value["game_format"], "beach");
+        assert_eq!(value["team_size"], 5);
+
+        // A stale team_size next to an explicit format is ignored
+        let run_context = stored(|m| {
+            m.insert("game_format".into(), "fours".into());
+            m.insert("team_size".into(), 7.into());
+        });
+        assert_eq!(run_context.game_format, GameFormat::Fours);
+    }
+
+    #[test]
+    fn runs_without_a_format_infer_it_from_team_size() {
+        let run_context = stored(|m| {
+            m.remove("game_format");
+            m.insert("team_size".into(), 5.into());
+        });
+        assert_eq!(run_context.game_format, GameFormat::Fives);
+
+        let run_context = stored(|m| {
+            m.insert("game_format".into(), serde_json::Value::Null);
+            m.remove("team_size");
+        });
+        assert_eq!(run_context.game_format, GameFormat::Sevens);
+    }
+}
@@ -3,25 +3,280 @@
 //! Provides scoring functions for pre-point detection, frame metrics calculation,
 //! and frame history tracking for the feature pipeline.
 
-use crate::geometry::is_point_in_polygon_robust;
-use crate::pipeline::types::DetectedFrame;
-use ultimate_event_detection::{pre_point_score, EndZoneOccupancy};
+use crate::config::DetectorConfig;
+use crate::geometry::{is_point_in_polygon_robust, PreparedRegions};
+use crate::pipeline::types::{DetectedFrame, ExcludedCounts, ExpectedCounts};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use ultimate_event_detection::{
+    pre_point_score, stance_adjusted_score, BystanderConfig, BystanderFilter,
+    ConfidenceCalibration, DiscSighting, DiscTracker, DiscTrackerConfig, EndZoneOccupancy,
+    Exclusion, ExpectedCount, GameFormat, PersonObservation, PoseFeatures, PullSide,
+    PullSideConfig, StanceConfig, TeamSizeConfig,
+};
+
+/// Which person detections count, and how much, stored per run in `metadata.json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CountingConfig {
+    /// Minimum person confidence in regions without their own threshold
+    #[serde(default = "default_min_conf")]
+    pub min_conf: f32,
+    /// Minimum person confidence per region (`left`, `right`, `field`), e.g. lower
+    /// for an end zone in shadow
+    #[serde(default)]
+    pub region_min_conf: BTreeMap<String, f32>,
+    /// Platt scaling of confidences into probabilities for the expected counts;
+    /// the defaults leave confidences as they are and keep scoring on integer counts
+    #[serde(default = "default_calibration_slope")]
+    pub calibration_slope: f32,
+    #[serde(default)]
+    pub calibration_offset: f32,
+}
+
+fn default_min_conf() -> f32 {
+    0.5
+}
+
+fn default_calibration_slope() -> f32 {
+    1.0
+}
+
+impl Default for CountingConfig {
+    fn default() -> Self {
+        Self {
+            min_conf: default_min_conf(),
+            region_min_conf: BTreeMap::new(),
+            calibration_slope: default_calibration_slope(),
+            calibration_offset: 0.0,
+        }
+    }
+}
+
+impl CountingConfig {
+    pub fn min_conf_for(&self, region: &str) -> f32 {
+        self.region_min_conf
+            .get(region)
+            .copied()
+            .unwrap_or(self.min_conf)
+    }
+
+    /// Lowest threshold of any region: the detector keeps everything from here, so
+    /// a region threshold below `min_conf` still has detections to count
+    pub fn detector_min_conf(&self) -> f32 {
+        self.region_min_conf
+            .values()
+            .copied()
+            .fold(self.min_conf, f32::min)
+    }
+
+    /// Whether a calibration is configured, so the expected counts are worth scoring on
+    pub fn is_calibrated(&self) -> bool {
+        self.calibration() != ConfidenceCalibration::default()
+    }
+
+    pub fn calibration(&self) -> ConfidenceCalibration {
+        ConfidenceCalibration {
+            slope: self.calibration_slope,
+            offset: self.calibration_offset,
+        }
+    }
+}
+
+/// Pull-side estimator settings, stored per run in `metadata.json`. Mirrors
+/// `PullSideConfig`; missing fields take its defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PullSideSettings {
+    pub debounce_frames: usize,
+    pub post_window: usize,
+    pub com_displacement_scale: f32,
+    pub field_growth_scale: f32,
+    pub slope_weight: f32,
+    pub com_weight: f32,
+    pub zero_weight: f32,
+    pub disc_weight: f32,
+    pub min_confidence: f32,
+}
+
+impl Default for PullSideSettings {
+    fn default() -> Self {
+        let config = PullSideConfig::default();
+        Self {
+            debounce_frames: config.debounce_frames,
+            post_window: config.post_window,
+            com_displacement_scale: config.com_displacement_scale,
+            field_growth_scale: config.field_growth_scale,
+            slope_weight: config.slope_weight,
+            com_weight: config.com_weight,
+            zero_weight: config.zero_weight,
+            disc_weight: config.disc_weight,
+            min_confidence: config.min_confidence,
+        }
+    }
+}
+
+impl From<&PullSideSettings> for PullSideConfig {
+    fn from(settings: &PullSideSettings) -> Self {
+        Self {
+            debounce_frames: settings.debounce_frames,
+            post_window: settings.post_window,
+            com_displacement_scale: settings.com_displacement_scale,
+            field_growth_scale: settings.field_growth_scale,
+            slope_weight: settings.slope_weight,
+            com_weight: settings.com_weight,
+            zero_weight: settings.zero_weight,
+            disc_weight: settings.disc_weight,
+            min_confidence: settings.min_confidence,
+        }
+    }
+}
+
+/// Stance limits and weights of the pose stage, stored per run in `metadata.json`.
+/// Mirrors `StanceConfig`; missing fields follow the game format's
+/// `stance_config()`, so the line geometry keeps up with format changes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct StanceSettings {
+    pub max_lean: Option<f32>,
+    pub max_shoulder_ratio: Option<f32>,
+    pub min_line_players: Option<usize>,
+    pub max_line_spread: Option<f32>,
+    pub max_line_gap: Option<f32>,
+    pub min_arm_extension: Option<f32>,
+    pub max_wrist_drop: Option<f32>,
+    pub lined_up_weight: Option<f32>,
+    pub throwing_weight: Option<f32>,
+}
+
+impl StanceSettings {
+    /// The stance config for `game_format` with these settings applied.
+    pub fn config_for(&self, game_format: GameFormat) -> StanceConfig {
+        let base = game_format.stance_config();
+        StanceConfig {
+            max_lean: self.max_lean.unwrap_or(base.max_lean),
+            max_shoulder_ratio: self.max_shoulder_ratio.unwrap_or(base.max_shoulder_ratio),
+            min_line_players: self.min_line_players.unwrap_or(base.min_line_players),
+            max_line_spread: self.max_line_spread.unwrap_or(base.max_line_spread),
+            max_line_gap: self.max_line_gap.unwrap_or(base.max_line_gap),
+            min_arm_extension: self.min_arm_extension.unwrap_or(base.min_arm_extension),
+            max_wrist_drop: self.max_wrist_drop.unwrap_or(base.max_wrist_drop),
+            lined_up_weight: self.lined_up_weight.unwrap_or(base.lined_up_weight),
+            throwing_weight: self.throwing_weight.unwrap_or(base.throwing_weight),
+        }
+    }
+}
+
+impl From<&StanceConfig> for StanceSettings {
+    fn from(config: &StanceConfig) -> Self {
+        Self {
+            max_lean: Some(config.max_lean),
+            max_shoulder_ratio: Some(config.max_shoulder_ratio),
+            min_line_players: Some(config.min_line_players),
+            max_line_spread: Some(config.max_line_spread),
+            max_line_gap: Some(config.max_line_gap),
+            min_arm_extension: Some(config.min_arm_extension),
+            max_wrist_drop: Some(config.max_wrist_drop),
+            lined_up_weight: Some(config.lined_up_weight),
+            throwing_weight: Some(config.throwing_weight),
+        }
+    }
+}
+
+/// Disc detection and tracking settings, stored per run in `metadata.json`. Speeds
+/// and gaps are per second of video and scaled to the run's sample rate by
+/// `config_for`; missing fields match `DiscTrackerConfig` at one frame per second.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DiscTrackingSettings {
+    /// Minimum disc detection confidence
+    pub min_conf: f32,
+    /// Fastest a disc moves, in overview fractions per second
+    pub max_speed: f32,
+    pub gate_margin: f32,
+    /// Longest a track survives without a sighting, in seconds
+    pub max_gap_secs: f32,
+    pub min_sightings: usize,
+    /// Distance from its first sighting a track must cover to count as airborne
+    pub min_travel: f32,
+    /// Average speed from the first sighting, per second, to count as airborne
+    pub min_speed: f32,
+}
+
+impl Default for DiscTrackingSettings {
+    fn default() -> Self {
+        let config = DiscTrackerConfig::default();
+        Self {
+            min_conf: 0.3,
+            max_speed: config.max_step,
+            gate_margin: config.gate_margin,
+            max_gap_secs: config.max_gap as f32,
+            min_sightings: config.min_sightings,
+            min_travel: config.min_travel,
+            min_speed: config.min_speed,
+        }
+    }
+}
+
+impl DiscTrackingSettings {
+    /// The tracker config for frames sampled at `sample_rate` per second.
+    pub fn config_for(&self, sample_rate: f64) -> DiscTrackerConfig {
+        let rate = if sample_rate > 0.0 {
+            sample_rate as f32
+        } else {
+            1.0
+        };
+        DiscTrackerConfig {
+            max_step: self.max_speed / rate,
+            gate_margin: self.gate_margin,
+            max_gap: (self.max_gap_secs * rate).round().max(1.0) as usize,
+            min_sightings: self.min_sightings,
+            min_travel: self.min_travel,
+            min_speed: self.min_speed / rate,
+        }
+    }
+}
 
 /// Feature extraction configuration (re-exported from feature module for convenience)
 pub struct FeatureConfig {
-    pub team_size: usize,
+    pub game_format: GameFormat,
     pub lookback_frames: usize,
     pub lookahead_frames: usize,
     pub output_dir: std::path::PathBuf,
+    pub pull_side: PullSideConfig,
+    pub team_size_estimation: TeamSizeConfig,
+    /// Overview regions prepared once per run; when `None`, each frame's own
+    /// overview regions are prepared on the fly
+    pub regions: Option<PreparedRegions>,
+    /// Weights for folding pose features into the pre-point score
+    pub stance: StanceConfig,
+    /// Learned bystander filtering; `None` counts everyone outside exclusion polygons
+    pub bystanders: Option<BystanderConfig>,
+    /// Disc trajectory tracking; `None` ignores disc detections
+    pub disc_tracking: Option<DiscTrackerConfig>,
+    /// Per-region confidence thresholds and calibration of the expected counts
+    pub counting: CountingConfig,
+    /// Run metadata written into the schemas of the Parquet exports
+    pub run_metadata: std::collections::HashMap<String, String>,
+    /// Cliff detector settings, read once when processing starts
+    pub detector: DetectorConfig,
 }
 
 impl Default for FeatureConfig {
     fn default() -> Self {
         Self {
-            team_size: 7,
+            game_format: GameFormat::default(),
             lookback_frames: 10,
             lookahead_frames: 15,
             output_dir: std::path::PathBuf::from("."),
+            pull_side: PullSideConfig::default(),
+            team_size_estimation: TeamSizeConfig::default(),
+            regions: None,
+            stance: StanceConfig::default(),
+            bystanders: Some(BystanderConfig::default()),
+            disc_tracking: Some(DiscTrackerConfig::default()),
+            counting: CountingConfig::default(),
+            run_metadata: std::collections::HashMap::new(),
+            detector: DetectorConfig::default(),
         }
     }
 }
@@ -31,9 +286,13 @@ impl Default for FeatureConfig {
 pub struct FrameHistory {
     pub left_count: f32,
     pub right_count: f32,
+    pub field_count: f32,
     pub com_x: Option<f32>,
     pub com_y: Option<f32>,
     pub std_dev: Option<f32>,
+    pub com_delta_x: Option<f32>,
+    /// End zone an in-flight disc was thrown from
+    pub disc_from: Option<PullSide>,
 }
 
 /// Calculate pre-point score based on normalized detection counts and team size.
@@ -66,52 +325,159 @@ pub fn calculate_deltas(frame: &mut DetectedFrame, prev_history: Option<&FrameHi
     }
 }
 
+/// This frame's own overview regions, when none were prepared for the run.
+fn local_regions(frame: &DetectedFrame, config: &FeatureConfig) -> Option<PreparedRegions> {
+    match config.regions {
+        Some(_) => None,
+        None => frame
+            .results
+            .iter()
+            .find(|r| r.suffix == "overview")
+            .map(|r| PreparedRegions::from_local(&r.regions)),
+    }
+}
+
+/// Feed the frame's disc detections to `tracker` and flag the end zone an
+/// in-flight disc was thrown from.
+pub fn track_disc(frame: &mut DetectedFrame, config: &FeatureConfig, tracker: &mut DiscTracker) {
+    let (crop_w, crop_h) = frame.overview_crop_dimensions();
+    let sightings: Vec<DiscSighting> = frame
+        .discs
+        .iter()
+        .map(|d| DiscSighting {
+            x: (d.bbox.x + d.bbox.w / 2.0) / crop_w,
+            y: (d.bbox.y + d.bbox.h / 2.0) / crop_h,
+            confidence: d.confidence,
+        })
+        .collect();
+    let flight = match tracker.push(frame.id, &sightings) {
+        Some(flight) => flight,
+        None => return,
+    };
+
+    let frame_regions = local_regions(frame, config);
+    let regions = match config.regions.as_ref().or(frame_regions.as_ref()) {
+        Some(regions) => regions,
+        None => return,
+    };
+    let (x, y) = (flight.origin.0 * crop_w, flight.origin.1 * crop_h);
+    let origin = regions.locate(x, y, crop_w, crop_h);
+    match origin.map(|i| regions.name(i)) {
+        Some("left") => frame.disc_from_left = true,
+        Some("right") => frame.disc_from_right = true,
+        _ => {}
+    }
+}
+
 /// Calculate normalized player counts and center-of-mass for a frame.
 ///
+/// The frame receives normalized counts; the returned counts are raw, before
+/// division by team size. Overview detections inside exclusion polygons, or
+/// judged bystanders by `bystanders`, are left out and tallied in `frame.excluded`.
+/// Detections below their region's `min_conf` keep their region flags but are
+/// not counted.
+///
+/// Alongside the integer counts, each region gets an expected count (sum of
+/// calibrated confidences) and its variance. With a calibration configured the
+/// pre-point score is computed from the expected counts, so a borderline detection
+/// nudges it instead of flipping it; otherwise it uses the integer counts.
+///
 /// Returns (left_count, right_count, field_count, pre_point_score, com_x, com_y)
 pub fn calculate_frame_metrics(
     frame: &mut DetectedFrame,
     config: &FeatureConfig,
+    mut bystanders: Option<&mut BystanderFilter>,
 ) -> (f32, f32, f32, f32, Option<f32>, Option<f32>) {
     let mut left_count = 0.0;
     let mut right_count = 0.0;
     let mut field_count = 0.0;
+    let mut left_expected = ExpectedCount::default();
+    let mut right_expected = ExpectedCount::default();
+    let mut field_expected = ExpectedCount::default();
+    let calibration = config.counting.calibration();
     let mut com_points = Vec::new();
     let has_overview = frame.results.iter().any(|r| r.suffix == "overview");
 
+    // Region geometry prepared once per run, or from this frame's overview regions
+    let frame_regions = local_regions(frame, config);
+    let regions = config.regions.as_ref().or(frame_regions.as_ref());
+    let mut overview_assignments: Vec<Option<usize>> = Vec::new();
+    let mut excluded = ExcludedCounts::default();
+
     // First pass: count and collect CoM points
     for result in frame.results.iter() {
         match result.suffix.as_str() {
             "overview" => {
-                // Two-pass: first classify by region, then track CoM
+                let regions = match regions {
+                    Some(regions) => regions,
+                    None => continue,
+                };
+                let (crop_w, crop_h) = (result.bbox.w, result.bbox.h);
+                let mut located = Vec::with_capacity(result.detections.len());
+                let mut people = Vec::new();
                 for detection in &result.detections {
-                    let mut in_valid_region = false;
+                    let ground_x = detection.bbox.x + detection.bbox.w / 2.0;
+                    let ground_y = detection.bbox.y + detection.bbox.h;
 
-                    // Check each region to classify this detection
-                    for region in &result.regions {
-                        if is_point_in_polygon_robust(
-                            detection.bbox.x + detection.bbox.w / 2.0,
-                            detection.bbox.y + detection.bbox.h,
-                            &region.effective_polygon,
-                        ) {
-                            in_valid_region = true;
-                            if region.name == "left" {
+                    // Classify once; the assignment is reused for the detection flags below
+                    let mut region = regions.locate(ground_x, ground_y, crop_w, crop_h);
+                    if region.is_some() && regions.is_excluded(ground_x, ground_y, crop_w, crop_h) {
+                        excluded.polygon += 1;
+                        region = None;
+                    }
+                    if region.is_some() {
+                        people.push((
+                            located.len(),
+                            PersonObservation {
+                                x: ground_x / crop_w,
+                                y: ground_y / crop_h,
+                                height: detection.bbox.h / crop_h,
+                            },
+                        ));
+                    }
+                    located.push((ground_x, ground_y, detection.confidence, region));
+                }
+
+                if let Some(filter) = bystanders.as_deref_mut() {
+                    let observations: Vec<_> = people.iter().map(|(_, p)| *p).collect();
+                    let verdicts = filter.observe(&observations);
+                    for ((index, _), verdict) in people.iter().zip(verdicts) {
+                        match verdict {
+                            Some(Exclusion::Stationary) => excluded.stationary += 1,
+                            Some(Exclusion::Perspective) => excluded.perspective += 1,
+                            None => continue,
+                        }
+                        located[*index].3 = None;
+                    }
+                }
+
+                for (ground_x, ground_y, confidence, region) in located {
+                    overview_assignments.push(region);
+
+                    if let Some(index) = region {
+                        let name = regions.name(index);
+                        // Below this region's threshold: flagged, but not counted
+                        if confidence < config.counting.min_conf_for(name) {
+                            continue;
+                        }
+                        let p = calibration.probability(confidence);
+                        match name {
+                            "left" => {
                                 left_count += 1.0;
-                            } else if region.name == "right" {
+                                left_expected.add(p);
+                            }
+                            "right" => {
                                 right_count += 1.0;
-                            } else if region.name == "field" {
+                                right_expected.add(p);
+                            }
+                            "field" => {
                                 field_count += 1.0;
+                                field_expected.add(p);
                             }
-                            break; // Only classify once
+                            _ => {}
                         }
-                    }
-
-                    // Only include detections in valid regions for CoM calculation
-                    if in_valid_region {
-                        com_points.push((
-                            detection.bbox.x + detection.bbox.w / 2.0,
-                            detection.bbox.y + detection.bbox.h,
-                        ));
+                        // Only include detections in valid regions for CoM calculation
+                        com_points.push((ground_x, ground_y));
                     }
                 }
             }
@@ -121,32 +487,43 @@ pub fn calculate_frame_metrics(
                     continue;
                 }
                 // Fallback: count directly if no overview crop
+                let min_conf = config.counting.min_conf_for(&result.suffix);
                 for detection in &result.detections {
-                    if detection.in_end_zone {
+                    if detection.in_end_zone && detection.confidence >= min_conf {
+                        let p = calibration.probability(detection.confidence);
                         if result.suffix == "left" {
                             left_count += 1.0;
+                            left_expected.add(p);
                         } else {
                             right_count += 1.0;
+                            right_expected.add(p);
                         }
                     }
                 }
             }
             _ => {
                 // Legacy: use effective_polygon if regions unavailable
+                let min_conf = config.counting.min_conf_for(&result.suffix);
                 for detection in &result.detections {
-                    if is_point_in_polygon_robust(
-                        detection.bbox.x + detection.bbox.w / 2.0,
-                        detection.bbox.y + detection.bbox.h / 2.0,
-                        &result.effective_polygon,
-                    ) {
+                    if detection.confidence >= min_conf
+                        && is_point_in_polygon_robust(
+                            detection.bbox.x + detection.bbox.w / 2.0,
+                            detection.bbox.y + detection.bbox.h / 2.0,
+                            &result.effective_polygon,
+                        )
+                    {
+                        let p = calibration.probability(detection.confidence);
                         if result.suffix == "left" || result.suffix == "right" {
                             if result.suffix == "left" {
                                 left_count += 1.0;
+                                left_expected.add(p);
                             } else {
                                 right_count += 1.0;
+                                right_expected.add(p);
                             }
                         } else {
                             field_count += 1.0;
+                            field_expected.add(p);
                         }
                     }
                 }
@@ -155,13 +532,38 @@ pub fn calculate_frame_metrics(
     }
 
     // Normalize counts by team size
-    let left_norm = left_count / config.team_size as f32;
-    let right_norm = right_count / config.team_size as f32;
-    let field_norm = field_count / config.team_size as f32;
+    let team_size = config.game_format.team_size() as usize;
+    let left_norm = left_count / team_size as f32;
+    let right_norm = right_count / team_size as f32;
+    let field_norm = field_count / team_size as f32;
 
-    // Calculate pre-point score
-    let pre_point_score =
-        calculate_pre_point_score(left_norm, right_norm, field_norm, config.team_size);
+    let expected = ExpectedCounts::from_counts(
+        left_expected.normalized(team_size as u32),
+        right_expected.normalized(team_size as u32),
+        field_expected.normalized(team_size as u32),
+    );
+
+    // Calculate pre-point score, from the expected counts once confidences are calibrated,
+    // sharpened by line-up stances and throws when poses are available
+    let mut pre_point_score = if config.counting.is_calibrated() {
+        calculate_pre_point_score(expected.left, expected.right, expected.field, team_size)
+    } else {
+        calculate_pre_point_score(left_norm, right_norm, field_norm, team_size)
+    };
+    if let (Some(left), Some(right)) = (frame.left_lined_up, frame.right_lined_up) {
+        pre_point_score = stance_adjusted_score(
+            pre_point_score,
+            &PoseFeatures {
+                lined_up: left,
+                throwing: frame.left_throwing.unwrap_or(false),
+            },
+            &PoseFeatures {
+                lined_up: right,
+                throwing: frame.right_throwing.unwrap_or(false),
+            },
+            &config.stance,
+        );
+    }
 
     let (com_x, com_y, std_dev) = if !com_points.is_empty() {
         let mean_x = com_points.iter().map(|(x, _)| x).sum::<f32>() / com_points.len() as f32;
@@ -196,54 +598,57 @@ pub fn calculate_frame_metrics(
     frame.left_count = left_norm;
     frame.right_count = right_norm;
     frame.field_count = field_norm;
+    frame.expected = expected;
     frame.pre_point_score = pre_point_score;
     frame.com_x = com_x;
     frame.com_y = com_y;
     frame.std_dev = std_dev;
+    frame.excluded = excluded;
 
     // Now update detection flags in a separate mutable pass
+    let mut assignments = overview_assignments.into_iter();
     for result in &mut frame.results {
         if result.suffix == "overview" {
+            let regions = match regions {
+                Some(regions) => regions,
+                None => continue,
+            };
             for detection in &mut result.detections {
-                let ground_x = detection.bbox.x + detection.bbox.w / 2.0;
-                let ground_y = detection.bbox.y + detection.bbox.h;
-                let mut found_region = false;
-                for region in &result.regions {
-                    if is_point_in_polygon_robust(ground_x, ground_y, &region.effective_polygon) {
-                        found_region = true;
-                        if region.name == "left" || region.name == "right" {
+                match assignments.next().flatten() {
+                    Some(index) => {
+                        let name = regions.name(index);
+                        if name == "left" || name == "right" {
                             detection.in_end_zone = true;
                             detection.in_field = false;
-                        } else if region.name == "field" {
+                        } else if name == "field" {
                             detection.in_field = true;
                             detection.in_end_zone = false;
                         }
                         tracing::trace!(
-                            ground_x,
-                            ground_y,
+                            ground_x = detection.bbox.x + detection.bbox.w / 2.0,
+                            ground_y = detection.bbox.y + detection.bbox.h,
                             bbox_x = detection.bbox.x,
                             bbox_y = detection.bbox.y,
                             bbox_w = detection.bbox.w,
                             bbox_h = detection.bbox.h,
-                            region = region.name.as_str(),
+                            region = name,
                             in_end_zone = detection.in_end_zone,
                             "detection region assignment"
                         );
-                        break;
                     }
-                }
-                if !found_region {
-                    detection.in_field = false;
-                    detection.in_end_zone = false;
+                    None => {
+                        detection.in_field = false;
+                        detection.in_end_zone = false;
+                    }
                 }
             }
         }
     }
 
     (
-        left_norm,
-        right_norm,
-        field_norm,
+        left_count,
+        right_count,
+        field_count,
         pre_point_score,
         com_x,
         com_y,
@@ -287,4 +692,82 @@ mod tests {
         // Just checking it doesn't panic
         let _ = ultimate_event_detection::is_cliff_at(&config, &probabilities, 14);
     }
+
+    #[test]
+    fn end_zone_threshold_below_the_default_counts_weaker_detections() {
+        let counting = CountingConfig {
+            region_min_conf: BTreeMap::from([("left".to_string(), 0.35)]),
+            ..CountingConfig::default()
+        };
+        assert_eq!(counting.detector_min_conf(), 0.35);
+
+        let crop = |suffix: &str, confidence: f32| {
+            serde_json::json!({
+                "suffix": suffix,
+                "detections": [{
+                    "bbox": { "x": 10.0, "y": 10.0, "w": 20.0, "h": 40.0 },
+                    "confidence": confidence,
+                    "class_id": 0,
+                    "in_end_zone": true,
+                    "in_field": false
+                }],
+                "original_polygon": [],
+                "effective_polygon": [],
+                "bbox": { "x": 0.0, "y": 0.0, "w": 100.0, "h": 100.0 },
+                "regions": []
+            })
+        };
+        let mut frame: DetectedFrame = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "results": [crop("left", 0.4), crop("right", 0.4)],
+            "left_count": 0.0,
+            "right_count": 0.0,
+            "field_count": 0.0,
+            "pre_point_score": 0.0,
+            "is_cliff": false,
+            "left_emptied_first": false,
+            "right_emptied_first": false,
+            "maybe_false_positive": false
+        }))
+        .unwrap();
+        let config = FeatureConfig {
+            counting,
+            bystanders: None,
+            ..FeatureConfig::default()
+        };
+
+        let (left, right, ..) = calculate_frame_metrics(&mut frame, &config, None);
+        assert_eq!(left, 1.0);
+        // The right end zone keeps the default 0.5
+        assert_eq!(right, 0.0);
+    }
+
+    #[test]
+    fn stance_settings_override_the_format_geometry() {
+        let settings = StanceSettings {
+            throwing_weight: Some(0.2),
+            ..StanceSettings::default()
+        };
+        let base = GameFormat::Fours.stance_config();
+        let config = settings.config_for(GameFormat::Fours);
+        assert_eq!(config.throwing_weight, 0.2);
+        assert_eq!(config.max_line_spread, base.max_line_spread);
+        assert_eq!(config.max_line_gap, base.max_line_gap);
+    }
+
+    #[test]
+    fn disc_tracking_scales_with_the_sample_rate() {
+        let settings = DiscTrackingSettings::default();
+        let default = DiscTrackerConfig::default();
+        let one_fps = settings.config_for(1.0);
+        assert_eq!(one_fps.max_step, default.max_step);
+        assert_eq!(one_fps.max_gap, default.max_gap);
+        assert_eq!(one_fps.min_speed, default.min_speed);
+
+        let five_fps = settings.config_for(5.0);
+        assert!((five_fps.max_step - default.max_step / 5.0).abs() < 1e-6);
+        assert!((five_fps.min_speed - default.min_speed / 5.0).abs() < 1e-6);
+        assert_eq!(five_fps.max_gap, default.max_gap * 5);
+        assert_eq!(five_fps.min_travel, default.min_travel);
+    }
 }
@@ -0,0 +1,386 @@
+//! Lens models for wide-angle and 360° sources.
+//!
+//! A run's `LensProfile` defines a rectified frame: the undistorted view of a
+//! wide-angle or fisheye camera, or a rectilinear view into equirectangular 360°
+//! footage. Crops are cut from the rectified frame, and polygons drawn on raw
+//! calibration frames are mapped into it (see `geometry::rectify_polygon`), so
+//! field lines that curve in the raw footage are straight where polygons are tested.
+//!
+//! Points are normalized frame coordinates in `[0, 1]`. Intrinsics are normalized
+//! the same way: `fx` and `cx` in frame widths, `fy` and `cy` in frame heights.
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// Lens model, stored per run in `metadata.json`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum LensProfile {
+    /// Brown-Conrady model of standard and wide lenses, as fitted by OpenCV's
+    /// `calibrateCamera`
+    Pinhole {
+        fx: f32,
+        fy: f32,
+        cx: f32,
+        cy: f32,
+        #[serde(default)]
+        k1: f32,
+        #[serde(default)]
+        k2: f32,
+        #[serde(default)]
+        p1: f32,
+        #[serde(default)]
+        p2: f32,
+        #[serde(default)]
+        k3: f32,
+        /// Focal length of the rectified view relative to the lens; below 1 keeps
+        /// more of the periphery
+        #[serde(default = "default_zoom")]
+        zoom: f32,
+    },
+    /// Equidistant fisheye model, as fitted by OpenCV's `fisheye::calibrate`
+    /// (GoPro and Insta360 wide modes)
+    Fisheye {
+        fx: f32,
+        fy: f32,
+        cx: f32,
+        cy: f32,
+        #[serde(default)]
+        k1: f32,
+        #[serde(default)]
+        k2: f32,
+        #[serde(default)]
+        k3: f32,
+        #[serde(default)]
+        k4: f32,
+        #[serde(default = "default_zoom")]
+        zoom: f32,
+    },
+    /// Rectilinear `width` x `height` view into an equirectangular 360° frame,
+    /// looking `yaw_deg` right and `pitch_deg` up from the frame center
+    Equirectangular {
+        yaw_deg: f32,
+        pitch_deg: f32,
+        hfov_deg: f32,
+        width: i32,
+        height: i32,
+    },
+}
+
+fn default_zoom() -> f32 {
+    1.0
+}
+
+impl LensProfile {
+    /// Pixel size of the rectified frame for a `src_w` x `src_h` source.
+    pub fn output_size(&self, src_w: i32, src_h: i32) -> (i32, i32) {
+        match *self {
+            LensProfile::Equirectangular { width, height, .. } => (width, height),
+            _ => (src_w, src_h),
+        }
+    }
+
+    /// Whether source pixels wrap around horizontally
+    pub fn wraps(&self) -> bool {
+        matches!(self, LensProfile::Equirectangular { .. })
+    }
+
+    /// Maps a rectified point to the raw source frame, or `None` if the lens
+    /// never sees it.
+    pub fn to_source(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        match *self {
+            LensProfile::Pinhole {
+                fx,
+                fy,
+                cx,
+                cy,
+                zoom,
+                ..
+            } => {
+                let (xd, yd) = self.distort((x - cx) / (fx * zoom), (y - cy) / (fy * zoom))?;
+                Some((cx + fx * xd, cy + fy * yd))
+            }
+            LensProfile::Fisheye {
+                fx,
+                fy,
+                cx,
+                cy,
+                zoom,
+                ..
+            } => {
+                let (xd, yd) = self.distort((x - cx) / (fx * zoom), (y - cy) / (fy * zoom))?;
+                Some((cx + fx * xd, cy + fy * yd))
+            }
+            LensProfile::Equirectangular { .. } => {
+                let (rx, ry, rz) = self.view_to_world(x, y);
+                let lon = rx.atan2(rz);
+                let lat = (ry / (rx * rx + ry * ry + rz * rz).sqrt()).asin();
+                Some((0.5 + lon / (2.0 * PI), 0.5 + lat / PI))
+            }
+        }
+    }
+
+    /// Maps a raw source point into the rectified frame, or `None` if it falls
+    /// outside the rectified view's half-space.
+    pub fn to_rectified(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        match *self {
+            LensProfile::Pinhole {
+                fx,
+                fy,
+                cx,
+                cy,
+                zoom,
+                ..
+            } => {
+                let (xu, yu) = self.undistort((x - cx) / fx, (y - cy) / fy)?;
+                Some((cx + fx * zoom * xu, cy + fy * zoom * yu))
+            }
+            LensProfile::Fisheye {
+                fx,
+                fy,
+                cx,
+                cy,
+                zoom,
+                ..
+            } => {
+                let (xu, yu) = self.undistort((x - cx) / fx, (y - cy) / fy)?;
+                Some((cx + fx * zoom * xu, cy + fy * zoom * yu))
+            }
+            LensProfile::Equirectangular {
+                yaw_deg,
+                pitch_deg,
+                hfov_deg,
+                width,
+                height,
+            } => {
+                let lon = (x - 0.5) * 2.0 * PI;
+                let lat = (y - 0.5) * PI;
+                let (wx, wy, wz) = (lat.cos() * lon.sin(), lat.sin(), lat.cos() * lon.cos());
+
+                // Inverse of `view_to_world`: undo yaw, then pitch
+                let (sin_yaw, cos_yaw) = yaw_deg.to_radians().sin_cos();
+                let (sin_pitch, cos_pitch) = pitch_deg.to_radians().sin_cos();
+                let vx = wx * cos_yaw - wz * sin_yaw;
+                let pz = wx * sin_yaw + wz * cos_yaw;
+                let vy = wy * cos_pitch + pz * sin_pitch;
+                let vz = -wy * sin_pitch + pz * cos_pitch;
+                if vz <= 0.0 {
+                    return None;
+                }
+
+                let focal = focal_px(width, hfov_deg);
+                Some((
+                    0.5 + focal * vx / vz / width as f32,
+                    0.5 + focal * vy / vz / height as f32,
+                ))
+            }
+        }
+    }
+
+    /// Ray through a rectified point of an equirectangular view, in world axes
+    /// (x right, y down, z forward at yaw = pitch = 0).
+    fn view_to_world(&self, x: f32, y: f32) -> (f32, f32, f32) {
+        let (yaw_deg, pitch_deg, hfov_deg, width, height) = match *self {
+            LensProfile::Equirectangular {
+                yaw_deg,
+                pitch_deg,
+                hfov_deg,
+                width,
+                height,
+            } => (yaw_deg, pitch_deg, hfov_deg, width, height),
+            _ => return (x, y, 1.0),
+        };
+        let focal = focal_px(width, hfov_deg);
+        let (vx, vy, vz) = ((x - 0.5) * width as f32, (y - 0.5) * height as f32, focal);
+
+        // Pitch up turns forward towards -y, then yaw right turns it towards +x
+        let (sin_pitch, cos_pitch) = pitch_deg.to_radians().sin_cos();
+        let py = vy * cos_pitch - vz * sin_pitch;
+        let pz = vy * sin_pitch + vz * cos_pitch;
+        let (sin_yaw, cos_yaw) = yaw_deg.to_radians().sin_cos();
+        (
+            vx * cos_yaw + pz * sin_yaw,
+            py,
+            -vx * sin_yaw + pz * cos_yaw,
+        )
+    }
+
+    /// Applies the lens distortion to ideal normalized camera coordinates.
+    fn distort(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        match *self {
+            LensProfile::Pinhole {
+                k1, k2, p1, p2, k3, ..
+            } => {
+                let r2 = x * x + y * y;
+                let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+                Some((
+                    x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x),
+                    y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y,
+                ))
+            }
+            LensProfile::Fisheye { k1, k2, k3, k4, .. } => {
+                let r = (x * x + y * y).sqrt();
+                if r < 1e-8 {
+                    return Some((x, y));
+                }
+                let theta = r.atan();
+                let t2 = theta * theta;
+                let theta_d = theta * (1.0 + t2 * (k1 + t2 * (k2 + t2 * (k3 + t2 * k4))));
+                Some((x * theta_d / r, y * theta_d / r))
+            }
+            LensProfile::Equirectangular { .. } => None,
+        }
+    }
+
+    /// Inverse of `distort`, by fixed-point iteration for the Brown-Conrady model
+    /// and Newton's method on the fisheye angle.
+    fn undistort(&self, xd: f32, yd: f32) -> Option<(f32, f32)> {
+        match *self {
+            LensProfile::Pinhole {
+                k1, k2, p1, p2, k3, ..
+            } => {
+                let (mut x, mut y) = (xd, yd);
+                for _ in 0..20 {
+                    let r2 = x * x + y * y;
+                    let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+                    let dx = 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x);
+                    let dy = p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y;
+                    x = (xd - dx) / radial;
+                    y = (yd - dy) / radial;
+                }
+                Some((x, y))
+            }
+            LensProfile::Fisheye { k1, k2, k3, k4, .. } => {
+                let theta_d = (xd * xd + yd * yd).sqrt();
+                if theta_d < 1e-8 {
+                    return Some((xd, yd));
+                }
+                let mut theta = theta_d;
+                for _ in 0..20 {
+                    let t2 = theta * theta;
+                    let f = theta * (1.0 + t2 * (k1 + t2 * (k2 + t2 * (k3 + t2 * k4)))) - theta_d;
+                    let df =
+                        1.0 + t2 * (3.0 * k1 + t2 * (5.0 * k2 + t2 * (7.0 * k3 + t2 * 9.0 * k4)));
+                    theta -= f / df;
+                }
+                // Rays at or beyond 90° have no rectilinear image
+                if !(0.0..FRAC_PI_2).contains(&theta) {
+                    return None;
+                }
+                let scale = theta.tan() / theta_d;
+                Some((xd * scale, yd * scale))
+            }
+            LensProfile::Equirectangular { .. } => None,
+        }
+    }
+}
+
+/// Focal length in pixels of a `width`-pixel view with horizontal FOV `hfov_deg`
+fn focal_px(width: i32, hfov_deg: f32) -> f32 {
+    width as f32 / 2.0 / (hfov_deg.to_radians() / 2.0).tan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: (f32, f32), b: (f32, f32)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn distortion_round_trips() {
+        let lenses = [
+            LensProfile::Pinhole {
+                fx: 0.6,
+                fy: 1.05,
+                cx: 0.5,
+                cy: 0.5,
+                k1: -0.28,
+                k2: 0.09,
+                p1: 0.001,
+                p2: -0.0005,
+                k3: 0.0,
+                zoom: 0.9,
+            },
+            LensProfile::Fisheye {
+                fx: 0.28,
+                fy: 0.5,
+                cx: 0.5,
+                cy: 0.48,
+                k1: 0.05,
+                k2: -0.01,
+                k3: 0.0,
+                k4: 0.0,
+                zoom: 0.7,
+            },
+            LensProfile::Equirectangular {
+                yaw_deg: 30.0,
+                pitch_deg: -15.0,
+                hfov_deg: 100.0,
+                width: 1920,
+                height: 1080,
+            },
+        ];
+        for lens in lenses {
+            for &(x, y) in &[(0.5, 0.5), (0.1, 0.2), (0.9, 0.85), (0.3, 0.7)] {
+                let source = lens.to_source(x, y).unwrap();
+                assert_close(lens.to_rectified(source.0, source.1).unwrap(), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn barrel_distortion_pulls_corners_inwards() {
+        let lens = LensProfile::Pinhole {
+            fx: 0.6,
+            fy: 1.05,
+            cx: 0.5,
+            cy: 0.5,
+            k1: -0.28,
+            k2: 0.0,
+            p1: 0.0,
+            p2: 0.0,
+            k3: 0.0,
+            zoom: 1.0,
+        };
+        assert_close(lens.to_source(0.5, 0.5).unwrap(), (0.5, 0.5));
+        let (x, y) = lens.to_source(0.9, 0.9).unwrap();
+        assert!(x < 0.9 && y < 0.9 && x > 0.5 && y > 0.5, "{} {}", x, y);
+    }
+
+    #[test]
+    fn equirectangular_view_looks_along_yaw_and_pitch() {
+        let lens = LensProfile::Equirectangular {
+            yaw_deg: 90.0,
+            pitch_deg: 0.0,
+            hfov_deg: 90.0,
+            width: 1000,
+            height: 500,
+        };
+        // The view's center is a quarter turn right of the panorama's center
+        assert_close(lens.to_source(0.5, 0.5).unwrap(), (0.75, 0.5));
+        // The right edge of a 90° view is 45° further
+        assert_close(lens.to_source(1.0, 0.5).unwrap(), (0.875, 0.5));
+        // Straight behind is outside the view
+        assert_eq!(lens.to_rectified(0.25, 0.5), None);
+
+        let tilted = LensProfile::Equirectangular {
+            yaw_deg: 0.0,
+            pitch_deg: -20.0,
+            hfov_deg: 90.0,
+            width: 1000,
+            height: 500,
+        };
+        // Looking down: the center is 20° below the horizon
+        assert_close(
+            tilted.to_source(0.5, 0.5).unwrap(),
+            (0.5, 0.5 + 20.0 / 180.0),
+        );
+    }
+}
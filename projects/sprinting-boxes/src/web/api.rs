@@ -1,7 +1,17 @@
+use crate::catalogue::{
+    self, CataloguePoint, CatalogueRun, PointFilter, RunFilter, StatsFilter, TeamStats,
+};
 use crate::cli::Args;
-use crate::run_context::{list_runs, list_videos, RunContext};
+use crate::pipeline::crop_store::{CompactionReport, CropRetention};
+use crate::pipeline::snapshot::{ConfigChange, ProcessingSnapshot};
+use crate::registry::Registry;
+use crate::run_artifacts::{disk_usage, DiskUsage, FieldBoundaries};
+use crate::run_bundle::{self, ExportOptions};
+use crate::run_context::{
+    find_run, list_runs, list_videos, Manifest, RunContext, TeamSizeEstimateInfo,
+};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
     Json,
 };
@@ -30,6 +40,8 @@ pub struct RunDetailResponse {
     pub run_id: String,
     pub run_context: RunContext,
     pub missing_dependencies: Vec<crate::run_context::RunDependency>,
+    /// Team size inferred by the last processing run, if it found line-ups
+    pub team_size_estimate: Option<TeamSizeEstimateInfo>,
 }
 
 pub async fn get_run_handler(
@@ -37,22 +49,25 @@ pub async fn get_run_handler(
     Path(run_id): Path<String>,
 ) -> Result<Json<RunDetailResponse>, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = crate::run_context::list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     let missing_dependencies = run_context.validate_process_run_dependencies();
+    let team_size_estimate =
+        TeamSizeEstimateInfo::load(&run_context.output_dir).unwrap_or_else(|e| {
+            tracing::warn!("Failed to load team-size estimate for {}: {}", run_id, e);
+            None
+        });
 
     Ok(Json(RunDetailResponse {
         run_id,
         run_context,
         missing_dependencies,
+        team_size_estimate,
     }))
 }
 
@@ -63,14 +78,11 @@ pub async fn extract_calibration_frames_handler(
     let output_root = std::path::Path::new(&args.output_root);
     let video_root = std::path::Path::new(&args.video_root);
 
-    let runs = crate::run_context::list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     match run_context.extract_calibration_frames(video_root) {
@@ -97,14 +109,11 @@ pub async fn get_calibration_frames_handler(
     Path(run_id): Path<String>,
 ) -> Result<Json<Vec<String>>, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = crate::run_context::list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     let dir = run_context.get_calibration_frames_dir();
@@ -152,18 +161,67 @@ pub async fn serve_calibration_frame_handler(
     }
 }
 
+pub async fn get_boundaries_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let boundaries_path = output_root.join(&run_id).join("field_boundaries.json");
+
+    let content =
+        std::fs::read_to_string(boundaries_path).map_err(|_| axum::http::StatusCode::NOT_FOUND)?;
+    match serde_json::from_str(&content) {
+        Ok(boundaries) => Ok(Json(boundaries)),
+        Err(e) => {
+            tracing::error!("Invalid field boundaries for {}: {}", run_id, e);
+            Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Exclusions of the stored boundaries at `path`, moved into the ROI of `payload`;
+/// `None` if there are none to keep.
+fn stored_exclusions(
+    path: &std::path::Path,
+    payload: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    let stored: FieldBoundaries =
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    if stored.exclusions.is_empty() {
+        return None;
+    }
+    let new: FieldBoundaries = serde_json::from_value(payload.clone()).ok()?;
+    let exclusions: Vec<_> = stored
+        .exclusions
+        .iter()
+        .map(|polygon| new.get_roi_points(&stored.get_global_points(polygon)))
+        .collect();
+    serde_json::to_value(exclusions).ok()
+}
+
 pub async fn save_boundaries_handler(
     State(args): State<Arc<Args>>,
     Path(run_id): Path<String>,
-    Json(payload): Json<serde_json::Value>,
+    Json(mut payload): Json<serde_json::Value>,
 ) -> Result<Json<bool>, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let boundaries_path = output_root.join(&run_id).join("field_boundaries.json");
+    let run_dir = output_root.join(&run_id);
+    let boundaries_path = run_dir.join("field_boundaries.json");
+
+    // Exclusions are not drawn in the editor; a save without them keeps the stored ones
+    if payload.get("exclusions").is_none() {
+        if let Some(exclusions) = stored_exclusions(&boundaries_path, &payload) {
+            payload["exclusions"] = exclusions;
+        }
+    }
 
     match std::fs::write(
         boundaries_path,
         serde_json::to_string_pretty(&payload).unwrap(),
-    ) {
+    )
+    .map_err(anyhow::Error::from)
+    .and_then(|_| Manifest::record(&run_dir, "field_boundaries.json", None))
+    {
         Ok(_) => Ok(Json(true)),
         Err(e) => {
             tracing::error!("Failed to save field boundaries for {}: {}", run_id, e);
@@ -177,14 +235,11 @@ pub async fn compute_crops_handler(
     Path(run_id): Path<String>,
 ) -> Result<Json<crate::run_context::CropsConfig>, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = crate::run_context::list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     match run_context.compute_and_save_crop_configs() {
@@ -201,14 +256,11 @@ pub async fn get_crops_handler(
     Path(run_id): Path<String>,
 ) -> Result<Json<crate::run_context::CropsConfig>, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = crate::run_context::list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     match run_context.load_crop_configs() {
@@ -220,18 +272,314 @@ pub async fn get_crops_handler(
     }
 }
 
+pub async fn get_preprocess_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<crate::run_context::PreprocessConfig>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    match run_context.load_preprocess_config() {
+        Ok(config) => Ok(Json(config)),
+        Err(e) => {
+            tracing::error!("Failed to load preprocessing config for {}: {}", run_id, e);
+            Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Loads the processing record of `run_id`; 404 if the run is unknown or was
+/// never processed with records enabled.
+fn load_processing_snapshot(
+    output_root: &std::path::Path,
+    run_id: &str,
+) -> Result<ProcessingSnapshot, axum::http::StatusCode> {
+    let run_context = find_run(output_root, run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+    match ProcessingSnapshot::load(&run_context.output_dir) {
+        Ok(Some(snapshot)) => Ok(snapshot),
+        Ok(None) => Err(axum::http::StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to load processing config for {}: {}", run_id, e);
+            Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn get_processing_config_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<ProcessingSnapshot>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    Ok(Json(load_processing_snapshot(output_root, &run_id)?))
+}
+
+#[derive(Serialize)]
+pub struct ConfigDiffResponse {
+    pub left: String,
+    pub right: String,
+    pub changes: Vec<ConfigChange>,
+}
+
+/// Parameters that differ between the processing records of two runs
+pub async fn diff_processing_config_handler(
+    State(args): State<Arc<Args>>,
+    Path((run_id, other_id)): Path<(String, String)>,
+) -> Result<Json<ConfigDiffResponse>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let left = load_processing_snapshot(output_root, &run_id)?;
+    let right = load_processing_snapshot(output_root, &other_id)?;
+    let changes = crate::pipeline::snapshot::diff(&left, &right).map_err(|e| {
+        tracing::error!("Failed to diff {} and {}: {}", run_id, other_id, e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(ConfigDiffResponse {
+        left: run_id,
+        right: other_id,
+        changes,
+    }))
+}
+
+#[derive(Serialize)]
+pub struct StorageResponse {
+    pub usage: DiskUsage,
+    pub crop_retention: CropRetention,
+    /// Outcome of the compaction, for POST /storage/compact
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<CompactionReport>,
+}
+
+/// Handler for GET /api/runs/:id/storage
+/// Reports the disk usage of a run and its crop retention policy
+pub async fn get_storage_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<StorageResponse>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    let usage = disk_usage(&run_context.output_dir).map_err(|e| {
+        tracing::error!("Failed to measure {}: {}", run_id, e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(StorageResponse {
+        usage,
+        crop_retention: run_context.crop_retention,
+        compaction: None,
+    }))
+}
+
+/// Handler for POST /api/runs/:id/storage/compact
+/// Applies the posted crop retention policy, which becomes the run's own, or
+/// the run's current one if the body is empty, to the saved crops. A body that
+/// is not a valid policy is rejected with 400.
+pub async fn compact_storage_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+    body: axum::body::Bytes,
+) -> Result<Json<StorageResponse>, axum::http::StatusCode> {
+    let posted: Option<CropRetention> = if body.iter().all(u8::is_ascii_whitespace) {
+        None
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(retention) => Some(retention),
+            Err(e) => {
+                tracing::warn!("Invalid crop retention for {}: {}", run_id, e);
+                return Err(axum::http::StatusCode::BAD_REQUEST);
+            }
+        }
+    };
+
+    let output_root = std::path::Path::new(&args.output_root);
+    let mut run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    // The Finalize worker is still writing crops
+    if let Some(state) = crate::pipeline::orchestrator::get_processing_state(&run_id) {
+        if !state.is_complete.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(axum::http::StatusCode::CONFLICT);
+        }
+    }
+
+    if let Some(retention) = posted {
+        run_context.crop_retention = retention;
+        run_context.save().map_err(|e| {
+            tracing::error!("Failed to save crop retention for {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    let output_dir = run_context.output_dir.clone();
+    let retention = run_context.crop_retention.clone();
+    let sample_rate = run_context.sample_rate;
+    let report = tokio::task::spawn_blocking(move || {
+        crate::pipeline::crop_store::compact(&output_dir, &retention, sample_rate)
+    })
+    .await
+    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|e| {
+        tracing::error!("Failed to compact crops of {}: {}", run_id, e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let usage = disk_usage(&run_context.output_dir).map_err(|e| {
+        tracing::error!("Failed to measure {}: {}", run_id, e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(StorageResponse {
+        usage,
+        crop_retention: run_context.crop_retention,
+        compaction: Some(report),
+    }))
+}
+
+pub async fn save_preprocess_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+    Json(payload): Json<crate::run_context::PreprocessConfig>,
+) -> Result<Json<bool>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    if let Err(e) = payload.validate() {
+        tracing::warn!("Rejected preprocessing config for {}: {}", run_id, e);
+        return Err(axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    match run_context.save_preprocess_config(&payload) {
+        Ok(_) => Ok(Json(true)),
+        Err(e) => {
+            tracing::error!("Failed to save preprocessing config for {}: {}", run_id, e);
+            Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PreprocessPreviewQuery {
+    /// Crop whose chain to preview; defaults to the overview
+    pub crop: Option<String>,
+    /// Number of leading steps to apply; defaults to the whole chain
+    pub steps: Option<usize>,
+}
+
+/// Renders a calibration frame through the first `steps` steps of a crop's saved
+/// preprocessing chain, cropped as the pipeline would crop it.
+pub async fn preview_preprocess_handler(
+    State(args): State<Arc<Args>>,
+    Path((run_id, filename)): Path<(String, String)>,
+    Query(query): Query<PreprocessPreviewQuery>,
+) -> Result<impl axum::response::IntoResponse, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    let frame_path = run_context.get_calibration_frames_dir().join(filename);
+    if !frame_path.exists() {
+        return Err(axum::http::StatusCode::NOT_FOUND);
+    }
+    let frame = opencv::imgcodecs::imread(
+        frame_path.to_str().unwrap(),
+        opencv::imgcodecs::IMREAD_COLOR,
+    )
+    .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let preprocess = run_context.load_preprocess_config().map_err(|e| {
+        tracing::error!("Failed to load preprocessing config for {}: {}", run_id, e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    // Before crops are computed, preview on the whole (rectified) frame
+    let crop = query.crop.as_deref().unwrap_or("overview");
+    let bbox = match run_context.load_crop_configs() {
+        Ok(crops) => {
+            let config = [
+                Some(&crops.overview),
+                crops.left_end_zone.as_ref(),
+                crops.right_end_zone.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .find(|c| c.name == crop)
+            .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+            config.bbox
+        }
+        Err(_) => crate::run_artifacts::BBox {
+            x: 0.0,
+            y: 0.0,
+            w: 1.0,
+            h: 1.0,
+        },
+    };
+    let content =
+        crate::pipeline::crop::crop_frame(&frame, &bbox, run_context.lens.as_ref(), &mut None)
+            .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let steps = preprocess.steps_for(crop);
+    let count = query.steps.unwrap_or(steps.len()).min(steps.len());
+    let (image, _) =
+        crate::pipeline::preprocess::apply_chain(&content, &steps[..count]).map_err(|e| {
+            tracing::error!("Preprocessing preview failed for {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let mut buf = opencv::core::Vector::<u8>::new();
+    opencv::imgcodecs::imencode(".jpg", &image, &mut buf, &opencv::core::Vector::new())
+        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut response = buf.to_vec().into_response();
+    response
+        .headers_mut()
+        .insert("Content-Type", "image/jpeg".parse().unwrap());
+    Ok(response)
+}
+
 pub async fn save_game_details_handler(
     State(args): State<Arc<Args>>,
     Path(run_id): Path<String>,
     Json(payload): Json<serde_json::Value>,
 ) -> Result<Json<bool>, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let details_path = output_root.join(&run_id).join("game_details.json");
+    let run_dir = output_root.join(&run_id);
+    let details_path = run_dir.join("game_details.json");
 
     match std::fs::write(
         details_path,
         serde_json::to_string_pretty(&payload).unwrap(),
-    ) {
+    )
+    .map_err(anyhow::Error::from)
+    .and_then(|_| Manifest::record(&run_dir, "game_details.json", None))
+    {
         Ok(_) => Ok(Json(true)),
         Err(e) => {
             tracing::error!("Failed to save game details for {}: {}", run_id, e);
@@ -275,6 +623,89 @@ pub async fn get_runs(State(args): State<Arc<Args>>) -> Json<Vec<RunInfo>> {
     Json(info_list)
 }
 
+/// Runs of the catalogue matching the filters, e.g. `?unaudited=true`
+pub async fn catalogue_runs_handler(
+    State(args): State<Arc<Args>>,
+    Query(filter): Query<RunFilter>,
+) -> Result<Json<Vec<CatalogueRun>>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    catalogue::sync(output_root)
+        .and_then(|conn| catalogue::query_runs(&conn, &filter))
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to query run catalogue: {}", e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Points of catalogued runs matching the filters, e.g. the confirmed breaks a
+/// team scored this season
+pub async fn catalogue_points_handler(
+    State(args): State<Arc<Args>>,
+    Query(filter): Query<PointFilter>,
+) -> Result<Json<Vec<CataloguePoint>>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    catalogue::sync(output_root)
+        .and_then(|conn| catalogue::query_points(&conn, &filter))
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to query point catalogue: {}", e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+pub async fn get_registry_handler(
+    State(args): State<Arc<Args>>,
+) -> Result<Json<Registry>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    Registry::load(output_root).map(Json).map_err(|e| {
+        tracing::error!("Failed to load registry: {}", e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+/// Replaces the registry; 400 with `{"problems": [...]}` if it is inconsistent
+pub async fn save_registry_handler(
+    State(args): State<Arc<Args>>,
+    Json(registry): Json<Registry>,
+) -> Result<Json<Registry>, axum::response::Response> {
+    let problems = registry.problems();
+    if !problems.is_empty() {
+        let body = serde_json::json!({ "problems": problems });
+        return Err((axum::http::StatusCode::BAD_REQUEST, Json(body)).into_response());
+    }
+    let output_root = std::path::Path::new(&args.output_root);
+    if let Err(e) = registry.save(output_root) {
+        tracing::error!("Failed to save registry: {}", e);
+        return Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    }
+    Ok(Json(registry))
+}
+
+/// Score lines, hold and break rates and pull sides of a registry team across
+/// its catalogued games
+pub async fn team_stats_handler(
+    State(args): State<Arc<Args>>,
+    Path(team_id): Path<String>,
+    Query(filter): Query<StatsFilter>,
+) -> Result<Json<TeamStats>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let registry = Registry::load(output_root).map_err(|e| {
+        tracing::error!("Failed to load registry: {}", e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    if registry.team(&team_id).is_none() {
+        return Err(axum::http::StatusCode::NOT_FOUND);
+    }
+    catalogue::sync(output_root)
+        .and_then(|conn| catalogue::team_stats(&conn, &team_id, &filter))
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to compute stats of team {}: {}", team_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
 pub async fn create_run_handler(
     State(args): State<Arc<Args>>,
     Json(payload): Json<CreateRunRequest>,
@@ -294,23 +725,72 @@ pub async fn update_run_handler(
     State(args): State<Arc<Args>>,
     Path(run_id): Path<String>,
     Json(mut payload): Json<RunContext>,
-) -> Result<Json<RunContext>, axum::http::StatusCode> {
+) -> Result<Json<RunContext>, axum::response::Response> {
     let output_root = std::path::Path::new(&args.output_root);
     let run_dir = output_root.join(&run_id);
 
-    if !run_dir.exists() {
-        return Err(axum::http::StatusCode::NOT_FOUND);
+    let stored = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?
+        .ok_or_else(|| axum::http::StatusCode::NOT_FOUND.into_response())?;
+
+    let registry = Registry::load(output_root).map_err(|e| {
+        tracing::error!("Failed to load registry: {}", e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    // Links the run already has stay valid, even to teams missing from this registry
+    let problems = registry.game_problems(&payload.game, &stored.game);
+    if !problems.is_empty() {
+        let body = serde_json::json!({ "problems": problems });
+        return Err((axum::http::StatusCode::BAD_REQUEST, Json(body)).into_response());
     }
+    // Keep a copy of linked names for bundles and registries without the team
+    (payload.light_team_name, payload.dark_team_name) = payload.team_names(&registry);
 
     payload.output_dir = run_dir;
     if let Err(e) = payload.save() {
         tracing::error!("Failed to update run context for {}: {}", run_id, e);
-        return Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+        return Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
     }
 
     Ok(Json(payload))
 }
 
+/// Handler for POST /api/runs/:id/team-size/accept
+/// Applies the team size estimated during processing to the run's game format.
+pub async fn accept_team_size_estimate_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<RunContext>, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let mut run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    let estimate = TeamSizeEstimateInfo::load(&run_context.output_dir)
+        .map_err(|e| {
+            tracing::error!("Failed to load team-size estimate for {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            tracing::warn!("No team-size estimate recorded for run {}", run_id);
+            axum::http::StatusCode::NOT_FOUND
+        })?;
+    run_context.accept_team_size_estimate(&estimate);
+
+    run_context.save().map_err(|e| {
+        tracing::error!("Failed to save accepted team size for {}: {}", run_id, e);
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(run_context))
+}
+
 #[derive(serde::Deserialize)]
 pub struct UpdateWorkerRequest {
     pub delta: i32,
@@ -357,14 +837,11 @@ pub async fn start_processing_handler(
     let output_root = std::path::Path::new(&args.output_root);
     let video_root = std::path::Path::new(&args.video_root);
 
-    let runs = crate::run_context::list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     // Validate dependencies
@@ -450,14 +927,11 @@ pub async fn backfill_metadata_handler(
     let output_root = std::path::Path::new(&args.output_root);
     let video_root = std::path::Path::new(&args.video_root);
 
-    let runs = list_runs(output_root).map_err(|e| {
-        tracing::error!("Failed to list runs: {}", e);
-        axum::http::StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-
-    let (_, mut run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let mut run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
         .ok_or(axum::http::StatusCode::NOT_FOUND)?;
 
     // Resolve video path (Logic from create_run)
@@ -489,3 +963,153 @@ pub async fn backfill_metadata_handler(
     tracing::error!("Failed to extract valid metadata for run {}", run_id);
     Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR)
 }
+
+// --- Run bundle handlers ---
+
+/// Blocking writer feeding a streamed response body
+struct ChannelWriter(tokio::sync::mpsc::Sender<std::io::Result<axum::body::Bytes>>);
+
+impl std::io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Ok(axum::body::Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Handler for GET /api/runs/:id/export/bundle
+/// Streams the run as a .tar.zst bundle; `?crops=true` and `?video=true` add the
+/// saved crops and the source video
+pub async fn export_bundle_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+    Query(options): Query<ExportOptions>,
+) -> Result<axum::response::Response, axum::http::StatusCode> {
+    let output_root = std::path::Path::new(&args.output_root);
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|e| {
+            tracing::error!("Failed to load run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(axum::http::StatusCode::NOT_FOUND)?;
+
+    // Results are still being written
+    if let Some(state) = crate::pipeline::orchestrator::get_processing_state(&run_id) {
+        if !state.is_complete.load(std::sync::atomic::Ordering::Relaxed) {
+            return Err(axum::http::StatusCode::CONFLICT);
+        }
+    }
+
+    let video_root = std::path::PathBuf::from(&args.video_root);
+    if options.video && !run_context.resolve_video_path(&video_root).exists() {
+        tracing::error!("Video of {} not found, cannot bundle it", run_id);
+        return Err(axum::http::StatusCode::NOT_FOUND);
+    }
+
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    let export_run_id = run_id.clone();
+    tokio::task::spawn_blocking(move || {
+        let writer = std::io::BufWriter::with_capacity(1 << 20, ChannelWriter(tx.clone()));
+        if let Err(e) = run_bundle::export(&run_context, &video_root, options, writer) {
+            tracing::error!("Failed to export {}: {}", export_run_id, e);
+            // Aborts the download instead of ending it as if complete
+            let _ = tx.blocking_send(Err(std::io::Error::other(e.to_string())));
+        }
+    });
+
+    let filename = format!("{}.{}", run_id, run_bundle::BUNDLE_EXTENSION);
+    axum::response::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "application/zstd")
+        .header(
+            axum::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(axum::body::Body::from_stream(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+        ))
+        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+#[derive(serde::Deserialize)]
+pub struct ImportBundleQuery {
+    /// Run id to import as; defaults to the id the run was exported under
+    pub run_id: Option<String>,
+}
+
+/// Handler for POST /api/runs/import
+/// Imports a run bundle posted as the request body. A run id or video name that
+/// is already taken is reported as 409 with the bundle's description, so the
+/// upload can be retried under another `?run_id=`
+pub async fn import_bundle_handler(
+    State(args): State<Arc<Args>>,
+    Query(query): Query<ImportBundleQuery>,
+    body: axum::body::Body,
+) -> Result<axum::response::Response, axum::http::StatusCode> {
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    let output_root = std::path::PathBuf::from(&args.output_root);
+    let video_root = std::path::PathBuf::from(&args.video_root);
+
+    // Hidden, so it is never listed as a run or video
+    let upload = output_root.join(format!(
+        ".upload-{}.{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        run_bundle::BUNDLE_EXTENSION
+    ));
+    let mut file = tokio::fs::File::create(&upload)
+        .await
+        .map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let written = match chunk {
+            Ok(chunk) => file.write_all(&chunk).await.is_ok(),
+            Err(_) => false,
+        };
+        if !written {
+            let _ = tokio::fs::remove_file(&upload).await;
+            return Err(axum::http::StatusCode::BAD_REQUEST);
+        }
+    }
+    if file.flush().await.is_err() {
+        let _ = tokio::fs::remove_file(&upload).await;
+        return Err(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    drop(file);
+
+    let path = upload.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let info = match run_bundle::read_info(&path) {
+            Ok(info) => info,
+            Err(e) => {
+                tracing::error!("Rejected run bundle: {}", e);
+                return Err(axum::http::StatusCode::BAD_REQUEST.into_response());
+            }
+        };
+        let run_id = query.run_id.unwrap_or_else(|| info.run_id.clone());
+        if !run_bundle::valid_run_id(&run_id) {
+            return Err(axum::http::StatusCode::BAD_REQUEST.into_response());
+        }
+        let conflicts = run_bundle::conflicts(&info, &output_root, &video_root, &run_id);
+        if !conflicts.is_empty() {
+            let body = serde_json::json!({ "bundle": info, "conflicts": conflicts });
+            return Err((axum::http::StatusCode::CONFLICT, Json(body)).into_response());
+        }
+        run_bundle::import(&path, &output_root, &video_root, &run_id).map_err(|e| {
+            tracing::error!("Failed to import run {}: {}", run_id, e);
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })
+    })
+    .await;
+    let _ = tokio::fs::remove_file(&upload).await;
+
+    match result.map_err(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR)? {
+        Ok(run_context) => Ok(Json(run_context).into_response()),
+        Err(response) => Ok(response),
+    }
+}
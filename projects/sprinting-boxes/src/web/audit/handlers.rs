@@ -3,7 +3,7 @@
 // This module contains all the async HTTP handlers for:
 // - Getting/saving audit state
 // - Updating settings and cliff fields
-// - Export handlers (YouTube, Insta360, VLC)
+// - Export handlers (YouTube, Insta360, VLC, rendered highlights)
 // - Recalculation endpoint (for frontend)
 
 use axum::{
@@ -20,206 +20,68 @@ use std::fs;
 use std::sync::Arc;
 
 use super::models::{AuditSettings, AuditState, CliffData};
-use super::utils::{format_timestamp, get_sample_rate, parse_duration_to_secs, recalculate_audit};
+use super::utils::{
+    format_timestamp, get_sample_rate, load_audit_state, parse_duration_to_secs, recalculate_audit,
+    save_team_names,
+};
 use crate::cli::Args;
-use crate::pipeline::types::{compact_to_polygon, CompactDetectionFile};
-use crate::run_context::list_runs;
-
-/// Load detections from JSON file, supporting both compact and legacy formats
-fn load_detections(
-    detections_path: &std::path::Path,
-) -> Result<HashMap<usize, HashMap<String, crate::pipeline::types::CropResult>>, StatusCode> {
-    let detections_json =
-        fs::read_to_string(detections_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // Try to load as compact format first
-    if let Ok(compact_file) = serde_json::from_str::<CompactDetectionFile>(&detections_json) {
-        let mut result = HashMap::new();
-        for compact_frame in compact_file.frames {
-            let mut crops = HashMap::new();
-            for (suffix, compact_crop) in compact_frame.crops {
-                let regions = if suffix == "overview" {
-                    compact_crop.regions.clone()
-                } else {
-                    None
-                };
-
-                let regions = if let Some(compact_regions) = regions {
-                    compact_regions
-                        .iter()
-                        .map(|r| crate::pipeline::types::RegionalPolygon {
-                            name: r.name.clone(),
-                            polygon: compact_to_polygon(&r.polygon),
-                            effective_polygon: compact_to_polygon(&r.polygon),
-                        })
-                        .collect()
-                } else {
-                    Vec::new()
-                };
-
-                let detections = compact_crop
-                    .detections
-                    .iter()
-                    .map(|d| crate::pipeline::types::EnrichedDetection {
-                        bbox: crate::pipeline::types::BBox {
-                            x: d.x,
-                            y: d.y,
-                            w: d.w,
-                            h: d.h,
-                        },
-                        confidence: d.confidence,
-                        class_id: 0,
-                        class_name: Some("person".to_string()),
-                        in_end_zone: d.in_end_zone,
-                        in_field: d.in_field,
-                    })
-                    .collect();
-
-                let source_bbox = if suffix == "left" || suffix == "right" {
-                    compact_crop.source_bbox
-                } else {
-                    None
-                };
+use crate::pipeline::crop_cache;
+use crate::pipeline::crop_store::CropStore;
+use crate::pipeline::detections_store::DetectionsReader;
+use crate::registry::Registry;
+use crate::render::RenderOptions;
+use crate::run_context::{find_run, Manifest};
+
+/// Load one frame's detections from the run's detections store
+fn load_frame_detections(
+    output_dir: &std::path::Path,
+    frame_index: usize,
+) -> Result<Option<HashMap<String, crate::pipeline::types::CropResult>>, StatusCode> {
+    let mut reader = match DetectionsReader::open(output_dir).map_err(|e| {
+        tracing::error!("Failed to open detections in {:?}: {}", output_dir, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })? {
+        Some(reader) => reader,
+        None => return Ok(None),
+    };
+    let compact_frame = match reader.get(frame_index).map_err(|e| {
+        tracing::error!("Failed to read detections of frame {}: {}", frame_index, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })? {
+        Some(frame) => frame,
+        None => return Ok(Some(HashMap::new())),
+    };
 
-                crops.insert(
-                    suffix.clone(),
-                    crate::pipeline::types::CropResult {
-                        suffix,
-                        detections,
-                        original_polygon: Vec::new(),
-                        effective_polygon: Vec::new(),
-                        bbox: source_bbox.unwrap_or(crate::pipeline::types::BBox {
-                            x: 0.0,
-                            y: 0.0,
-                            w: 0.0,
-                            h: 0.0,
-                        }),
-                        image: None,
-                        regions,
-                    },
-                );
-            }
-            result.insert(compact_frame.id, crops);
-        }
-        Ok(result)
-    } else if let Ok(legacy_frames) =
-        serde_json::from_str::<Vec<crate::pipeline::types::DetectedFrame>>(&detections_json)
-    {
-        // Fallback to legacy format
-        let mut result = HashMap::new();
-        for frame in legacy_frames {
-            let mut crops = HashMap::new();
-            for crop_result in frame.results {
-                crops.insert(crop_result.suffix.clone(), crop_result);
-            }
-            result.insert(frame.id, crops);
-        }
-        Ok(result)
-    } else {
-        Err(StatusCode::INTERNAL_SERVER_ERROR)
-    }
+    Ok(Some(compact_frame.into_crop_results()))
 }
 
 /// Load audit state from CSV and optional JSON file
 fn load_or_init_audit_state(
     run_context: &crate::run_context::RunContext,
 ) -> Result<AuditState, StatusCode> {
-    let output_dir = &run_context.output_dir;
-
-    // Load points.csv, fallback to pull_points.csv
-    let mut points_path = output_dir.join("points.csv");
-    if !points_path.exists() {
-        points_path = output_dir.join("pull_points.csv");
-        if !points_path.exists() {
-            return Err(StatusCode::NOT_FOUND);
+    let loaded = Registry::load_for_run(run_context)
+        .and_then(|registry| load_audit_state(run_context, &registry));
+    match loaded {
+        Ok(Some(audit_state)) => Ok(audit_state),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to load audit of {}: {}", run_context.run_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
+}
 
-    let points_content =
-        fs::read_to_string(&points_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let sample_rate = get_sample_rate(run_context.sample_rate);
-
-    // Parse CSV into initial cliffs
-    let mut cliffs = Vec::new();
-    for (idx, line) in points_content.lines().enumerate() {
-        if idx == 0 {
-            continue; // Skip header
-        }
-
-        let parts: Vec<&str> = line.split(',').collect();
-        if parts.len() < 4 {
-            continue;
-        }
-
-        let frame_index: usize = parts[0].parse().unwrap_or(0);
-        let left_emptied_first = parts[2].trim() == "1";
-        let right_emptied_first = parts[3].trim() == "1";
-
-        cliffs.push(CliffData {
-            frame_index,
-            timestamp: format_timestamp(frame_index, sample_rate, 0.0),
-            left_emptied_first,
-            right_emptied_first,
-            maybe_false_positive: !left_emptied_first && !right_emptied_first,
-            status: "Unconfirmed".to_string(),
-            halftime_winner: None,
-            manual_side_override: None,
-            manual_color_override: None,
-            left_team_color: None,
-            right_team_color: None,
-            score_light: 0,
-            score_dark: 0,
-            is_break: false,
-        });
-    }
-
-    // Load audit.json if it exists (contains user edits)
-    let audit_path = output_dir.join("audit.json");
-    if audit_path.exists() {
-        let audit_content =
-            fs::read_to_string(&audit_path).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        let audit_state: AuditState = serde_json::from_str(&audit_content).unwrap_or(AuditState {
-            cliffs: cliffs.clone(),
-            settings: AuditSettings {
-                light_team_name: run_context.light_team_name.clone(),
-                dark_team_name: run_context.dark_team_name.clone(),
-                ..AuditSettings::default()
-            },
-        });
-
-        // Merge with loaded cliffs (preserve user edits)
-        let mut cliff_map: HashMap<usize, CliffData> = audit_state
-            .cliffs
-            .into_iter()
-            .map(|c| (c.frame_index, c))
-            .collect();
-
-        for cliff in cliffs {
-            cliff_map.entry(cliff.frame_index).or_insert(cliff);
-        }
-
-        let mut merged_cliffs: Vec<CliffData> = cliff_map.into_values().collect();
-        merged_cliffs.sort_by_key(|c| c.frame_index);
-
-        // Always recalculate timestamps and scores on load
-        let final_cliffs = recalculate_audit(&merged_cliffs, &audit_state.settings, sample_rate);
-
-        Ok(AuditState {
-            cliffs: final_cliffs,
-            settings: audit_state.settings,
-        })
-    } else {
-        let settings = AuditSettings {
-            light_team_name: run_context.light_team_name.clone(),
-            dark_team_name: run_context.dark_team_name.clone(),
-            ..AuditSettings::default()
-        };
-        Ok(AuditState {
-            cliffs: recalculate_audit(&cliffs, &settings, sample_rate),
-            settings,
+/// Moves team names edited in `settings` to the run; see [`save_team_names`]
+fn apply_team_names(
+    run_context: &mut crate::run_context::RunContext,
+    settings: &mut AuditSettings,
+) -> Result<(), StatusCode> {
+    Registry::load_for_run(run_context)
+        .and_then(|registry| save_team_names(run_context, &registry, settings))
+        .map_err(|e| {
+            tracing::error!("Failed to save team names of {}: {}", run_context.run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
         })
-    }
 }
 
 // ============================================================================
@@ -232,10 +94,8 @@ pub async fn get_cliffs_handler(
     Path(run_id): Path<String>,
 ) -> Result<Json<AuditState>, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let audit_state = load_or_init_audit_state(&run_context)?;
@@ -254,14 +114,13 @@ pub async fn get_cliffs_handler(
 pub async fn save_audit_handler(
     State(args): State<Arc<Args>>,
     Path(run_id): Path<String>,
-    Json(audit_state): Json<AuditState>,
+    Json(mut audit_state): Json<AuditState>,
 ) -> Result<StatusCode, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let mut run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    apply_team_names(&mut run_context, &mut audit_state.settings)?;
     let output_dir = &run_context.output_dir;
     let audit_path = output_dir.join("audit.json");
 
@@ -277,6 +136,8 @@ pub async fn save_audit_handler(
     let json = serde_json::to_string_pretty(&enriched_state)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     fs::write(&audit_path, json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Manifest::record(output_dir, "audit.json", None)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::OK)
 }
@@ -285,14 +146,13 @@ pub async fn save_audit_handler(
 pub async fn update_audit_settings_handler(
     State(args): State<Arc<Args>>,
     Path(run_id): Path<String>,
-    Json(settings): Json<AuditSettings>,
+    Json(mut settings): Json<AuditSettings>,
 ) -> Result<StatusCode, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let mut run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    apply_team_names(&mut run_context, &mut settings)?;
     let output_dir = &run_context.output_dir;
     let audit_path = output_dir.join("audit.json");
 
@@ -307,6 +167,8 @@ pub async fn update_audit_settings_handler(
     let json = serde_json::to_string_pretty(&audit_state)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     fs::write(&audit_path, json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Manifest::record(output_dir, "audit.json", None)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::OK)
 }
@@ -317,10 +179,8 @@ pub async fn update_cliff_field_handler(
     Path((run_id, frame_index, field)): Path<(String, usize, String)>,
 ) -> Result<StatusCode, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     let output_dir = &run_context.output_dir;
     let audit_path = output_dir.join("audit.json");
@@ -391,6 +251,8 @@ pub async fn update_cliff_field_handler(
     let json = serde_json::to_string_pretty(&audit_state)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     fs::write(&audit_path, json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Manifest::record(output_dir, "audit.json", None)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::OK)
 }
@@ -438,44 +300,66 @@ pub async fn serve_run_crop_handler(
     Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<impl axum::response::IntoResponse, axum::http::StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let crops_dir = run_context.output_dir.join("crops");
-    let mut file_path = crops_dir.join(&filename);
+    let store = CropStore::open(&run_context.output_dir).map_err(|e| {
+        tracing::error!("Failed to open crops of {}: {}", run_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let read = |name: &str| {
+        store.read(name).map_err(|e| {
+            tracing::error!("Failed to read crop {} of {}: {}", name, run_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    };
 
-    if !file_path.exists() && filename.contains("_overview.jpg") {
+    let mut found = read(&filename)?.map(|crop| (filename.clone(), crop));
+    if found.is_none() && filename.contains("_overview.jpg") {
         let frame_prefix = filename.split("_overview.jpg").next().unwrap_or("");
-        if let Ok(entries) = fs::read_dir(&crops_dir) {
-            for entry in entries.flatten() {
-                let name = entry.file_name().to_string_lossy().into_owned();
-                if name.starts_with(frame_prefix) && name.ends_with(".jpg") {
-                    file_path = crops_dir.join(name);
-                    break;
-                }
-            }
+        let names = store
+            .names()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(name) = names
+            .into_iter()
+            .find(|name| name.starts_with(frame_prefix))
+        {
+            found = read(&name)?.map(|crop| (name, crop));
         }
     }
-
-    if !file_path.exists() {
-        return Err(StatusCode::NOT_FOUND);
+    if found.is_none() {
+        // Crops were not saved (or not retained): cut this one from the video
+        let budget_bytes = args.crop_cache_mb * 1024 * 1024;
+        let video_root = std::path::PathBuf::from(&args.video_root);
+        let context = run_context.clone();
+        let name = filename.clone();
+        let regenerated = tokio::task::spawn_blocking(move || {
+            crop_cache::regenerate(&context, &video_root, &name, budget_bytes)
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to regenerate crop {} of {}: {}",
+                filename,
+                run_id,
+                e
+            );
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        found = regenerated.map(|data| (filename.clone(), (data, 1.0)));
     }
-
-    let final_filename = file_path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or(&filename)
-        .to_string();
+    let (final_filename, (data, scale)) = found.ok_or(StatusCode::NOT_FOUND)?;
 
     let annotate = params.get("annotate").map(|v| v == "true").unwrap_or(false);
 
     if annotate {
-        let img =
-            opencv::imgcodecs::imread(file_path.to_str().unwrap(), opencv::imgcodecs::IMREAD_COLOR)
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let img = opencv::imgcodecs::imdecode(
+            &opencv::core::Vector::<u8>::from_slice(&data),
+            opencv::imgcodecs::IMREAD_COLOR,
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let frame_info = final_filename
             .strip_prefix("frame_")
@@ -490,29 +374,21 @@ pub async fn serve_run_crop_handler(
         let frame_index: usize = parts[0].parse().map_err(|_| StatusCode::BAD_REQUEST)?;
         let suffix = parts[1];
 
-        let mut detections_path = run_context.output_dir.join("detections.json");
-        if !detections_path.exists() {
-            let pull_path = run_context.output_dir.join("pull_detections.json");
-            if pull_path.exists() {
-                detections_path = pull_path;
-            } else {
-                tracing::warn!(
-                    "detections.json and pull_detections.json missing for run {}, cannot annotate",
-                    run_id
-                );
+        let frame_crops = match load_frame_detections(&run_context.output_dir, frame_index)? {
+            Some(crops) => crops,
+            None => {
+                tracing::warn!("No detections for run {}, cannot annotate", run_id);
                 return Err(StatusCode::NOT_FOUND);
             }
-        }
-
-        let all_frames = load_detections(&detections_path)?;
-
-        let frame_crops = all_frames.get(&frame_index).ok_or(StatusCode::NOT_FOUND)?;
+        };
 
         let crop_result = frame_crops.get(suffix).ok_or(StatusCode::NOT_FOUND)?;
+        // Downscaled crops are smaller than the crop the detections refer to
+        let crop_result = crop_result.scaled(scale);
 
         // Note: We pass None for the frame parameter since metrics are now in CSV files
         // The CoM/StdDev visualization will be skipped for compact format
-        let annotated_img = crate::pipeline::finalize::draw_annotations(&img, crop_result, None)
+        let annotated_img = crate::pipeline::finalize::draw_annotations(&img, &crop_result, None)
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let mut buf = opencv::core::Vector::<u8>::new();
@@ -532,17 +408,12 @@ pub async fn serve_run_crop_handler(
         );
         Ok(response)
     } else {
-        match fs::read(file_path) {
-            Ok(data) => {
-                let mut response = axum::response::IntoResponse::into_response(data);
-                response.headers_mut().insert(
-                    axum::http::header::CONTENT_TYPE,
-                    "image/jpeg".parse().unwrap(),
-                );
-                Ok(response)
-            }
-            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-        }
+        let mut response = axum::response::IntoResponse::into_response(data);
+        response.headers_mut().insert(
+            axum::http::header::CONTENT_TYPE,
+            "image/jpeg".parse().unwrap(),
+        );
+        Ok(response)
     }
 }
 
@@ -551,10 +422,8 @@ pub async fn get_features_handler(
     Path(run_id): Path<String>,
 ) -> Result<Json<Vec<FeatureData>>, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     let output_dir = &run_context.output_dir;
 
@@ -627,10 +496,8 @@ pub async fn get_youtube_chapters_handler(
     Path(run_id): Path<String>,
 ) -> Result<String, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let audit_state = load_or_init_audit_state(&run_context)?;
@@ -733,11 +600,8 @@ pub async fn get_studio_clips_handler(
     State(args): State<Arc<Args>>,
     Path(run_id): Path<String>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let runs = list_runs(std::path::Path::new(&args.output_root))
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(std::path::Path::new(&args.output_root), &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let audit_state = load_or_init_audit_state(&run_context)?;
@@ -887,10 +751,8 @@ fn render_scheme(
 /// Helper to generate M3U playlist content
 fn generate_vlc_playlist(args: &Args, run_id: &str) -> Result<String, StatusCode> {
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == run_id)
+    let run_context = find_run(output_root, run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let audit_state = load_or_init_audit_state(&run_context)?;
@@ -958,10 +820,8 @@ pub async fn save_vlc_playlist_handler(
     let playlist = generate_vlc_playlist(&args, &run_id)?;
 
     let output_root = std::path::Path::new(&args.output_root);
-    let runs = list_runs(output_root).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let (_, run_context) = runs
-        .into_iter()
-        .find(|(id, _)| id == &run_id)
+    let run_context = find_run(output_root, &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let output_path = run_context.output_dir.join("playlist.m3u");
@@ -969,3 +829,90 @@ pub async fn save_vlc_playlist_handler(
 
     Ok(StatusCode::OK)
 }
+
+/// Handler for POST /api/runs/:id/export/render
+/// Starts rendering the confirmed points to MP4 in the background
+pub async fn start_render_handler(
+    State(args): State<Arc<Args>>,
+    Path(run_id): Path<String>,
+    body: Option<Json<RenderOptions>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let options = body.map(|Json(options)| options).unwrap_or_default();
+
+    let run_context = find_run(std::path::Path::new(&args.output_root), &run_id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let audit_state = load_or_init_audit_state(&run_context)?;
+    let sample_rate = if run_context.sample_rate > 0.0 {
+        run_context.sample_rate
+    } else {
+        1.0
+    };
+    let cliffs = recalculate_audit(&audit_state.cliffs, &audit_state.settings, sample_rate);
+
+    match crate::render::start_render(
+        &run_context,
+        std::path::Path::new(&args.video_root),
+        &cliffs,
+        &audit_state.settings,
+        sample_rate,
+        options,
+    ) {
+        Ok(state) => Ok(Json(state.to_progress_json())),
+        Err(e) => {
+            tracing::error!("Failed to start render for {}: {:?}", run_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Handler for POST /api/runs/:id/export/render/stop
+pub async fn stop_render_handler(Path(run_id): Path<String>) -> Json<bool> {
+    Json(crate::render::stop_render(&run_id))
+}
+
+/// Handler for GET /api/runs/:id/export/render/progress
+pub async fn render_progress_handler(
+    Path(run_id): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    match crate::render::get_render_state(&run_id) {
+        Some(state) => Ok(Json(state.to_progress_json())),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Handler for GET /api/runs/:id/export/render/progress/sse
+pub async fn render_progress_sse_handler(
+    Path(run_id): Path<String>,
+) -> axum::response::Sse<
+    impl futures::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>,
+> {
+    use axum::response::sse::Event;
+    use std::time::Duration;
+
+    let stream = async_stream::stream! {
+        loop {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+
+            if let Some(state) = crate::render::get_render_state(&run_id) {
+                let json = state.to_progress_json();
+                let is_complete = json["is_complete"].as_bool().unwrap_or(false);
+
+                yield Ok(Event::default().data(json.to_string()));
+
+                if is_complete {
+                    break;
+                }
+            } else {
+                break;
+            }
+        }
+    };
+
+    axum::response::Sse::new(stream).keep_alive(
+        axum::response::sse::KeepAlive::new()
+            .interval(Duration::from_secs(1))
+            .text("keep-alive"),
+    )
+}
@@ -15,7 +15,8 @@ pub mod models;
 pub mod utils;
 pub use handlers::{
     get_cliffs_handler, get_features_handler, get_studio_clips_handler, get_vlc_playlist_handler,
-    get_youtube_chapters_handler, recalculate_audit_handler, save_audit_handler,
-    save_vlc_playlist_handler, serve_run_crop_handler, update_audit_settings_handler,
-    update_cliff_field_handler,
+    get_youtube_chapters_handler, recalculate_audit_handler, render_progress_handler,
+    render_progress_sse_handler, save_audit_handler, save_vlc_playlist_handler,
+    serve_run_crop_handler, start_render_handler, stop_render_handler,
+    update_audit_settings_handler, update_cliff_field_handler,
 };
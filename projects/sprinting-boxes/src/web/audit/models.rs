@@ -32,8 +32,8 @@ pub struct AuditSettings {
 impl Default for AuditSettings {
     fn default() -> Self {
         Self {
-            light_team_name: "Team A".to_string(),
-            dark_team_name: "Team B".to_string(),
+            light_team_name: "Light".to_string(),
+            dark_team_name: "Dark".to_string(),
             initial_score_light: 0,
             initial_score_dark: 0,
             video_start_time: "00:00:00".to_string(),
@@ -74,4 +74,28 @@ impl CliffData {
             is_break: false,
         }
     }
+
+    /// Side the pull came from: the manual override, else the side that emptied first
+    pub fn pull_side(&self) -> Option<&str> {
+        self.manual_side_override
+            .as_deref()
+            .or(if self.left_emptied_first {
+                Some("left")
+            } else if self.right_emptied_first {
+                Some("right")
+            } else {
+                None
+            })
+    }
+
+    /// Color of the pulling team ("light" or "dark"), once team colors are assigned
+    pub fn pulling_color(&self) -> Option<&str> {
+        self.pull_side().and_then(|side| {
+            if side == "left" {
+                self.left_team_color.as_deref()
+            } else {
+                self.right_team_color.as_deref()
+            }
+        })
+    }
 }
@@ -4,8 +4,13 @@
 // - Timestamp formatting from frame indices
 // - Duration parsing
 // - Score recalculation with team assignment and break detection
+// - Loading the audit state of a run
 
-use super::models::{AuditSettings, CliffData};
+use super::models::{AuditSettings, AuditState, CliffData};
+use crate::registry::Registry;
+use crate::run_context::RunContext;
+use std::collections::HashMap;
+use std::fs;
 
 /// Format a timestamp from a frame index, sample rate, and offset
 pub fn format_timestamp(frame_index: usize, sample_rate: f64, offset_secs: f64) -> String {
@@ -37,6 +42,139 @@ pub fn get_sample_rate(sample_rate: f64) -> f64 {
     }
 }
 
+/// Load the audit state of a processed run: the cliffs in points.csv merged with
+/// the user edits in audit.json (if it exists), recalculated. Teams the run links
+/// to the registry are named as there. `None` if the run has no points.csv yet.
+pub fn load_audit_state(
+    run_context: &RunContext,
+    registry: &Registry,
+) -> anyhow::Result<Option<AuditState>> {
+    let output_dir = &run_context.output_dir;
+
+    let points_path = output_dir.join("points.csv");
+    if !points_path.exists() {
+        return Ok(None);
+    }
+
+    let points_content = fs::read_to_string(&points_path)?;
+
+    let sample_rate = get_sample_rate(run_context.sample_rate);
+    let (light_team_name, dark_team_name) = run_context.team_names(registry);
+
+    // Parse CSV into initial cliffs
+    let mut cliffs = Vec::new();
+    for (idx, line) in points_content.lines().enumerate() {
+        if idx == 0 {
+            continue; // Skip header
+        }
+
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < 4 {
+            continue;
+        }
+
+        let frame_index: usize = parts[0].parse().unwrap_or(0);
+        let left_emptied_first = parts[2].trim() == "1";
+        let right_emptied_first = parts[3].trim() == "1";
+
+        cliffs.push(CliffData {
+            frame_index,
+            timestamp: format_timestamp(frame_index, sample_rate, 0.0),
+            left_emptied_first,
+            right_emptied_first,
+            maybe_false_positive: !left_emptied_first && !right_emptied_first,
+            status: "Unconfirmed".to_string(),
+            halftime_winner: None,
+            manual_side_override: None,
+            manual_color_override: None,
+            left_team_color: None,
+            right_team_color: None,
+            score_light: 0,
+            score_dark: 0,
+            is_break: false,
+        });
+    }
+
+    // Load audit.json if it exists (contains user edits)
+    let audit_path = output_dir.join("audit.json");
+    if audit_path.exists() {
+        let audit_content = fs::read_to_string(&audit_path)?;
+        let mut audit_state: AuditState =
+            serde_json::from_str(&audit_content).unwrap_or(AuditState {
+                cliffs: cliffs.clone(),
+                settings: AuditSettings {
+                    light_team_name: light_team_name.clone(),
+                    dark_team_name: dark_team_name.clone(),
+                    ..AuditSettings::default()
+                },
+            });
+        // Team names belong to the run and the registry; the copy in audit.json is ignored
+        audit_state.settings.light_team_name = light_team_name;
+        audit_state.settings.dark_team_name = dark_team_name;
+
+        // Merge with loaded cliffs (preserve user edits)
+        let mut cliff_map: HashMap<usize, CliffData> = audit_state
+            .cliffs
+            .into_iter()
+            .map(|c| (c.frame_index, c))
+            .collect();
+
+        for cliff in cliffs {
+            cliff_map.entry(cliff.frame_index).or_insert(cliff);
+        }
+
+        let mut merged_cliffs: Vec<CliffData> = cliff_map.into_values().collect();
+        merged_cliffs.sort_by_key(|c| c.frame_index);
+
+        // Always recalculate timestamps and scores on load
+        let final_cliffs = recalculate_audit(&merged_cliffs, &audit_state.settings, sample_rate);
+
+        Ok(Some(AuditState {
+            cliffs: final_cliffs,
+            settings: audit_state.settings,
+        }))
+    } else {
+        let settings = AuditSettings {
+            light_team_name,
+            dark_team_name,
+            ..AuditSettings::default()
+        };
+        Ok(Some(AuditState {
+            cliffs: recalculate_audit(&cliffs, &settings, sample_rate),
+            settings,
+        }))
+    }
+}
+
+/// Saves team names edited in the audit settings to the run, where the audit
+/// reads them from, then sets `settings` to the names the audit shows. A side
+/// linked to a registry team keeps that team's name.
+pub fn save_team_names(
+    run_context: &mut RunContext,
+    registry: &Registry,
+    settings: &mut AuditSettings,
+) -> anyhow::Result<()> {
+    let linked = |id: &Option<String>| id.as_deref().and_then(|id| registry.team(id)).is_some();
+    let mut changed = false;
+    if !linked(&run_context.game.light_team_id)
+        && run_context.light_team_name != settings.light_team_name
+    {
+        run_context.light_team_name = settings.light_team_name.clone();
+        changed = true;
+    }
+    if !linked(&run_context.game.dark_team_id)
+        && run_context.dark_team_name != settings.dark_team_name
+    {
+        run_context.dark_team_name = settings.dark_team_name.clone();
+        changed = true;
+    }
+    if changed {
+        run_context.save()?;
+    }
+    (settings.light_team_name, settings.dark_team_name) = run_context.team_names(registry);
+    Ok(())
+}
+
 /// Recalculate audit state: scores, team colors, and breaks
 ///
 /// This is the core business logic for the audit system. It:
@@ -133,18 +271,7 @@ pub fn recalculate_audit(
 
         // Score update (if not first point)
         if valid_point_count > 0 {
-            let pull_side = cliff
-                .manual_side_override
-                .as_deref()
-                .or(if cliff.left_emptied_first {
-                    Some("left")
-                } else if cliff.right_emptied_first {
-                    Some("right")
-                } else {
-                    None
-                });
-
-            if let Some(side) = pull_side {
+            if let Some(side) = cliff.pull_side() {
                 let pulling_team = if side == "left" { &left } else { &right };
                 if pulling_team == "light" {
                     score_light += 1;
@@ -179,43 +306,7 @@ pub fn recalculate_audit(
         let cur = &valid_points[j];
         let next = &valid_points[j + 1];
 
-        let cur_pull_side = cur
-            .manual_side_override
-            .as_deref()
-            .or(if cur.left_emptied_first {
-                Some("left")
-            } else if cur.right_emptied_first {
-                Some("right")
-            } else {
-                None
-            });
-        let cur_pull_team = cur_pull_side.and_then(|side| {
-            if side == "left" {
-                cur.left_team_color.as_ref()
-            } else {
-                cur.right_team_color.as_ref()
-            }
-        });
-
-        let next_pull_side = next
-            .manual_side_override
-            .as_deref()
-            .or(if next.left_emptied_first {
-                Some("left")
-            } else if next.right_emptied_first {
-                Some("right")
-            } else {
-                None
-            });
-        let next_pull_team = next_pull_side.and_then(|side| {
-            if side == "left" {
-                next.left_team_color.as_ref()
-            } else {
-                next.right_team_color.as_ref()
-            }
-        });
-
-        if let (Some(cur_team), Some(next_team)) = (cur_pull_team, next_pull_team) {
+        if let (Some(cur_team), Some(next_team)) = (cur.pulling_color(), next.pulling_color()) {
             if cur_team == next_team {
                 break_indices.push(cur.frame_index);
             }
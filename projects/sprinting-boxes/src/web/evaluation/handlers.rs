@@ -10,7 +10,7 @@ use std::fs;
 use std::sync::Arc;
 use std::time::Instant;
 
-use ultimate_event_detection::{CliffDetector, CliffDetectorConfig, GpuCliffDetector};
+use ultimate_event_detection::{CliffDetector, CliffDetectorConfig, GameFormat, GpuCliffDetector};
 use crate::run_context::list_runs;
 use crate::web::server::AppState;
 
@@ -112,9 +112,9 @@ fn recalculate_score_with_field_onset(
     right_count: f32,
     field_count: f32,
     field_onset: f32,
+    team_size: u32,
 ) -> f32 {
-    const TEAM_SIZE: f32 = 7.0;
-    let threshold = 2.0 / TEAM_SIZE;
+    let threshold = 2.0 / team_size as f32;
 
     let min_ez = left_count.min(right_count);
     let balance = if min_ez >= threshold {
@@ -351,6 +351,7 @@ fn load_ground_truth(
 fn evaluate_config_gpu(
     raw_features: &[FrameFeatures],
     ground_truth: &HashSet<usize>,
+    game_format: GameFormat,
     config: &DetectorConfigParams,
     gpu_detector: &Option<Arc<GpuCliffDetector>>,
 ) -> EvaluationMetrics {
@@ -365,6 +366,7 @@ fn evaluate_config_gpu(
                 f.right_count,
                 f.field_count,
                 config.field_onset,
+                game_format.team_size(),
             )
         })
         .collect();
@@ -564,7 +566,7 @@ pub async fn global_sweep_handler(
 
     // Load data from all runs that have features.csv and audit.json
     let load_start = Instant::now();
-    let mut run_data: Vec<(Vec<FrameFeatures>, HashSet<usize>, HashSet<usize>)> = Vec::new();
+    let mut run_data: Vec<(Vec<FrameFeatures>, HashSet<usize>, HashSet<usize>, GameFormat)> = Vec::new();
     let mut csv_load_time = std::time::Duration::ZERO;
 
     for (run_id, run_context) in &runs {
@@ -588,7 +590,7 @@ pub async fn global_sweep_handler(
             csv_load_time += csv_timer.elapsed();
             if let Ok((ground_truth, false_positives)) = load_ground_truth(&audit_path, &points_path) {
                 if !ground_truth.is_empty() || !false_positives.is_empty() {
-                    run_data.push((raw_features, ground_truth, false_positives));
+                    run_data.push((raw_features, ground_truth, false_positives, run_context.game_format));
                 }
             }
         } else {
@@ -653,8 +655,8 @@ pub async fn global_sweep_handler(
     let mut baseline_fn_causes: HashMap<String, usize> = HashMap::new();
     let mut baseline_fp_causes: HashMap<String, usize> = HashMap::new();
 
-    for (raw_features, ground_truth, _false_positives) in &run_data {
-        let metrics = evaluate_config_gpu(raw_features, ground_truth, &default_params, &state.gpu_detector);
+    for (raw_features, ground_truth, _false_positives, game_format) in &run_data {
+        let metrics = evaluate_config_gpu(raw_features, ground_truth, *game_format, &default_params, &state.gpu_detector);
         baseline_total_tp += metrics.tp;
         baseline_total_fp += metrics.fp;
         baseline_total_fn += metrics.fn_count;
@@ -735,8 +737,8 @@ pub async fn global_sweep_handler(
             let mut fn_causes: HashMap<String, usize> = HashMap::new();
             let mut fp_causes: HashMap<String, usize> = HashMap::new();
 
-            for (raw_features, ground_truth, _false_positives) in &run_data {
-                let metrics = evaluate_config_gpu(raw_features, ground_truth, &params, &state.gpu_detector);
+            for (raw_features, ground_truth, _false_positives, game_format) in &run_data {
+                let metrics = evaluate_config_gpu(raw_features, ground_truth, *game_format, &params, &state.gpu_detector);
                 total_tp += metrics.tp;
                 total_fp += metrics.fp;
                 total_fn += metrics.fn_count;
@@ -1,25 +1,31 @@
 use crate::cli::Args;
-use crate::config::DetectorConfig;
+use crate::config::{DetectorConfig, DETECTOR_CONFIG_FILE};
+use crate::run_artifacts::migrate_runs;
 use ultimate_event_detection::GpuCliffDetector;
 use crate::web::api::{
-    backfill_metadata_handler, compute_crops_handler, create_run_handler,
-    extract_calibration_frames_handler, get_calibration_frames_handler, get_crops_handler,
-    get_run_handler, get_runs, get_videos, processing_progress_handler,
-    processing_progress_sse_handler, save_boundaries_handler, save_game_details_handler,
+    accept_team_size_estimate_handler, backfill_metadata_handler, catalogue_points_handler,
+    catalogue_runs_handler, compact_storage_handler, compute_crops_handler, create_run_handler,
+    diff_processing_config_handler, export_bundle_handler, extract_calibration_frames_handler,
+    get_boundaries_handler, get_calibration_frames_handler, get_crops_handler,
+    get_preprocess_handler, get_processing_config_handler, get_registry_handler, get_run_handler,
+    get_runs, get_storage_handler, get_videos, import_bundle_handler, preview_preprocess_handler,
+    processing_progress_handler, processing_progress_sse_handler, save_boundaries_handler,
+    save_game_details_handler, save_preprocess_handler, save_registry_handler,
     serve_calibration_frame_handler, start_processing_handler, stop_processing_handler,
-    update_run_handler, update_worker_count_handler,
+    team_stats_handler, update_run_handler, update_worker_count_handler,
 };
 use crate::web::assets::{index_handler, static_handler};
 use crate::web::audit::{
     get_cliffs_handler, get_features_handler, get_studio_clips_handler, get_vlc_playlist_handler,
-    get_youtube_chapters_handler, recalculate_audit_handler, save_audit_handler,
-    save_vlc_playlist_handler, serve_run_crop_handler, update_audit_settings_handler,
-    update_cliff_field_handler,
+    get_youtube_chapters_handler, recalculate_audit_handler, render_progress_handler,
+    render_progress_sse_handler, save_audit_handler, save_vlc_playlist_handler,
+    serve_run_crop_handler, start_render_handler, stop_render_handler,
+    update_audit_settings_handler, update_cliff_field_handler,
 };
 use crate::web::evaluation::{get_detector_config_handler, global_sweep_handler};
 use anyhow::Result;
 use axum::{
-    extract::FromRef,
+    extract::{DefaultBodyLimit, FromRef},
     routing::{get, post, put},
     Router,
 };
@@ -46,12 +52,12 @@ pub async fn run_server(args: Args) -> Result<()> {
     let shared_args = Arc::new(args);
 
     // Load detector configuration from YAML file
-    let detector_config = Arc::new(DetectorConfig::from_file("detector.config.yaml"));
+    let detector_config = Arc::new(DetectorConfig::from_file(DETECTOR_CONFIG_FILE));
 
-    // Initialize GPU detector (optional, falls back to CPU if unavailable)
+    // Initialize batch cliff detector (Metal/wgpu when available, otherwise parallel CPU)
     let gpu_detector = match GpuCliffDetector::new() {
         Ok(detector) => {
-            info!("GPU-accelerated cliff detector initialized");
+            info!("Batch cliff detector initialized ({})", detector.backend_name());
             Some(Arc::new(detector))
         }
         Err(e) => {
@@ -60,6 +66,14 @@ pub async fn run_server(args: Args) -> Result<()> {
         }
     };
 
+    // Bring every run up to the current layout once, so loading a run never writes
+    let output_root = std::path::PathBuf::from(&shared_args.output_root);
+    match tokio::task::spawn_blocking(move || migrate_runs(&output_root)).await? {
+        Ok(0) => {}
+        Ok(applied) => info!("Applied {} run migrations", applied),
+        Err(e) => warn!("Failed to migrate runs: {}", e),
+    }
+
     let app_state = AppState {
         args: shared_args,
         gpu_detector,
@@ -90,12 +104,25 @@ pub async fn run_server(args: Args) -> Result<()> {
         .route("/api/videos", get(get_videos))
         .route("/api/runs", get(get_runs))
         .route("/api/runs", post(create_run_handler))
+        .route("/api/catalogue/runs", get(catalogue_runs_handler))
+        .route("/api/catalogue/points", get(catalogue_points_handler))
+        .route("/api/registry", get(get_registry_handler))
+        .route("/api/registry", put(save_registry_handler))
+        .route("/api/registry/teams/:id/stats", get(team_stats_handler))
+        .route(
+            "/api/runs/import",
+            post(import_bundle_handler).layer(DefaultBodyLimit::disable()),
+        )
         .route("/api/runs/:id", get(get_run_handler))
         .route("/api/runs/:id", put(update_run_handler))
         .route(
             "/api/runs/:id/metadata/backfill",
             post(backfill_metadata_handler),
         )
+        .route(
+            "/api/runs/:id/team-size/accept",
+            post(accept_team_size_estimate_handler),
+        )
         .route(
             "/api/runs/:id/calibration/extract",
             post(extract_calibration_frames_handler),
@@ -110,18 +137,34 @@ pub async fn run_server(args: Args) -> Result<()> {
         )
         .route(
             "/api/runs/:id/calibration/boundaries",
-            post(save_boundaries_handler),
+            get(get_boundaries_handler).post(save_boundaries_handler),
         )
         .route(
             "/api/runs/:id/calibration/game-details",
             post(save_game_details_handler),
         )
+        .route(
+            "/api/runs/:id/calibration/preview/:path",
+            get(preview_preprocess_handler),
+        )
+        .route(
+            "/api/runs/:id/preprocess",
+            get(get_preprocess_handler).put(save_preprocess_handler),
+        )
         .route("/api/runs/:id/crops", get(get_crops_handler))
         .route("/api/runs/:id/crops/compute", post(compute_crops_handler))
         .route(
             "/api/runs/:id/process/start",
             post(start_processing_handler),
         )
+        .route(
+            "/api/runs/:id/process/config",
+            get(get_processing_config_handler),
+        )
+        .route(
+            "/api/runs/:id/process/config/diff/:other",
+            get(diff_processing_config_handler),
+        )
         .route("/api/runs/:id/process/stop", post(stop_processing_handler))
         .route(
             "/api/runs/:id/process/progress",
@@ -170,7 +213,26 @@ pub async fn run_server(args: Args) -> Result<()> {
             "/api/runs/:id/export/vlc-playlist",
             get(get_vlc_playlist_handler).post(save_vlc_playlist_handler),
         )
+        .route("/api/runs/:id/export/render", post(start_render_handler))
+        .route(
+            "/api/runs/:id/export/render/stop",
+            post(stop_render_handler),
+        )
+        .route(
+            "/api/runs/:id/export/render/progress",
+            get(render_progress_handler),
+        )
+        .route(
+            "/api/runs/:id/export/render/progress/sse",
+            get(render_progress_sse_handler),
+        )
+        .route("/api/runs/:id/export/bundle", get(export_bundle_handler))
         .route("/api/runs/:id/crops/:filename", get(serve_run_crop_handler))
+        .route("/api/runs/:id/storage", get(get_storage_handler))
+        .route(
+            "/api/runs/:id/storage/compact",
+            post(compact_storage_handler),
+        )
         .route("/", get(index_handler))
         .route("/*path", get(static_handler))
         .with_state(app_state);
@@ -0,0 +1,155 @@
+//! Region lookup and NMS on frame-sized inputs: a 1920x1080 overview crop with
+//! two end-zone quads and a buffered field hull, and detection sets from a
+//! sparse frame up to a tiled frame full of line-up duplicates.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use geo::Contains;
+use ultimate_event_detection::{Aabb, BoxGrid, PreparedPolygon};
+
+fn lcg(seed: u64) -> impl FnMut() -> f32 {
+    let mut state = seed;
+    move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as f32) / (1u64 << 31) as f32
+    }
+}
+
+/// Left and right end zones, then the field as a 24-vertex hull like the buffered polygons.
+fn regions() -> Vec<Vec<(f32, f32)>> {
+    let field: Vec<(f32, f32)> = (0..24)
+        .map(|i| {
+            let a = i as f32 / 24.0 * std::f32::consts::TAU;
+            (960.0 + 620.0 * a.cos(), 560.0 + 380.0 * a.sin())
+        })
+        .collect();
+    vec![
+        vec![(60.0, 260.0), (360.0, 220.0), (420.0, 900.0), (20.0, 860.0)],
+        vec![(1560.0, 220.0), (1860.0, 260.0), (1900.0, 860.0), (1500.0, 900.0)],
+        field,
+    ]
+}
+
+/// Ground points (bottom centre) of `n` detections.
+fn ground_points(n: usize) -> Vec<(f32, f32)> {
+    let mut next = lcg(5);
+    (0..n).map(|_| (1920.0 * next(), 1080.0 * next())).collect()
+}
+
+/// Players in tight groups (line-ups, huddles), each seen two or three times by overlapping tiles.
+fn detections(n: usize) -> Vec<(Aabb, f32)> {
+    let mut next = lcg(9);
+    let mut boxes = Vec::with_capacity(n);
+    while boxes.len() < n {
+        let (gx, gy) = (1800.0 * next(), 950.0 * next());
+        for p in 0..7 {
+            let (x, y) = (gx + 22.0 * p as f32, gy + 8.0 * next());
+            for _ in 0..2 + (next() * 2.0) as usize {
+                let jitter = 4.0 * (next() - 0.5);
+                boxes.push((Aabb::from_xywh(x + jitter, y + jitter, 36.0, 90.0), 0.5 + 0.5 * next()));
+            }
+        }
+    }
+    boxes.truncate(n);
+    boxes.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    boxes
+}
+
+fn iou(a: &Aabb, b: &Aabb) -> f32 {
+    let w = a.max_x.min(b.max_x) - a.min_x.max(b.min_x);
+    let h = a.max_y.min(b.max_y) - a.min_y.max(b.min_y);
+    if w <= 0.0 || h <= 0.0 {
+        return 0.0;
+    }
+    let inter = w * h;
+    inter / (a.width() * a.height() + b.width() * b.height() - inter)
+}
+
+/// Greedy hard NMS over confidence-sorted boxes, optionally visiting only grid neighbours.
+fn greedy_nms(boxes: &[(Aabb, f32)], threshold: f32, use_grid: bool) -> usize {
+    let grid = use_grid.then(|| BoxGrid::new(&boxes.iter().map(|b| b.0).collect::<Vec<_>>()));
+    let mut suppressed = vec![false; boxes.len()];
+    let mut candidates = Vec::new();
+    let mut kept = 0;
+    for i in 0..boxes.len() {
+        if suppressed[i] {
+            continue;
+        }
+        kept += 1;
+        match &grid {
+            Some(grid) => grid.neighbours(i, &mut candidates),
+            None => {
+                candidates.clear();
+                candidates.extend(i + 1..boxes.len());
+            }
+        }
+        for &j in &candidates {
+            if j > i && !suppressed[j] && iou(&boxes[i].0, &boxes[j].0) > threshold {
+                suppressed[j] = true;
+            }
+        }
+    }
+    kept
+}
+
+fn point_in_region(c: &mut Criterion) {
+    let regions = regions();
+    let prepared: Vec<PreparedPolygon> = regions.iter().map(|r| PreparedPolygon::new(r)).collect();
+    let mut group = c.benchmark_group("point_in_region");
+
+    for n in [30, 120] {
+        let points = ground_points(n);
+
+        // What `is_point_in_polygon_robust` did: a new geo polygon per test, two passes per frame
+        group.bench_with_input(BenchmarkId::new("geo_rebuilt_per_call", n), &points, |b, points| {
+            b.iter(|| {
+                let mut hits = 0;
+                for _pass in 0..2 {
+                    for &(x, y) in points {
+                        for region in &regions {
+                            let coords: Vec<(f64, f64)> =
+                                region.iter().map(|&(x, y)| (x as f64, y as f64)).collect();
+                            let poly = geo::Polygon::new(geo::LineString::from(coords), vec![]);
+                            if poly.contains(&geo::Point::new(x as f64, y as f64)) {
+                                hits += 1;
+                                break;
+                            }
+                        }
+                    }
+                }
+                black_box(hits)
+            })
+        });
+
+        // Prepared once per run, one pass per frame
+        group.bench_with_input(BenchmarkId::new("prepared", n), &points, |b, points| {
+            b.iter(|| {
+                let mut hits = 0;
+                for &(x, y) in points {
+                    if prepared.iter().any(|p| p.contains(x, y)) {
+                        hits += 1;
+                    }
+                }
+                black_box(hits)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn nms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nms");
+    for n in [30, 100, 300, 1000] {
+        let boxes = detections(n);
+        assert_eq!(greedy_nms(&boxes, 0.5, false), greedy_nms(&boxes, 0.5, true));
+        group.bench_with_input(BenchmarkId::new("all_pairs", n), &boxes, |b, boxes| {
+            b.iter(|| black_box(greedy_nms(boxes, 0.5, false)))
+        });
+        group.bench_with_input(BenchmarkId::new("grid", n), &boxes, |b, boxes| {
+            b.iter(|| black_box(greedy_nms(boxes, 0.5, true)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, point_in_region, nms);
+criterion_main!(benches);
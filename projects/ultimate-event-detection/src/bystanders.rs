@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+
+/// A detected person in one frame, in overview coordinates normalized to `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PersonObservation {
+    /// Ground point (bottom centre of the box).
+    pub x: f32,
+    pub y: f32,
+    /// Box height as a fraction of the overview height.
+    pub height: f32,
+}
+
+/// Why a person was judged not to be a player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exclusion {
+    /// Standing in the same spot for most of the game so far (coach, photographer).
+    Stationary,
+    /// Box height far from what players show at that image row (camera-side
+    /// spectators, people well behind the back line).
+    Perspective,
+}
+
+/// Configuration for bystander filtering.
+#[derive(Clone, Debug)]
+pub struct BystanderConfig {
+    /// Grid cell edge for the occupancy map, as a fraction of the overview.
+    pub cell_size: f32,
+    /// Frames to observe before anyone can be judged stationary.
+    pub min_frames: usize,
+    /// Fraction of frames a cell must be occupied in for its occupant to be stationary.
+    /// Pre-point line-ups hold players still for a minute at most, far below this.
+    pub stationary_fraction: f32,
+    /// Horizontal bands the perspective model is split into.
+    pub height_bands: usize,
+    /// Heights a band needs before it judges anyone.
+    pub min_band_samples: usize,
+    /// Recent heights kept per band.
+    pub band_window: usize,
+    /// Largest accepted ratio between a box height and its band's median, either way.
+    pub max_height_ratio: f32,
+}
+
+impl Default for BystanderConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.02,
+            min_frames: 300,
+            stationary_fraction: 0.8,
+            height_bands: 16,
+            min_band_samples: 30,
+            band_window: 500,
+            max_height_ratio: 2.0,
+        }
+    }
+}
+
+/// Learns where non-players stand and how tall players appear, frame by frame.
+///
+/// Each frame is judged against what was learned from the earlier ones, then
+/// added to the model, so judgements only start once enough frames are seen.
+pub struct BystanderFilter {
+    config: BystanderConfig,
+    cols: usize,
+    rows: usize,
+    /// Frames in which each cell held at least one person
+    occupied: Vec<u32>,
+    frames: usize,
+    bands: Vec<VecDeque<f32>>,
+    /// Cells touched in the current frame, reused across frames
+    touched: Vec<usize>,
+}
+
+impl BystanderFilter {
+    pub fn new(config: BystanderConfig) -> Self {
+        let cells = (1.0 / config.cell_size.max(1e-3)).ceil() as usize;
+        let bands = config.height_bands.max(1);
+        Self {
+            cols: cells,
+            rows: cells,
+            occupied: vec![0; cells * cells],
+            frames: 0,
+            bands: vec![VecDeque::new(); bands],
+            touched: Vec::new(),
+            config,
+        }
+    }
+
+    /// Frames observed so far.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Judges one frame's people, then learns from them. Returns one verdict per
+    /// person, `None` for likely players.
+    pub fn observe(&mut self, people: &[PersonObservation]) -> Vec<Option<Exclusion>> {
+        let verdicts: Vec<Option<Exclusion>> = people.iter().map(|p| self.judge(p)).collect();
+
+        self.touched.clear();
+        for p in people {
+            if let Some(cell) = self.cell(p.x, p.y) {
+                self.touched.push(cell);
+            }
+        }
+        self.touched.sort_unstable();
+        self.touched.dedup();
+        for &cell in &self.touched {
+            self.occupied[cell] += 1;
+        }
+        self.frames += 1;
+
+        // Heights of people already judged non-players would skew the model
+        for (p, verdict) in people.iter().zip(&verdicts) {
+            if verdict.is_some() || !(p.height.is_finite() && p.height > 0.0) {
+                continue;
+            }
+            if let Some(band) = self.band(p.y) {
+                let window = self.config.band_window.max(1);
+                let recent = &mut self.bands[band];
+                if recent.len() == window {
+                    recent.pop_front();
+                }
+                recent.push_back(p.height);
+            }
+        }
+
+        verdicts
+    }
+
+    fn judge(&self, p: &PersonObservation) -> Option<Exclusion> {
+        if self.frames >= self.config.min_frames {
+            if let Some(cell) = self.cell(p.x, p.y) {
+                let fraction = self.occupied[cell] as f32 / self.frames as f32;
+                if fraction >= self.config.stationary_fraction {
+                    return Some(Exclusion::Stationary);
+                }
+            }
+        }
+
+        let band = &self.bands[self.band(p.y)?];
+        if band.len() < self.config.min_band_samples || !p.height.is_finite() || p.height <= 0.0 {
+            return None;
+        }
+        let mut sorted: Vec<f32> = band.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let expected = sorted[sorted.len() / 2];
+        let ratio = p.height / expected;
+        let max = self.config.max_height_ratio;
+        if ratio > max || ratio < 1.0 / max {
+            Some(Exclusion::Perspective)
+        } else {
+            None
+        }
+    }
+
+    fn cell(&self, x: f32, y: f32) -> Option<usize> {
+        if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+            return None;
+        }
+        let col = ((x * self.cols as f32) as usize).min(self.cols - 1);
+        let row = ((y * self.rows as f32) as usize).min(self.rows - 1);
+        Some(row * self.cols + col)
+    }
+
+    fn band(&self, y: f32) -> Option<usize> {
+        if !(0.0..=1.0).contains(&y) {
+            return None;
+        }
+        Some(((y * self.bands.len() as f32) as usize).min(self.bands.len() - 1))
+    }
+}
+
+impl Default for BystanderFilter {
+    fn default() -> Self {
+        Self::new(BystanderConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random values in [0, 1).
+    fn lcg(seed: u64) -> impl FnMut() -> f32 {
+        let mut state = seed;
+        move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32) / (1u64 << 31) as f32
+        }
+    }
+
+    /// Players wandering the field, taller lower in the image.
+    fn players(next: &mut impl FnMut() -> f32, count: usize) -> Vec<PersonObservation> {
+        (0..count)
+            .map(|_| {
+                let y = 0.2 + 0.7 * next();
+                PersonObservation { x: 0.05 + 0.9 * next(), y, height: 0.02 + 0.08 * y }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn photographer_behind_back_line_becomes_stationary() {
+        let config = BystanderConfig { min_frames: 50, ..Default::default() };
+        let mut filter = BystanderFilter::new(config);
+        let mut next = lcg(5);
+        let photographer = PersonObservation { x: 0.031, y: 0.55, height: 0.064 };
+
+        for frame in 0..200 {
+            let mut people = players(&mut next, 10);
+            people.push(photographer);
+            let verdicts = filter.observe(&people);
+
+            let expected = if frame < 50 { None } else { Some(Exclusion::Stationary) };
+            assert_eq!(verdicts[10], expected, "frame {}", frame);
+            // Wandering players are never stationary
+            assert!(verdicts[..10].iter().all(|v| *v != Some(Exclusion::Stationary)));
+        }
+    }
+
+    #[test]
+    fn out_of_scale_boxes_are_perspective_outliers() {
+        let mut filter = BystanderFilter::default();
+        let mut next = lcg(9);
+        for _ in 0..100 {
+            filter.observe(&players(&mut next, 10));
+        }
+
+        // A camera-side spectator three times a player's height at that row, a
+        // distant person at a third, and a player at the expected height
+        let y = 0.5;
+        let expected = 0.02 + 0.08 * y;
+        let verdicts = filter.observe(&[
+            PersonObservation { x: 0.4, y, height: 3.0 * expected },
+            PersonObservation { x: 0.5, y, height: expected / 3.0 },
+            PersonObservation { x: 0.6, y, height: 1.1 * expected },
+        ]);
+        assert_eq!(verdicts, vec![Some(Exclusion::Perspective), Some(Exclusion::Perspective), None]);
+    }
+
+    #[test]
+    fn judges_nobody_before_learning() {
+        let mut filter = BystanderFilter::default();
+        let verdicts = filter.observe(&[
+            PersonObservation { x: 0.5, y: 0.5, height: 0.9 },
+            PersonObservation { x: -1.0, y: 2.0, height: f32::NAN },
+        ]);
+        assert_eq!(verdicts, vec![None, None]);
+        assert_eq!(filter.frames(), 1);
+    }
+}
@@ -28,7 +28,7 @@ impl Default for CliffDetectorConfig {
     }
 }
 
-fn median(values: &[f32]) -> f32 {
+pub(crate) fn median(values: &[f32]) -> f32 {
     if values.is_empty() {
         return 0.0;
     }
@@ -174,7 +174,7 @@ impl CliffDetector {
             if cliff {
                 let gap_ok = self
                     .last_cliff_index
-                    .map_or(true, |last| frame_idx - last >= self.config.min_gap);
+                    .is_none_or(|last| frame_idx - last >= self.config.min_gap);
                 if gap_ok {
                     finalized_cliff = true;
                     self.last_cliff_index = Some(frame_idx);
@@ -0,0 +1,83 @@
+/// Platt scaling of detector confidences into probabilities of a real player:
+/// `p = sigmoid(slope * logit(confidence) + offset)`. The default is the identity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceCalibration {
+    pub slope: f32,
+    pub offset: f32,
+}
+
+impl Default for ConfidenceCalibration {
+    fn default() -> Self {
+        Self { slope: 1.0, offset: 0.0 }
+    }
+}
+
+impl ConfidenceCalibration {
+    pub fn probability(&self, confidence: f32) -> f32 {
+        // Keep the logit finite at confidences of exactly 0 or 1
+        let c = confidence.clamp(1e-4, 1.0 - 1e-4);
+        let logit = (c / (1.0 - c)).ln();
+        1.0 / (1.0 + (-(self.slope * logit + self.offset)).exp())
+    }
+}
+
+/// Uncertainty-aware count of the players in one region.
+///
+/// Each detection is a real player with its calibrated probability `p`, independently
+/// of the others, so the count is Poisson-binomial: its expectation is the sum of `p`
+/// and its variance the sum of `p * (1 - p)`. A borderline detection moves the
+/// expectation by about half a player instead of flipping the count between 0 and 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExpectedCount {
+    pub expected: f32,
+    pub variance: f32,
+}
+
+impl ExpectedCount {
+    pub fn add(&mut self, probability: f32) {
+        let p = probability.clamp(0.0, 1.0);
+        self.expected += p;
+        self.variance += p * (1.0 - p);
+    }
+
+    /// Scaled by `1 / team_size`, like the normalized counts of `EndZoneOccupancy`.
+    pub fn normalized(&self, team_size: u32) -> Self {
+        let n = team_size.max(1) as f32;
+        Self { expected: self.expected / n, variance: self.variance / (n * n) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_calibration_keeps_confidences() {
+        let calibration = ConfidenceCalibration::default();
+        for c in [0.1, 0.35, 0.5, 0.9] {
+            assert!((calibration.probability(c) - c).abs() < 1e-5, "{}", c);
+        }
+    }
+
+    #[test]
+    fn calibration_shifts_and_sharpens() {
+        // Detectors are overconfident near the threshold: a negative offset lowers them
+        let calibration = ConfidenceCalibration { slope: 2.0, offset: -0.5 };
+        assert!(calibration.probability(0.5) < 0.5);
+        assert!(calibration.probability(0.9) > 0.9);
+        assert!(calibration.probability(0.0) >= 0.0 && calibration.probability(1.0) <= 1.0);
+    }
+
+    #[test]
+    fn expected_count_is_poisson_binomial() {
+        let mut count = ExpectedCount::default();
+        for p in [1.0, 1.0, 0.5] {
+            count.add(p);
+        }
+        assert_eq!(count, ExpectedCount { expected: 2.5, variance: 0.25 });
+
+        let normalized = count.normalized(5);
+        assert!((normalized.expected - 0.5).abs() < 1e-6);
+        assert!((normalized.variance - 0.01).abs() < 1e-6);
+    }
+}
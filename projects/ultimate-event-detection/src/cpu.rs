@@ -0,0 +1,156 @@
+use rayon::prelude::*;
+
+use crate::cliff::{median, CliffDetectorConfig};
+
+/// Batch cliff detection on the CPU, bit-identical to calling `is_cliff_at` for
+/// every index.
+///
+/// `is_cliff_at` re-smooths the whole series on each call. Here the series is
+/// smoothed once, a branch-free pass over contiguous slices (which the compiler
+/// vectorizes) rejects indices failing the drop and absolute-threshold checks,
+/// and only the surviving candidates pay for the median windows, in parallel.
+///
+/// Smoothing deliberately sums each window in the same order as `is_cliff_at`
+/// rather than using prefix sums, which would round differently.
+pub fn detect_cliffs_cpu(scores: &[f32], config: &CliffDetectorConfig) -> Vec<bool> {
+    let smoothed = smooth(scores, config.smoothing_window);
+    let candidates = drop_mask(&smoothed, config);
+
+    candidates
+        .par_iter()
+        .enumerate()
+        .map(|(i, &candidate)| candidate && windows_hold(config, scores, &smoothed, i))
+        .collect()
+}
+
+fn smooth(scores: &[f32], window: usize) -> Vec<f32> {
+    if window <= 1 {
+        return scores.to_vec();
+    }
+    (0..scores.len())
+        .into_par_iter()
+        .map(|i| {
+            let start = i.saturating_sub(window - 1);
+            let slice = &scores[start..i + 1];
+            slice.iter().sum::<f32>() / slice.len() as f32
+        })
+        .collect()
+}
+
+/// Drop and absolute-threshold checks of `is_cliff_at`, written with negated
+/// comparisons so NaN scores are treated exactly as the scalar code treats them.
+#[inline(always)]
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
+fn passes_drop(current: f32, next: f32, back: f32, config: &CliffDetectorConfig) -> bool {
+    let drop = current - next;
+    let cumulative_drop = back - next;
+    let effective_drop = drop.max(cumulative_drop);
+    !(effective_drop < config.min_drop) & !(next > config.absolute_threshold)
+}
+
+fn drop_mask(smoothed: &[f32], config: &CliffDetectorConfig) -> Vec<bool> {
+    let n = smoothed.len();
+    let mut mask = vec![false; n];
+
+    // `is_cliff_at` needs i + 1 < n and i + min_post_duration < n
+    let limit = n.saturating_sub(config.min_post_duration.max(1));
+    // Matches `smoothing_window - 1` in `is_cliff_at` (release semantics for 0)
+    let lag = config.smoothing_window.wrapping_sub(1);
+    let head = lag.min(limit);
+
+    // Leading indices whose cumulative window is clamped to the start
+    for (i, m) in mask.iter_mut().enumerate().take(head) {
+        *m = passes_drop(smoothed[i], smoothed[i + 1], smoothed[0], config);
+    }
+
+    if head < limit {
+        let current = &smoothed[head..limit];
+        let next = &smoothed[head + 1..limit + 1];
+        let back = &smoothed[head - lag..limit - lag];
+        for (((m, &c), &nx), &b) in mask[head..limit].iter_mut().zip(current).zip(next).zip(back) {
+            *m = passes_drop(c, nx, b, config);
+        }
+    }
+
+    mask
+}
+
+/// Pre-point plateau and post-point stability checks of `is_cliff_at`.
+fn windows_hold(config: &CliffDetectorConfig, scores: &[f32], smoothed: &[f32], i: usize) -> bool {
+    let start_pre = i.saturating_sub(config.min_prepoint_duration);
+    let pre_window = &smoothed[start_pre..i];
+    if !pre_window.is_empty() {
+        let threshold = if pre_window.len() >= config.min_prepoint_duration {
+            0.5
+        } else {
+            config.video_start_prepoint_threshold
+        };
+        if median(pre_window) < threshold {
+            return false;
+        }
+    }
+
+    let post_end = (i + 1 + config.min_post_duration).min(scores.len());
+    let post_window = &scores[i + 1..post_end];
+    if post_window.len() < config.min_post_duration {
+        return false;
+    }
+    if median(post_window) > config.max_post_proba {
+        return false;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cliff::is_cliff_at;
+
+    /// Deterministic noisy game trace: plateaus, drops and jitter.
+    fn trace(len: usize, seed: u64) -> Vec<f32> {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32) / (1u64 << 31) as f32
+        };
+        (0..len)
+            .map(|i| {
+                let base = if (i / 40) % 2 == 0 { 0.85 } else { 0.15 };
+                (base + (next() - 0.5) * 0.3).clamp(0.0, 1.0)
+            })
+            .collect()
+    }
+
+    fn reference(scores: &[f32], config: &CliffDetectorConfig) -> Vec<bool> {
+        (0..scores.len()).map(|i| is_cliff_at(config, scores, i)).collect()
+    }
+
+    #[test]
+    fn matches_is_cliff_at_across_configs() {
+        let configs = [
+            CliffDetectorConfig::default(),
+            CliffDetectorConfig { smoothing_window: 1, ..Default::default() },
+            CliffDetectorConfig { smoothing_window: 6, min_drop: 0.05, ..Default::default() },
+            CliffDetectorConfig { min_prepoint_duration: 3, min_post_duration: 1, ..Default::default() },
+            CliffDetectorConfig { min_post_duration: 0, max_post_proba: 1.0, ..Default::default() },
+        ];
+        for (seed, len) in [(1, 500), (2, 37), (3, 2000)] {
+            let scores = trace(len, seed);
+            for config in &configs {
+                let expected = reference(&scores, config);
+                assert!(expected.iter().any(|&c| c) || len < 100);
+                assert_eq!(detect_cliffs_cpu(&scores, config), expected, "seed {} len {}", seed, len);
+            }
+        }
+    }
+
+    #[test]
+    fn short_and_empty_series() {
+        let config = CliffDetectorConfig::default();
+        for len in 0..15 {
+            let scores = trace(len, 7);
+            assert_eq!(detect_cliffs_cpu(&scores, &config), reference(&scores, &config));
+        }
+    }
+}
@@ -0,0 +1,227 @@
+/// A disc detection in one frame, in overview coordinates normalized to `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiscSighting {
+    pub x: f32,
+    pub y: f32,
+    pub confidence: f32,
+}
+
+/// Configuration for disc trajectory tracking. Distances are normalized to the
+/// overview; steps, gaps and speeds count sampled frames, so they depend on the
+/// caller's sample rate.
+#[derive(Clone, Debug)]
+pub struct DiscTrackerConfig {
+    /// Farthest a disc can move between consecutive sampled frames.
+    pub max_step: f32,
+    /// Extra slack around the predicted position, for detector jitter.
+    pub gate_margin: f32,
+    /// Frames a track survives without a sighting (occlusion, motion blur).
+    pub max_gap: usize,
+    /// Sightings before a track counts as a flight.
+    pub min_sightings: usize,
+    /// Distance from its first sighting a track must cover to count as airborne.
+    /// Discs lying on the ground or held still never get there.
+    pub min_travel: f32,
+    /// Average speed from the first sighting, per frame, to count as airborne.
+    /// A disc carried by a walking player moves far more slowly than a throw.
+    pub min_speed: f32,
+}
+
+impl Default for DiscTrackerConfig {
+    fn default() -> Self {
+        Self { max_step: 0.15, gate_margin: 0.02, max_gap: 3, min_sightings: 3, min_travel: 0.05, min_speed: 0.04 }
+    }
+}
+
+/// A disc currently in flight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiscFlight {
+    /// Where and when the disc was first seen, i.e. where it was thrown from.
+    pub origin: (f32, f32),
+    pub origin_frame: usize,
+    pub position: (f32, f32),
+    pub sightings: usize,
+}
+
+struct Track {
+    origin: (f32, f32),
+    origin_frame: usize,
+    last: (f32, f32),
+    last_frame: usize,
+    /// Displacement per frame over the last step
+    velocity: (f32, f32),
+    sightings: usize,
+}
+
+impl Track {
+    fn predict(&self, frame_index: usize) -> (f32, f32) {
+        let frames = frame_index.saturating_sub(self.last_frame) as f32;
+        (self.last.0 + self.velocity.0 * frames, self.last.1 + self.velocity.1 * frames)
+    }
+
+    fn travel(&self) -> f32 {
+        distance(self.origin, self.last)
+    }
+
+    fn is_airborne(&self, config: &DiscTrackerConfig) -> bool {
+        let frames = self.last_frame.saturating_sub(self.origin_frame).max(1) as f32;
+        self.sightings >= config.min_sightings
+            && self.travel() >= config.min_travel
+            && self.travel() / frames >= config.min_speed
+    }
+}
+
+/// Links disc sightings across sampled frames into trajectories.
+///
+/// Each track predicts its next position at constant velocity; sightings are
+/// matched greedily to the nearest prediction within reach, and the rest start
+/// new tracks. Several discs (warm-up throws, a spare on the sideline) are tracked
+/// independently.
+pub struct DiscTracker {
+    config: DiscTrackerConfig,
+    tracks: Vec<Track>,
+}
+
+impl DiscTracker {
+    pub fn new(config: DiscTrackerConfig) -> Self {
+        Self { config, tracks: Vec::new() }
+    }
+
+    /// Adds one frame's sightings; frames must arrive in ascending order. Returns the
+    /// longest flight seen in this frame, if any.
+    pub fn push(&mut self, frame_index: usize, sightings: &[DiscSighting]) -> Option<DiscFlight> {
+        let max_gap = self.config.max_gap;
+        self.tracks.retain(|t| frame_index.saturating_sub(t.last_frame) <= max_gap + 1);
+
+        // Every track/sighting pair within reach, nearest first
+        let mut pairs = Vec::new();
+        for (ti, track) in self.tracks.iter().enumerate() {
+            let frames = frame_index.saturating_sub(track.last_frame).max(1) as f32;
+            let reach = self.config.max_step * frames + self.config.gate_margin;
+            let predicted = track.predict(frame_index);
+            for (si, s) in sightings.iter().enumerate() {
+                let d = distance(predicted, (s.x, s.y)).min(distance(track.last, (s.x, s.y)));
+                if d <= reach {
+                    pairs.push((d, ti, si));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut track_used = vec![false; self.tracks.len()];
+        let mut sighting_used = vec![false; sightings.len()];
+        let mut updated = Vec::new();
+        for (_, ti, si) in pairs {
+            if track_used[ti] || sighting_used[si] {
+                continue;
+            }
+            track_used[ti] = true;
+            sighting_used[si] = true;
+
+            let track = &mut self.tracks[ti];
+            let frames = frame_index.saturating_sub(track.last_frame).max(1) as f32;
+            let position = (sightings[si].x, sightings[si].y);
+            track.velocity = ((position.0 - track.last.0) / frames, (position.1 - track.last.1) / frames);
+            track.last = position;
+            track.last_frame = frame_index;
+            track.sightings += 1;
+            updated.push(ti);
+        }
+
+        for (s, _) in sightings.iter().zip(&sighting_used).filter(|(_, used)| !**used) {
+            self.tracks.push(Track {
+                origin: (s.x, s.y),
+                origin_frame: frame_index,
+                last: (s.x, s.y),
+                last_frame: frame_index,
+                velocity: (0.0, 0.0),
+                sightings: 1,
+            });
+        }
+
+        updated
+            .into_iter()
+            .map(|ti| &self.tracks[ti])
+            .filter(|t| t.is_airborne(&self.config))
+            .max_by_key(|t| t.sightings)
+            .map(|t| DiscFlight {
+                origin: t.origin,
+                origin_frame: t.origin_frame,
+                position: t.last,
+                sightings: t.sightings,
+            })
+    }
+}
+
+impl Default for DiscTracker {
+    fn default() -> Self {
+        Self::new(DiscTrackerConfig::default())
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f32, y: f32) -> DiscSighting {
+        DiscSighting { x, y, confidence: 0.8 }
+    }
+
+    #[test]
+    fn pull_is_tracked_from_its_origin() {
+        let mut tracker = DiscTracker::default();
+        // A pull from the left end zone, a disc lying still on the right sideline,
+        // and one dropped frame
+        let mut flights = Vec::new();
+        for frame in 0..8 {
+            let mut sightings = vec![at(0.9, 0.95)];
+            if frame != 4 {
+                sightings.push(at(0.1 + 0.08 * frame as f32, 0.5 - 0.02 * frame as f32));
+            }
+            flights.push(tracker.push(frame, &sightings));
+        }
+
+        // Not airborne until the third sighting
+        assert!(flights[..2].iter().all(|f| f.is_none()));
+        assert_eq!(flights[4], None);
+        for flight in flights[2..].iter().filter_map(|f| f.as_ref()) {
+            assert_eq!(flight.origin, (0.1, 0.5));
+            assert_eq!(flight.origin_frame, 0);
+        }
+        let last = flights[7].unwrap();
+        assert_eq!(last.sightings, 7);
+        assert!((last.position.0 - 0.66).abs() < 1e-5);
+    }
+
+    #[test]
+    fn carried_disc_is_not_airborne() {
+        let mut tracker = DiscTracker::default();
+        // A player walks the disc to the goal line before the pull
+        for frame in 0..20 {
+            assert_eq!(tracker.push(frame, &[at(0.1 + 0.01 * frame as f32, 0.5)]), None);
+        }
+    }
+
+    #[test]
+    fn distant_sightings_start_new_tracks() {
+        let mut tracker = DiscTracker::default();
+        tracker.push(0, &[at(0.1, 0.5)]);
+        tracker.push(1, &[at(0.18, 0.5)]);
+        // Jumps across the field are a different disc, not the same throw
+        assert_eq!(tracker.push(2, &[at(0.8, 0.2)]), None);
+        // The first track is still alive through the gap
+        let flight = tracker.push(3, &[at(0.34, 0.5)]).unwrap();
+        assert_eq!(flight.origin, (0.1, 0.5));
+        assert_eq!(flight.sightings, 3);
+
+        // Past max_gap the track is forgotten
+        let mut tracker = DiscTracker::default();
+        tracker.push(0, &[at(0.1, 0.5)]);
+        tracker.push(1, &[at(0.18, 0.5)]);
+        assert_eq!(tracker.push(6, &[at(0.26, 0.5)]), None);
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::cliff::CliffDetectorConfig;
+use crate::stance::StanceConfig;
+
+/// Shoulder-to-hip length of an adult player, in meters.
+const TORSO_LENGTH_M: f32 = 0.5;
+
+/// Share of the end-zone depth a lined-up team may stray from its goal line.
+const LINE_DEPTH_SHARE: f32 = 1.0 / 18.0;
+
+/// Game format being played. Determines how many players a "full" end zone
+/// holds and which cliff-detection defaults suit the footage.
+///
+/// Mixed-gender grass games use `Sevens`; the gender ratio does not change
+/// the line-up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameFormat {
+    /// 7v7 on a full grass field (open, women's, mixed).
+    #[default]
+    Sevens,
+    /// 5v5 on a reduced grass or indoor field.
+    Fives,
+    /// 4v4 on a small field, typically beach or hat tournaments.
+    Fours,
+    /// 5v5 beach ultimate on a 75 × 25 m field.
+    Beach,
+}
+
+impl GameFormat {
+    pub const ALL: [GameFormat; 4] = [
+        GameFormat::Sevens,
+        GameFormat::Fives,
+        GameFormat::Fours,
+        GameFormat::Beach,
+    ];
+
+    /// Players per team on the field.
+    pub fn team_size(self) -> u32 {
+        match self {
+            GameFormat::Sevens => 7,
+            GameFormat::Fives | GameFormat::Beach => 5,
+            GameFormat::Fours => 4,
+        }
+    }
+
+    /// Expected end-zone depth in meters.
+    pub fn end_zone_depth_m(self) -> f32 {
+        match self {
+            GameFormat::Sevens => 18.0,
+            GameFormat::Fives => 15.0,
+            GameFormat::Fours => 10.0,
+            GameFormat::Beach => 15.0,
+        }
+    }
+
+    /// Typical spacing between players lined up on the goal line, in meters.
+    pub fn line_up_spacing_m(self) -> f32 {
+        match self {
+            GameFormat::Sevens => 5.0,
+            GameFormat::Fives => 4.5,
+            GameFormat::Fours => 4.0,
+            GameFormat::Beach => 4.5,
+        }
+    }
+
+    /// Default cliff-detection parameters for this format.
+    ///
+    /// With fewer players each one is a larger fraction of the normalized
+    /// count, so a single occlusion moves the score further. Smaller formats
+    /// smooth over more frames and require a deeper drop.
+    pub fn cliff_config(self) -> CliffDetectorConfig {
+        let base = CliffDetectorConfig::default();
+        match self {
+            GameFormat::Sevens => base,
+            GameFormat::Fives | GameFormat::Beach => CliffDetectorConfig {
+                min_drop: 0.2,
+                smoothing_window: 4,
+                ..base
+            },
+            GameFormat::Fours => CliffDetectorConfig {
+                min_drop: 0.25,
+                smoothing_window: 5,
+                ..base
+            },
+        }
+    }
+
+    /// Line-up geometry for the stance features, in torso lengths.
+    ///
+    /// A team lined up on its goal line strays from the line by a small share of
+    /// the end zone's depth, and a player more than two spacings from the next
+    /// one is not part of the same line.
+    pub fn stance_config(self) -> StanceConfig {
+        StanceConfig {
+            max_line_spread: self.end_zone_depth_m() * LINE_DEPTH_SHARE / TORSO_LENGTH_M,
+            max_line_gap: 2.0 * self.line_up_spacing_m() / TORSO_LENGTH_M,
+            ..StanceConfig::default()
+        }
+    }
+
+    /// Best-guess format for a bare team size, used for runs recorded before
+    /// the format was stored explicitly.
+    pub fn from_team_size(team_size: u32) -> Self {
+        match team_size {
+            0..=4 => GameFormat::Fours,
+            5..=6 => GameFormat::Fives,
+            _ => GameFormat::Sevens,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GameFormat::Sevens => "sevens",
+            GameFormat::Fives => "fives",
+            GameFormat::Fours => "fours",
+            GameFormat::Beach => "beach",
+        }
+    }
+}
+
+impl fmt::Display for GameFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GameFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sevens" | "7s" | "7v7" | "mixed" => Ok(GameFormat::Sevens),
+            "fives" | "5s" | "5v5" => Ok(GameFormat::Fives),
+            "fours" | "4s" | "4v4" => Ok(GameFormat::Fours),
+            "beach" => Ok(GameFormat::Beach),
+            other => Err(format!("unknown game format: {}", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::{pre_point_score, EndZoneOccupancy};
+
+    #[test]
+    fn round_trips_through_str() {
+        for format in GameFormat::ALL {
+            assert_eq!(format.as_str().parse::<GameFormat>(), Ok(format));
+        }
+        assert_eq!("4v4".parse::<GameFormat>(), Ok(GameFormat::Fours));
+        assert!("ultimate".parse::<GameFormat>().is_err());
+    }
+
+    #[test]
+    fn team_size_inference_matches_format() {
+        assert_eq!(GameFormat::from_team_size(7), GameFormat::Sevens);
+        assert_eq!(GameFormat::from_team_size(5), GameFormat::Fives);
+        assert_eq!(GameFormat::from_team_size(4), GameFormat::Fours);
+    }
+
+    #[test]
+    fn sevens_keeps_existing_defaults() {
+        let config = GameFormat::Sevens.cliff_config();
+        let default = CliffDetectorConfig::default();
+        assert_eq!(config.min_drop, default.min_drop);
+        assert_eq!(config.smoothing_window, default.smoothing_window);
+    }
+
+    #[test]
+    fn sevens_keeps_stance_defaults() {
+        let config = GameFormat::Sevens.stance_config();
+        let default = StanceConfig::default();
+        assert!((config.max_line_spread - default.max_line_spread).abs() < 1e-6);
+        assert!((config.max_line_gap - default.max_line_gap).abs() < 1e-6);
+    }
+
+    #[test]
+    fn smaller_end_zones_need_tighter_lines() {
+        let sevens = GameFormat::Sevens.stance_config();
+        let fours = GameFormat::Fours.stance_config();
+        assert!(fours.max_line_spread < sevens.max_line_spread);
+        assert!(fours.max_line_gap < sevens.max_line_gap);
+    }
+
+    #[test]
+    fn half_line_up_scores_as_full_in_fours() {
+        // Two players in each end zone is a full pre-point huddle side in 4v4
+        let format = GameFormat::Fours;
+        let two = 2.0 / format.team_size() as f32;
+        let occupancy = EndZoneOccupancy { left: two, right: two, field: 0.0 };
+        assert!(pre_point_score(&occupancy, format.team_size()) > 0.9);
+
+        // The same raw counts normalized as a 7s game are only a partial signal
+        let two = 2.0 / GameFormat::Sevens.team_size() as f32;
+        let occupancy = EndZoneOccupancy { left: two, right: two, field: 0.0 };
+        assert!(pre_point_score(&occupancy, 7) < 0.7);
+    }
+}
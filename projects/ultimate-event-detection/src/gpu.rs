@@ -1,6 +1,8 @@
 use crate::cliff::CliffDetectorConfig;
-#[cfg(not(feature = "metal"))]
-use crate::cliff::is_cliff_at;
+use crate::cpu::detect_cliffs_cpu;
+#[cfg(feature = "wgpu")]
+use crate::gpu_wgpu::{self, WgpuBackend};
+#[cfg(feature = "metal")]
 use std::ffi::c_void;
 
 /// Detector configuration layout matching the Metal shader's struct.
@@ -52,29 +54,22 @@ extern "C" {
     fn gpu_release_pipeline(pipeline: *mut c_void);
 }
 
-/// GPU-accelerated cliff detector using Metal (macOS only, requires `metal` feature).
-///
-/// Falls back to the pure-Rust `is_cliff_at` on non-macOS or when the `metal` feature
-/// is not enabled.
-pub struct GpuCliffDetector {
-    #[cfg(feature = "metal")]
+/// Metal device, queue and pipeline created through the Objective-C bridge.
+#[cfg(feature = "metal")]
+struct MetalBackend {
     device: *mut c_void,
-    #[cfg(feature = "metal")]
     command_queue: *mut c_void,
-    #[cfg(feature = "metal")]
     pipeline: *mut c_void,
-    #[cfg(not(feature = "metal"))]
-    _phantom: std::marker::PhantomData<()>,
 }
 
 #[cfg(feature = "metal")]
-unsafe impl Send for GpuCliffDetector {}
+unsafe impl Send for MetalBackend {}
 #[cfg(feature = "metal")]
-unsafe impl Sync for GpuCliffDetector {}
+unsafe impl Sync for MetalBackend {}
 
-impl GpuCliffDetector {
-    #[cfg(feature = "metal")]
-    pub fn new() -> Result<Self, String> {
+#[cfg(feature = "metal")]
+impl MetalBackend {
+    fn new() -> Result<Self, String> {
         unsafe {
             let device = gpu_init();
             if device.is_null() {
@@ -96,28 +91,7 @@ impl GpuCliffDetector {
         }
     }
 
-    #[cfg(not(feature = "metal"))]
-    pub fn new() -> Result<Self, String> {
-        Ok(Self { _phantom: std::marker::PhantomData })
-    }
-
-    /// Detect cliffs in a score sequence.
-    ///
-    /// Uses the Metal GPU shader when the `metal` feature is enabled on macOS,
-    /// otherwise falls back to the pure-Rust batch implementation.
-    pub fn detect_cliffs(&self, scores: &[f32], config: &CliffDetectorConfig) -> Result<Vec<bool>, String> {
-        #[cfg(feature = "metal")]
-        {
-            self.detect_cliffs_gpu(scores, config)
-        }
-        #[cfg(not(feature = "metal"))]
-        {
-            Ok((0..scores.len()).map(|i| is_cliff_at(config, scores, i)).collect())
-        }
-    }
-
-    #[cfg(feature = "metal")]
-    fn detect_cliffs_gpu(&self, scores: &[f32], config: &CliffDetectorConfig) -> Result<Vec<bool>, String> {
+    fn detect_cliffs(&self, scores: &[f32], config: &CliffDetectorConfig) -> Result<Vec<bool>, String> {
         if scores.is_empty() {
             return Ok(Vec::new());
         }
@@ -143,7 +117,7 @@ impl GpuCliffDetector {
 }
 
 #[cfg(feature = "metal")]
-impl Drop for GpuCliffDetector {
+impl Drop for MetalBackend {
     fn drop(&mut self) {
         unsafe {
             gpu_release_pipeline(self.pipeline);
@@ -152,3 +126,81 @@ impl Drop for GpuCliffDetector {
         }
     }
 }
+
+enum Backend {
+    #[cfg(feature = "metal")]
+    Metal(MetalBackend),
+    #[cfg(feature = "wgpu")]
+    Wgpu(WgpuBackend),
+    Cpu,
+}
+
+/// Batch cliff detector that runs on the best available backend.
+///
+/// Backends are tried in order: Metal (`metal` feature, macOS), wgpu (`wgpu`
+/// feature; Vulkan, DX12, GL or a software adapter such as lavapipe), and
+/// finally the parallel CPU backend (`detect_cliffs_cpu`), which is always
+/// available.
+pub struct GpuCliffDetector {
+    backend: Backend,
+}
+
+impl GpuCliffDetector {
+    /// Initializes the first backend that starts successfully. Never fails in
+    /// practice since the CPU backend is the last resort; the `Result` is kept
+    /// for API compatibility.
+    pub fn new() -> Result<Self, String> {
+        #[cfg(feature = "metal")]
+        {
+            if let Ok(metal) = MetalBackend::new() {
+                return Ok(Self { backend: Backend::Metal(metal) });
+            }
+        }
+        #[cfg(feature = "wgpu")]
+        {
+            if let Ok(wgpu) = WgpuBackend::new() {
+                return Ok(Self { backend: Backend::Wgpu(wgpu) });
+            }
+        }
+        Ok(Self { backend: Backend::Cpu })
+    }
+
+    /// Always uses the parallel CPU backend.
+    pub fn cpu() -> Self {
+        Self { backend: Backend::Cpu }
+    }
+
+    /// Human-readable name of the active backend, e.g. "metal", "wgpu (llvmpipe)" or "cpu".
+    pub fn backend_name(&self) -> String {
+        match &self.backend {
+            #[cfg(feature = "metal")]
+            Backend::Metal(_) => "metal".to_string(),
+            #[cfg(feature = "wgpu")]
+            Backend::Wgpu(wgpu) => format!("wgpu ({})", wgpu.adapter_name()),
+            Backend::Cpu => "cpu".to_string(),
+        }
+    }
+
+    /// Detect cliffs in a score sequence.
+    ///
+    /// The CPU backend is bit-identical to `is_cliff_at`. GPU backends implement
+    /// the same algorithm but may round scores sitting exactly on a threshold
+    /// differently. The wgpu shader holds median windows of at most 30 frames;
+    /// longer windows run on the CPU.
+    pub fn detect_cliffs(&self, scores: &[f32], config: &CliffDetectorConfig) -> Result<Vec<bool>, String> {
+        match &self.backend {
+            #[cfg(feature = "metal")]
+            Backend::Metal(metal) => metal.detect_cliffs(scores, config),
+            #[cfg(feature = "wgpu")]
+            Backend::Wgpu(wgpu) => {
+                if config.min_prepoint_duration > gpu_wgpu::MAX_WINDOW
+                    || config.min_post_duration > gpu_wgpu::MAX_WINDOW
+                {
+                    return Ok(detect_cliffs_cpu(scores, config));
+                }
+                wgpu.detect_cliffs(scores, &MetalDetectorParams::from(config))
+            }
+            Backend::Cpu => Ok(detect_cliffs_cpu(scores, config)),
+        }
+    }
+}
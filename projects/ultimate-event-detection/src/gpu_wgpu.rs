@@ -0,0 +1,225 @@
+use wgpu::util::DeviceExt;
+
+use crate::gpu::MetalDetectorParams;
+
+/// Longest median window the WGSL shader holds (see `MAX_WINDOW` in the shader).
+pub(crate) const MAX_WINDOW: usize = 30;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Cliff detection through wgpu (Vulkan, DX12, Metal or GL), including software
+/// adapters such as lavapipe/llvmpipe.
+pub(crate) struct WgpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter_name: String,
+}
+
+impl WgpuBackend {
+    /// Picks the highest-performance adapter, falling back to a software one.
+    /// `WGPU_BACKEND` and related environment variables are honoured.
+    pub(crate) fn new() -> Result<Self, String> {
+        pollster::block_on(Self::init())
+    }
+
+    async fn init() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+
+        let adapter = match instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+        {
+            Ok(adapter) => adapter,
+            Err(_) => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: true,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| format!("No wgpu adapter available: {}", e))?,
+        };
+        let adapter_name = adapter.get_info().name;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("cliff-detector"),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("Failed to create wgpu device: {}", e))?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("detect_cliffs"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("wgsl/detect_cliffs.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("detect_cliffs"),
+            layout: None,
+            module: &module,
+            entry_point: Some("detect_cliffs"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Ok(Self { device, queue, pipeline, adapter_name })
+    }
+
+    pub(crate) fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    pub(crate) fn detect_cliffs(&self, scores: &[f32], params: &MetalDetectorParams) -> Result<Vec<bool>, String> {
+        if scores.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let workgroups = (scores.len() as u32).div_ceil(WORKGROUP_SIZE);
+        if workgroups > self.device.limits().max_compute_workgroups_per_dimension {
+            return Err(format!("Score series too long for a single dispatch: {}", scores.len()));
+        }
+
+        let score_bytes: Vec<u8> = scores.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let param_bytes: Vec<u8> = [
+            params.min_drop,
+            params.min_prepoint_duration,
+            params.min_post_duration,
+            params.max_post_proba,
+            params.absolute_threshold,
+            params.min_gap,
+            params.smoothing_window,
+            params.video_start_prepoint_threshold,
+        ]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+        let output_size = (scores.len() * std::mem::size_of::<u32>()) as wgpu::BufferAddress;
+
+        let score_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("scores"),
+            contents: &score_bytes,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let param_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: &param_bytes,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("detected"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("detected-readback"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("detect_cliffs"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: score_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: param_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: output_buffer.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("detect_cliffs") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("detect_cliffs"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(workgroups, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output_buffer, 0, &readback_buffer, 0, output_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        readback_buffer.map_async(wgpu::MapMode::Read, .., move |result| {
+            let _ = tx.send(result);
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| format!("wgpu poll failed: {}", e))?;
+        rx.recv()
+            .map_err(|_| "wgpu readback callback dropped".to_string())?
+            .map_err(|e| format!("wgpu readback failed: {}", e))?;
+
+        let flags = {
+            let view = readback_buffer
+                .get_mapped_range(..)
+                .map_err(|e| format!("wgpu readback failed: {}", e))?;
+            view.chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) != 0)
+                .collect()
+        };
+        readback_buffer.unmap();
+        Ok(flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cliff::CliffDetectorConfig;
+    use crate::cpu::detect_cliffs_cpu;
+    use wgpu::naga;
+
+    #[test]
+    fn shader_validates() {
+        let module = naga::front::wgsl::parse_str(include_str!("wgsl/detect_cliffs.wgsl"))
+            .expect("WGSL parse failed");
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+            .expect("WGSL validation failed");
+    }
+
+    #[test]
+    fn matches_cpu_backend() {
+        // Needs an adapter. CI provides one through lavapipe and sets WGPU_REQUIRED,
+        // so a missing adapter fails there instead of passing silently.
+        let backend = match WgpuBackend::new() {
+            Ok(backend) => backend,
+            Err(e) if std::env::var_os("WGPU_REQUIRED").is_some() => {
+                panic!("WGPU_REQUIRED is set but no adapter is available: {}", e)
+            }
+            Err(e) => {
+                eprintln!("skipping: {} (set WGPU_REQUIRED to fail instead)", e);
+                return;
+            }
+        };
+
+        // Plateaus and drops well away from the thresholds, so GPU rounding cannot flip a decision
+        let scores: Vec<f32> = (0..1000)
+            .map(|i| match (i / 40) % 3 {
+                0 => 0.9,
+                1 => 0.1,
+                _ => if i % 2 == 0 { 0.8 } else { 0.95 },
+            })
+            .collect();
+
+        for config in [
+            CliffDetectorConfig::default(),
+            CliffDetectorConfig { smoothing_window: 1, ..Default::default() },
+            CliffDetectorConfig { min_prepoint_duration: 3, min_post_duration: 5, ..Default::default() },
+        ] {
+            let expected = detect_cliffs_cpu(&scores, &config);
+            assert!(expected.iter().any(|&c| c));
+            let actual = backend.detect_cliffs(&scores, &MetalDetectorParams::from(&config)).unwrap();
+            assert_eq!(actual, expected, "adapter: {}", backend.adapter_name());
+        }
+    }
+}
@@ -1,9 +1,30 @@
+pub mod bystanders;
 pub mod cliff;
+pub mod counting;
+pub mod cpu;
+pub mod disc;
+pub mod format;
 pub mod gpu;
+#[cfg(feature = "wgpu")]
+mod gpu_wgpu;
 pub mod pull_side;
 pub mod scoring;
+pub mod spatial;
+pub mod stance;
+pub mod team_size;
 
+pub use bystanders::{BystanderConfig, BystanderFilter, Exclusion, PersonObservation};
 pub use cliff::{is_cliff_at, CliffDetector, CliffDetectorConfig};
+pub use counting::{ConfidenceCalibration, ExpectedCount};
+pub use cpu::detect_cliffs_cpu;
+pub use disc::{DiscFlight, DiscSighting, DiscTracker, DiscTrackerConfig};
+pub use format::GameFormat;
 pub use gpu::GpuCliffDetector;
-pub use pull_side::{detect_pull_side, PullSide};
+pub use pull_side::{
+    detect_pull_side, estimate_pull_side, PullSide, PullSideConfig, PullSideEstimate,
+    PullSideEvidence, PullSideSample,
+};
 pub use scoring::{pre_point_score, EndZoneOccupancy};
+pub use spatial::{Aabb, BoxGrid, PreparedPolygon};
+pub use stance::{pose_features, stance_adjusted_score, Pose, PoseFeatures, StanceConfig};
+pub use team_size::{estimate_team_size, RawCounts, TeamSizeConfig, TeamSizeEstimate};
@@ -76,6 +76,249 @@ pub fn detect_pull_side(
     }
 }
 
+/// Per-frame input to [`estimate_pull_side`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullSideSample {
+    pub frame_index: usize,
+    pub occupancy: EndZoneOccupancy,
+    /// Frame-to-frame change in normalized center-of-mass x, if players were visible.
+    pub com_delta_x: Option<f32>,
+    /// End zone (`Left` or `Right`) a disc seen in flight this frame was thrown from.
+    pub disc_from: Option<PullSide>,
+}
+
+/// Configuration for the evidence-weighted pull-side estimator.
+#[derive(Clone, Debug)]
+pub struct PullSideConfig {
+    /// Consecutive zero-count frames for the legacy zero-crossing vote.
+    pub debounce_frames: usize,
+    /// Frames after the cliff used to measure occupancy slopes, CoM motion and field growth.
+    pub post_window: usize,
+    /// Cumulative CoM x displacement (normalized) that counts as a full-strength vote.
+    pub com_displacement_scale: f32,
+    /// Field occupancy growth (normalized) that counts as a fully started point.
+    pub field_growth_scale: f32,
+    pub slope_weight: f32,
+    pub com_weight: f32,
+    pub zero_weight: f32,
+    /// Weight of discs seen flying out of an end zone, the most direct signal.
+    pub disc_weight: f32,
+    /// Below this confidence the estimate is reported as `Unknown` (or `Tie`).
+    pub min_confidence: f32,
+}
+
+impl Default for PullSideConfig {
+    fn default() -> Self {
+        Self {
+            debounce_frames: 2,
+            post_window: 8,
+            com_displacement_scale: 0.05,
+            field_growth_scale: 0.3,
+            slope_weight: 1.0,
+            com_weight: 1.0,
+            zero_weight: 0.5,
+            disc_weight: 2.0,
+            min_confidence: 0.2,
+        }
+    }
+}
+
+/// Individual signals behind a [`PullSideEstimate`].
+///
+/// Votes are in [-1, 1]: positive favors `Left`, negative favors `Right`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullSideEvidence {
+    /// Least-squares occupancy slope of the left end zone after the cliff (per frame).
+    pub left_slope: f32,
+    /// Least-squares occupancy slope of the right end zone after the cliff (per frame).
+    pub right_slope: f32,
+    pub slope_vote: f32,
+    /// Summed CoM x displacement after the cliff; `None` when no CoM was available.
+    pub com_displacement: Option<f32>,
+    pub com_vote: f32,
+    /// Result of the legacy zero-crossing detector over the same window.
+    pub zero_count_side: PullSide,
+    /// Mean field occupancy after the cliff minus the mean before it.
+    pub field_growth: f32,
+    /// Share of in-flight disc frames thrown from the left minus those from the right;
+    /// `None` when no disc was seen in flight.
+    pub disc_vote: Option<f32>,
+}
+
+/// Pull side plus a confidence in [0, 1].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullSideEstimate {
+    pub side: PullSide,
+    pub confidence: f32,
+    pub evidence: PullSideEvidence,
+}
+
+/// Least-squares slope of `values` against their index.
+fn slope(values: &[f32]) -> f32 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let n = values.len() as f32;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f32>() / n;
+    let mut num = 0.0;
+    let mut den = 0.0;
+    for (i, v) in values.iter().enumerate() {
+        let dx = i as f32 - mean_x;
+        num += dx * (v - mean_y);
+        den += dx * dx;
+    }
+    if den > 0.0 {
+        num / den
+    } else {
+        0.0
+    }
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0f32, 0usize), |(s, c), v| (s + v, c + 1));
+    if count > 0 {
+        Some(sum / count as f32)
+    } else {
+        None
+    }
+}
+
+/// Estimate which team pulled using every signal around a cliff, not just zero crossings.
+///
+/// `history` is a slice of samples in ascending frame order covering lookback + lookahead
+/// around `cliff_frame`. Four signals are combined:
+///
+/// 1. **Occupancy slope** — the pulling end zone declines faster after the cliff, even when
+///    sideline bystanders keep it from ever reaching zero.
+/// 2. **CoM displacement** — players flow away from the pulling end zone, so a rightward
+///    (positive x) CoM shift points to a left pull.
+/// 3. **Zero crossing** — the result of [`detect_pull_side`], kept as a lower-weight vote.
+/// 4. **Field growth** — does not pick a side, but scales confidence: a real point start
+///    fills the field.
+/// 5. **Disc flight** — frames after the cliff where the disc is in the air, by the end
+///    zone it left. The pull itself, so it outweighs the others when present.
+pub fn estimate_pull_side(
+    history: &[PullSideSample],
+    cliff_frame: usize,
+    config: &PullSideConfig,
+) -> PullSideEstimate {
+    let post: Vec<&PullSideSample> = history
+        .iter()
+        .filter(|s| s.frame_index >= cliff_frame && s.frame_index <= cliff_frame + config.post_window)
+        .collect();
+
+    let left: Vec<f32> = post.iter().map(|s| s.occupancy.left).collect();
+    let right: Vec<f32> = post.iter().map(|s| s.occupancy.right).collect();
+    let left_slope = slope(&left);
+    let right_slope = slope(&right);
+
+    // Declines are positive; compare how much faster one side empties than the other.
+    let left_decline = (-left_slope).max(0.0);
+    let right_decline = (-right_slope).max(0.0);
+    let slope_vote = if left_decline + right_decline > f32::EPSILON {
+        (left_decline - right_decline) / (left_decline + right_decline)
+    } else {
+        0.0
+    };
+
+    let com_deltas: Vec<f32> = post
+        .iter()
+        .filter(|s| s.frame_index > cliff_frame)
+        .filter_map(|s| s.com_delta_x)
+        .collect();
+    let com_displacement = if com_deltas.is_empty() {
+        None
+    } else {
+        Some(com_deltas.iter().sum::<f32>())
+    };
+    let com_vote = com_displacement
+        .map(|d| (d / config.com_displacement_scale.max(f32::EPSILON)).tanh())
+        .unwrap_or(0.0);
+
+    let zero_window: Vec<(usize, EndZoneOccupancy)> = history
+        .iter()
+        .map(|s| (s.frame_index, s.occupancy.clone()))
+        .collect();
+    let zero_count_side = detect_pull_side(&zero_window, config.debounce_frames);
+    let zero_vote = match zero_count_side {
+        PullSide::Left => 1.0,
+        PullSide::Right => -1.0,
+        PullSide::Tie | PullSide::Unknown => 0.0,
+    };
+
+    let field_before = mean(
+        history
+            .iter()
+            .filter(|s| s.frame_index <= cliff_frame)
+            .map(|s| s.occupancy.field),
+    );
+    let field_after = mean(
+        history
+            .iter()
+            .filter(|s| s.frame_index > cliff_frame)
+            .map(|s| s.occupancy.field),
+    );
+    let field_growth = match (field_before, field_after) {
+        (Some(before), Some(after)) => after - before,
+        _ => 0.0,
+    };
+
+    // Only the pull counts: a disc thrown before the cliff belongs to the previous point
+    let (from_left, from_right) = post.iter().fold((0, 0), |(l, r), s| match s.disc_from {
+        Some(PullSide::Left) => (l + 1, r),
+        Some(PullSide::Right) => (l, r + 1),
+        _ => (l, r),
+    });
+    let disc_vote = if from_left + from_right > 0 {
+        Some((from_left as f32 - from_right as f32) / (from_left + from_right) as f32)
+    } else {
+        None
+    };
+
+    let mut weighted = config.slope_weight * slope_vote + config.zero_weight * zero_vote;
+    let mut total_weight = config.slope_weight + config.zero_weight;
+    if com_displacement.is_some() {
+        weighted += config.com_weight * com_vote;
+        total_weight += config.com_weight;
+    }
+    if let Some(vote) = disc_vote {
+        weighted += config.disc_weight * vote;
+        total_weight += config.disc_weight;
+    }
+    let combined = if total_weight > 0.0 { weighted / total_weight } else { 0.0 };
+
+    let field_factor = (field_growth / config.field_growth_scale.max(f32::EPSILON)).clamp(0.0, 1.0);
+    let confidence = (combined.abs() * (0.5 + 0.5 * field_factor)).clamp(0.0, 1.0);
+
+    let side = if confidence < config.min_confidence {
+        if zero_count_side == PullSide::Tie {
+            PullSide::Tie
+        } else {
+            PullSide::Unknown
+        }
+    } else if combined > 0.0 {
+        PullSide::Left
+    } else {
+        PullSide::Right
+    };
+
+    PullSideEstimate {
+        side,
+        confidence,
+        evidence: PullSideEvidence {
+            left_slope,
+            right_slope,
+            slope_vote,
+            com_displacement,
+            com_vote,
+            zero_count_side,
+            field_growth,
+            disc_vote,
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,4 +373,167 @@ mod tests {
         ]);
         assert_eq!(detect_pull_side(&h, 2), PullSide::Unknown);
     }
+
+    /// Builds a trace from raw per-frame player counts (team of 7) plus CoM x positions.
+    fn trace(rows: &[(u32, u32, u32, Option<f32>)]) -> Vec<PullSideSample> {
+        let mut prev_com: Option<f32> = None;
+        rows.iter()
+            .enumerate()
+            .map(|(i, &(l, r, f, com))| {
+                let com_delta_x = match (com, prev_com) {
+                    (Some(c), Some(p)) => Some(c - p),
+                    _ => None,
+                };
+                prev_com = com;
+                PullSideSample {
+                    frame_index: 100 + i,
+                    occupancy: EndZoneOccupancy {
+                        left: l as f32 / 7.0,
+                        right: r as f32 / 7.0,
+                        field: f as f32 / 7.0,
+                    },
+                    com_delta_x,
+                    disc_from: None,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn crowded_end_zone_left_pull() {
+        // Two sideline bystanders stand behind the left back line the whole game,
+        // so the left count never drops below 2. Pullers sprint right, receivers hold.
+        let h = trace(&[
+            (9, 7, 0, Some(0.50)),
+            (9, 7, 0, Some(0.50)),
+            (9, 7, 1, Some(0.50)),
+            (9, 7, 0, Some(0.50)), // cliff at frame 103
+            (7, 7, 2, Some(0.53)),
+            (5, 7, 4, Some(0.56)),
+            (4, 6, 5, Some(0.60)),
+            (3, 6, 6, Some(0.63)),
+            (2, 6, 7, Some(0.66)),
+            (2, 5, 8, Some(0.69)),
+            (2, 5, 8, Some(0.71)),
+        ]);
+        assert_eq!(
+            detect_pull_side(
+                &h.iter().map(|s| (s.frame_index, s.occupancy.clone())).collect::<Vec<_>>(),
+                2
+            ),
+            PullSide::Unknown,
+            "legacy detector should miss this point"
+        );
+
+        let est = estimate_pull_side(&h, 103, &PullSideConfig::default());
+        assert_eq!(est.side, PullSide::Left, "{:?}", est);
+        assert!(est.confidence > 0.5, "confidence: {}", est.confidence);
+        assert!(est.evidence.left_slope < est.evidence.right_slope);
+        assert!(est.evidence.field_growth > 0.0);
+    }
+
+    #[test]
+    fn crowded_end_zone_right_pull() {
+        // Mirror image: a coach paces behind the right end zone.
+        let h = trace(&[
+            (7, 8, 0, Some(0.50)),
+            (7, 8, 0, Some(0.50)),
+            (7, 8, 0, Some(0.50)),
+            (7, 8, 0, Some(0.50)), // cliff at frame 103
+            (7, 6, 2, Some(0.47)),
+            (6, 4, 4, Some(0.44)),
+            (6, 3, 5, Some(0.41)),
+            (6, 2, 6, Some(0.38)),
+            (5, 1, 7, Some(0.35)),
+            (5, 1, 8, Some(0.33)),
+        ]);
+        let est = estimate_pull_side(&h, 103, &PullSideConfig::default());
+        assert_eq!(est.side, PullSide::Right, "{:?}", est);
+        assert!(est.confidence > 0.5, "confidence: {}", est.confidence);
+    }
+
+    #[test]
+    fn clean_emptying_agrees_with_zero_crossing() {
+        let h = trace(&[
+            (7, 7, 0, Some(0.50)),
+            (7, 7, 0, Some(0.50)), // cliff at frame 101
+            (3, 7, 4, Some(0.58)),
+            (0, 6, 7, Some(0.66)),
+            (0, 5, 8, Some(0.70)),
+            (0, 4, 9, Some(0.72)),
+        ]);
+        let est = estimate_pull_side(&h, 101, &PullSideConfig::default());
+        assert_eq!(est.evidence.zero_count_side, PullSide::Left);
+        assert_eq!(est.side, PullSide::Left);
+    }
+
+    #[test]
+    fn static_huddle_is_unknown() {
+        // Both lines stay put and nobody enters the field: likely a false-positive cliff.
+        let h = trace(&[
+            (6, 6, 1, Some(0.50)),
+            (6, 6, 1, Some(0.50)),
+            (6, 6, 1, Some(0.50)), // cliff at frame 102
+            (6, 6, 1, Some(0.50)),
+            (6, 6, 1, Some(0.50)),
+            (6, 6, 1, Some(0.50)),
+        ]);
+        let est = estimate_pull_side(&h, 102, &PullSideConfig::default());
+        assert_eq!(est.side, PullSide::Unknown);
+        assert!(est.confidence < 0.2, "confidence: {}", est.confidence);
+    }
+
+    #[test]
+    fn missing_com_still_uses_slopes() {
+        let h = trace(&[
+            (8, 7, 0, None),
+            (8, 7, 0, None), // cliff at frame 101
+            (6, 7, 2, None),
+            (4, 7, 4, None),
+            (2, 7, 6, None),
+            (2, 6, 7, None),
+        ]);
+        let est = estimate_pull_side(&h, 101, &PullSideConfig::default());
+        assert_eq!(est.evidence.com_displacement, None);
+        assert_eq!(est.side, PullSide::Left);
+    }
+
+    #[test]
+    fn disc_flight_decides_symmetric_emptying() {
+        // Both lines leave at the same pace, so only the disc tells the pullers apart
+        let mut h = trace(&[
+            (7, 7, 0, Some(0.50)),
+            (7, 7, 0, Some(0.50)), // cliff at frame 101
+            (6, 6, 2, Some(0.50)),
+            (5, 5, 4, Some(0.50)),
+            (4, 4, 6, Some(0.50)),
+            (3, 3, 8, Some(0.50)),
+        ]);
+        assert_eq!(estimate_pull_side(&h, 101, &PullSideConfig::default()).side, PullSide::Unknown);
+
+        for sample in &mut h[2..5] {
+            sample.disc_from = Some(PullSide::Right);
+        }
+        let est = estimate_pull_side(&h, 101, &PullSideConfig::default());
+        assert_eq!(est.evidence.disc_vote, Some(-1.0));
+        assert_eq!(est.side, PullSide::Right, "{:?}", est);
+    }
+
+    #[test]
+    fn disc_before_cliff_is_ignored() {
+        // The last throw of the previous point flies out of the left end zone
+        let mut h = trace(&[
+            (7, 7, 0, Some(0.50)),
+            (7, 7, 0, Some(0.50)), // cliff at frame 101
+            (6, 6, 2, Some(0.50)),
+            (5, 5, 4, Some(0.50)),
+            (4, 4, 6, Some(0.50)),
+            (3, 3, 8, Some(0.50)),
+        ]);
+        h[0].disc_from = Some(PullSide::Left);
+        h[3].disc_from = Some(PullSide::Right);
+        let est = estimate_pull_side(&h, 101, &PullSideConfig::default());
+        assert_eq!(est.evidence.disc_vote, Some(-1.0));
+        assert_eq!(est.side, PullSide::Right, "{:?}", est);
+    }
 }
@@ -0,0 +1,312 @@
+/// Axis-aligned bounding box in pixel or normalized coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Aabb {
+    /// Box from its top-left corner and size.
+    pub fn from_xywh(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { min_x: x, min_y: y, max_x: x + w, max_y: y + h }
+    }
+
+    /// Smallest box containing all points; `None` for an empty slice.
+    pub fn from_points(points: &[(f32, f32)]) -> Option<Self> {
+        let (&(x0, y0), rest) = points.split_first()?;
+        let mut bounds = Self { min_x: x0, min_y: y0, max_x: x0, max_y: y0 };
+        for &(x, y) in rest {
+            bounds.min_x = bounds.min_x.min(x);
+            bounds.min_y = bounds.min_y.min(y);
+            bounds.max_x = bounds.max_x.max(x);
+            bounds.max_y = bounds.max_y.max(y);
+        }
+        Some(bounds)
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+
+    /// True if the boxes share any point, edges included.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+}
+
+/// Polygon prepared once for many point-in-polygon tests.
+///
+/// Keeps the vertices and bounding box so each query is a bounding-box reject
+/// followed by a crossing-number test, with no allocation. Points on the
+/// boundary are outside, matching `geo::Contains` for polygons.
+#[derive(Debug, Clone)]
+pub struct PreparedPolygon {
+    vertices: Vec<(f64, f64)>,
+    bounds: Option<Aabb>,
+}
+
+impl PreparedPolygon {
+    /// Vertices in order; the ring is closed implicitly. Fewer than three
+    /// vertices give a polygon that contains nothing.
+    pub fn new(vertices: &[(f32, f32)]) -> Self {
+        let bounds = if vertices.len() >= 3 { Aabb::from_points(vertices) } else { None };
+        Self {
+            vertices: vertices.iter().map(|&(x, y)| (x as f64, y as f64)).collect(),
+            bounds,
+        }
+    }
+
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    /// True if the point lies strictly inside the polygon.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        // Points on or outside the bounding box can only be on the boundary or outside
+        if !(x > bounds.min_x && x < bounds.max_x && y > bounds.min_y && y < bounds.max_y) {
+            return false;
+        }
+
+        let (px, py) = (x as f64, y as f64);
+        let mut inside = false;
+        let mut prev = self.vertices[self.vertices.len() - 1];
+        for &curr in &self.vertices {
+            let (ax, ay) = prev;
+            let (bx, by) = curr;
+            prev = curr;
+
+            // On an edge: collinear and within the segment's extent
+            let cross = (bx - ax) * (py - ay) - (by - ay) * (px - ax);
+            if cross == 0.0
+                && px >= ax.min(bx)
+                && px <= ax.max(bx)
+                && py >= ay.min(by)
+                && py <= ay.max(by)
+            {
+                return false;
+            }
+
+            if (ay > py) != (by > py) && px < (bx - ax) * (py - ay) / (by - ay) + ax {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+/// Uniform grid over boxes for finding the ones that may overlap.
+///
+/// Every box is registered in each cell it touches, so two boxes that share a
+/// point always share a cell. Queries return a superset of the overlapping
+/// boxes; callers still compute the exact overlap.
+#[derive(Debug, Clone)]
+pub struct BoxGrid {
+    cols: usize,
+    /// Box indices grouped by cell; cell `c` owns `entries[starts[c]..starts[c + 1]]`
+    starts: Vec<u32>,
+    entries: Vec<u32>,
+    /// Inclusive (first column, last column, first row, last row) of each box
+    spans: Vec<(usize, usize, usize, usize)>,
+}
+
+impl BoxGrid {
+    /// Builds the grid with cells about the size of an average box, coarsened
+    /// so there are at most a few cells per box.
+    pub fn new(boxes: &[Aabb]) -> Self {
+        let finite = |b: &&Aabb| {
+            b.min_x.is_finite() && b.min_y.is_finite() && b.max_x.is_finite() && b.max_y.is_finite()
+        };
+
+        let mut count = 0usize;
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        let mut size_sum = 0.0f32;
+        for b in boxes.iter().filter(finite) {
+            count += 1;
+            min_x = min_x.min(b.min_x);
+            min_y = min_y.min(b.min_y);
+            max_x = max_x.max(b.max_x);
+            max_y = max_y.max(b.max_y);
+            size_sum += (b.width() + b.height()) / 2.0;
+        }
+        let (origin_x, origin_y, extent_x, extent_y, mean_size) = if count == 0 {
+            (0.0, 0.0, 0.0, 0.0, 1.0)
+        } else {
+            (min_x, min_y, max_x - min_x, max_y - min_y, size_sum / count as f32)
+        };
+
+        let max_cells = (boxes.len() * 4).max(16) as f32;
+        let mut cell_size = mean_size.max(1.0);
+        while cell_size.is_finite()
+            && (extent_x / cell_size + 1.0) * (extent_y / cell_size + 1.0) > max_cells
+        {
+            cell_size *= 2.0;
+        }
+        let cols = (extent_x / cell_size) as usize + 1;
+        let rows = (extent_y / cell_size) as usize + 1;
+
+        // Non-finite coordinates clamp into the grid; such boxes never overlap anything anyway
+        let index = |v: f32, origin: f32, count: usize| {
+            (((v - origin) / cell_size).floor().max(0.0) as usize).min(count - 1)
+        };
+        let spans: Vec<(usize, usize, usize, usize)> = boxes
+            .iter()
+            .map(|b| {
+                (
+                    index(b.min_x, origin_x, cols),
+                    index(b.max_x, origin_x, cols),
+                    index(b.min_y, origin_y, rows),
+                    index(b.max_y, origin_y, rows),
+                )
+            })
+            .collect();
+
+        // Counting sort of (cell, box) pairs into one flat array
+        let mut starts = vec![0u32; cols * rows + 1];
+        for &(col0, col1, row0, row1) in &spans {
+            for row in row0..=row1 {
+                for col in col0..=col1 {
+                    starts[row * cols + col + 1] += 1;
+                }
+            }
+        }
+        for c in 1..starts.len() {
+            starts[c] += starts[c - 1];
+        }
+        let mut fill = starts.clone();
+        let mut entries = vec![0u32; starts[starts.len() - 1] as usize];
+        for (i, &(col0, col1, row0, row1)) in spans.iter().enumerate() {
+            for row in row0..=row1 {
+                for col in col0..=col1 {
+                    let slot = &mut fill[row * cols + col];
+                    entries[*slot as usize] = i as u32;
+                    *slot += 1;
+                }
+            }
+        }
+
+        Self { cols, starts, entries, spans }
+    }
+
+    /// Indices of the other boxes sharing a cell with box `i`, sorted ascending.
+    /// Clears `out` first so the buffer can be reused across queries.
+    pub fn neighbours(&self, i: usize, out: &mut Vec<usize>) {
+        out.clear();
+        let (col0, col1, row0, row1) = self.spans[i];
+        for row in row0..=row1 {
+            for col in col0..=col1 {
+                let cell = row * self.cols + col;
+                let members = &self.entries[self.starts[cell] as usize..self.starts[cell + 1] as usize];
+                out.extend(members.iter().map(|&j| j as usize).filter(|&j| j != i));
+            }
+        }
+        // Boxes spanning several cells are only repeated when this box does too
+        if col0 != col1 || row0 != row1 {
+            out.sort_unstable();
+            out.dedup();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::Contains;
+
+    /// Deterministic pseudo-random values in [0, 1).
+    fn lcg(seed: u64) -> impl FnMut() -> f32 {
+        let mut state = seed;
+        move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32) / (1u64 << 31) as f32
+        }
+    }
+
+    #[test]
+    fn prepared_polygon_matches_geo() {
+        // Concave end-zone-like shape with a notch, plus a convex quad
+        let shapes: [&[(f32, f32)]; 2] = [
+            &[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (5.0, 4.0), (0.0, 10.0)],
+            &[(0.12, 0.31), (0.34, 0.28), (0.37, 0.72), (0.09, 0.69)],
+        ];
+        for shape in shapes {
+            let prepared = PreparedPolygon::new(shape);
+            let coords: Vec<(f64, f64)> = shape.iter().map(|&(x, y)| (x as f64, y as f64)).collect();
+            let poly = geo::Polygon::new(geo::LineString::from(coords), vec![]);
+            let bounds = prepared.bounds().unwrap();
+
+            let mut next = lcg(11);
+            for _ in 0..5000 {
+                let x = bounds.min_x - 0.1 * bounds.width() + 1.2 * bounds.width() * next();
+                let y = bounds.min_y - 0.1 * bounds.height() + 1.2 * bounds.height() * next();
+                let expected = poly.contains(&geo::Point::new(x as f64, y as f64));
+                assert_eq!(prepared.contains(x, y), expected, "({}, {})", x, y);
+            }
+            // Vertices are on the boundary
+            for &(x, y) in shape {
+                assert!(!prepared.contains(x, y));
+            }
+        }
+
+        // Exactly representable points on axis-aligned edges
+        let square = PreparedPolygon::new(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        for (x, y) in [(2.0, 0.0), (4.0, 2.0), (2.0, 4.0), (0.0, 1.5)] {
+            assert!(!square.contains(x, y));
+        }
+        assert!(square.contains(2.0, 2.0));
+    }
+
+    #[test]
+    fn degenerate_polygon_contains_nothing() {
+        assert!(!PreparedPolygon::new(&[]).contains(0.0, 0.0));
+        assert!(!PreparedPolygon::new(&[(0.0, 0.0), (1.0, 1.0)]).contains(0.5, 0.5));
+    }
+
+    #[test]
+    fn grid_finds_every_overlapping_pair() {
+        let mut next = lcg(3);
+        let boxes: Vec<Aabb> = (0..300)
+            .map(|_| {
+                let w = 10.0 + 40.0 * next();
+                Aabb::from_xywh(1920.0 * next(), 1080.0 * next(), w, 2.0 * w)
+            })
+            .collect();
+        let grid = BoxGrid::new(&boxes);
+
+        let mut found = Vec::new();
+        for i in 0..boxes.len() {
+            grid.neighbours(i, &mut found);
+            assert!(found.windows(2).all(|w| w[0] < w[1]));
+            for j in 0..boxes.len() {
+                if i != j && boxes[i].intersects(&boxes[j]) {
+                    assert!(found.binary_search(&j).is_ok(), "{} and {} overlap", i, j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn grid_handles_empty_and_degenerate_input() {
+        let grid = BoxGrid::new(&[]);
+        assert!(grid.spans.is_empty());
+
+        let boxes = [Aabb::from_xywh(5.0, 5.0, 0.0, 0.0), Aabb::from_xywh(f32::NAN, 0.0, 1.0, 1.0)];
+        let grid = BoxGrid::new(&boxes);
+        let mut found = Vec::new();
+        grid.neighbours(0, &mut found);
+        assert!(!found.contains(&0));
+    }
+}
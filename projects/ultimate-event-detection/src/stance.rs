@@ -0,0 +1,282 @@
+// COCO-17 keypoint indices
+const LEFT_SHOULDER: usize = 5;
+const RIGHT_SHOULDER: usize = 6;
+const LEFT_WRIST: usize = 9;
+const RIGHT_WRIST: usize = 10;
+const LEFT_HIP: usize = 11;
+const RIGHT_HIP: usize = 12;
+const LEFT_ANKLE: usize = 15;
+const RIGHT_ANKLE: usize = 16;
+
+/// One person's COCO-17 keypoints in image coordinates (y down), `None` where the
+/// pose model was not confident.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Pose {
+    pub keypoints: [Option<(f32, f32)>; 17],
+}
+
+/// Configuration for the stance features.
+#[derive(Clone, Debug)]
+pub struct StanceConfig {
+    /// Largest torso lean from vertical, in radians, for an upright stance.
+    pub max_lean: f32,
+    /// Largest shoulder width relative to torso length for a side-on stance. Players
+    /// lined up on the goal line face the other end zone, side-on to a sideline camera.
+    pub max_shoulder_ratio: f32,
+    /// Ready players needed for a full line.
+    pub min_line_players: usize,
+    /// Spread of ground points across their fitted line, in torso lengths, at which
+    /// the players no longer count as a line.
+    pub max_line_spread: f32,
+    /// Largest gap between neighbours along the fitted line, in torso lengths, for
+    /// the players to still count as one line.
+    pub max_line_gap: f32,
+    /// Wrist-to-shoulder distance, in torso lengths, for an extended arm.
+    pub min_arm_extension: f32,
+    /// Largest wrist drop below its shoulder, in torso lengths, for a throwing arm.
+    pub max_wrist_drop: f32,
+    /// How far a full line-up on both sides lifts the pre-point score towards 1.
+    pub lined_up_weight: f32,
+    /// Fraction of the pre-point score removed on frames with a throw.
+    pub throwing_weight: f32,
+}
+
+impl Default for StanceConfig {
+    fn default() -> Self {
+        Self {
+            max_lean: 0.35,
+            max_shoulder_ratio: 0.45,
+            min_line_players: 3,
+            max_line_spread: 2.0,
+            max_line_gap: 20.0,
+            min_arm_extension: 0.9,
+            max_wrist_drop: 0.35,
+            lined_up_weight: 0.3,
+            throwing_weight: 0.5,
+        }
+    }
+}
+
+/// Pose features of one end zone in one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PoseFeatures {
+    /// In [0, 1]: share of people in a ready stance, scaled by how well they form a line.
+    pub lined_up: f32,
+    /// Someone's arm is extended at shoulder height, as in a pull.
+    pub throwing: bool,
+}
+
+/// Summarizes the poses found in one end-zone crop.
+pub fn pose_features(poses: &[Pose], config: &StanceConfig) -> PoseFeatures {
+    let mut measured = 0;
+    let mut torsos = Vec::new();
+    let mut ground = Vec::new();
+    let mut throwing = false;
+
+    for pose in poses {
+        let shoulder = pose.midpoint(LEFT_SHOULDER, RIGHT_SHOULDER);
+        let (shoulder, hip) = match (shoulder, pose.midpoint(LEFT_HIP, RIGHT_HIP)) {
+            (Some(shoulder), Some(hip)) => (shoulder, hip),
+            _ => continue,
+        };
+        let torso = distance(shoulder, hip);
+        if torso <= 0.0 {
+            continue;
+        }
+        measured += 1;
+        throwing |= pose.is_throwing(torso, config);
+
+        if pose.is_ready(shoulder, hip, torso, config) {
+            torsos.push(torso);
+            if let Some(feet) = pose.midpoint(LEFT_ANKLE, RIGHT_ANKLE) {
+                ground.push(feet);
+            }
+        }
+    }
+
+    if measured == 0 || torsos.is_empty() {
+        return PoseFeatures { lined_up: 0.0, throwing };
+    }
+    let ready = torsos.len();
+    let share = ready as f32 / measured as f32;
+    let coverage = (ready as f32 / config.min_line_players.max(1) as f32).min(1.0);
+
+    torsos.sort_by(|a, b| a.total_cmp(b));
+    let torso = torsos[ready / 2];
+    let (spread, gap) = line_fit(&ground);
+    let straightness = if gap / torso > config.max_line_gap {
+        0.0
+    } else {
+        (1.0 - spread / torso / config.max_line_spread).clamp(0.0, 1.0)
+    };
+
+    PoseFeatures { lined_up: share * coverage * straightness, throwing }
+}
+
+/// Folds both end zones' pose features into a pre-point score: teams lined up on
+/// both goal lines lift it, and a throw cuts it so the cliff lands on the pull.
+pub fn stance_adjusted_score(score: f32, left: &PoseFeatures, right: &PoseFeatures, config: &StanceConfig) -> f32 {
+    let lined_up = left.lined_up.min(right.lined_up);
+    let mut score = score + config.lined_up_weight * lined_up * (1.0 - score);
+    if left.throwing || right.throwing {
+        score *= 1.0 - config.throwing_weight;
+    }
+    score.clamp(0.0, 1.0)
+}
+
+impl Pose {
+    /// Midpoint of a left/right keypoint pair, or whichever of the two is visible.
+    fn midpoint(&self, left: usize, right: usize) -> Option<(f32, f32)> {
+        match (self.keypoints[left], self.keypoints[right]) {
+            (Some(a), Some(b)) => Some(((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn is_ready(&self, shoulder: (f32, f32), hip: (f32, f32), torso: f32, config: &StanceConfig) -> bool {
+        let (dx, dy) = (hip.0 - shoulder.0, hip.1 - shoulder.1);
+        if dy <= 0.0 || dx.abs().atan2(dy) > config.max_lean {
+            return false;
+        }
+        // A hidden shoulder means the far side of a side-on body
+        match (self.keypoints[LEFT_SHOULDER], self.keypoints[RIGHT_SHOULDER]) {
+            (Some(l), Some(r)) => (l.0 - r.0).abs() / torso <= config.max_shoulder_ratio,
+            _ => true,
+        }
+    }
+
+    fn is_throwing(&self, torso: f32, config: &StanceConfig) -> bool {
+        [(LEFT_SHOULDER, LEFT_WRIST), (RIGHT_SHOULDER, RIGHT_WRIST)].iter().any(|&(s, w)| {
+            match (self.keypoints[s], self.keypoints[w]) {
+                (Some(shoulder), Some(wrist)) => {
+                    distance(shoulder, wrist) / torso >= config.min_arm_extension
+                        && (wrist.1 - shoulder.1) / torso <= config.max_wrist_drop
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Standard deviation of points across their best-fit line, whatever its direction,
+/// and the largest gap between neighbours along it.
+fn line_fit(points: &[(f32, f32)]) -> (f32, f32) {
+    if points.len() < 2 {
+        return (0.0, 0.0);
+    }
+    let n = points.len() as f32;
+    let mx = points.iter().map(|p| p.0).sum::<f32>() / n;
+    let my = points.iter().map(|p| p.1).sum::<f32>() / n;
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for p in points {
+        let (dx, dy) = (p.0 - mx, p.1 - my);
+        sxx += dx * dx;
+        syy += dy * dy;
+        sxy += dx * dy;
+    }
+    let (sxx, syy, sxy) = (sxx / n, syy / n, sxy / n);
+    // Smaller eigenvalue of the covariance matrix
+    let half_diff = (sxx - syy) / 2.0;
+    let minor = (sxx + syy) / 2.0 - (half_diff * half_diff + sxy * sxy).sqrt();
+    let spread = if points.len() < 3 { 0.0 } else { minor.max(0.0).sqrt() };
+
+    // Positions along the major axis
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (sin, cos) = angle.sin_cos();
+    let mut along: Vec<f32> = points.iter().map(|p| (p.0 - mx) * cos + (p.1 - my) * sin).collect();
+    along.sort_by(f32::total_cmp);
+    let gap = along.windows(2).map(|w| w[1] - w[0]).fold(0.0, f32::max);
+    (spread, gap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A standing person `height` pixels tall with feet at (x, ground); `side_on`
+    /// narrows the shoulders, `wrist` overrides the right wrist position.
+    fn person(x: f32, ground: f32, height: f32, side_on: bool, wrist: Option<(f32, f32)>) -> Pose {
+        let half_shoulders = if side_on { 0.02 } else { 0.12 } * height;
+        let shoulder_y = ground - 0.8 * height;
+        let mut pose = Pose::default();
+        pose.keypoints[LEFT_SHOULDER] = Some((x - half_shoulders, shoulder_y));
+        pose.keypoints[RIGHT_SHOULDER] = Some((x + half_shoulders, shoulder_y));
+        pose.keypoints[LEFT_HIP] = Some((x - 0.05 * height, ground - 0.5 * height));
+        pose.keypoints[RIGHT_HIP] = Some((x + 0.05 * height, ground - 0.5 * height));
+        pose.keypoints[LEFT_ANKLE] = Some((x - 0.05 * height, ground));
+        pose.keypoints[RIGHT_ANKLE] = Some((x + 0.05 * height, ground));
+        // Arms hanging by default
+        pose.keypoints[LEFT_WRIST] = Some((x - half_shoulders, shoulder_y + 0.35 * height));
+        pose.keypoints[RIGHT_WRIST] = Some(wrist.unwrap_or((x + half_shoulders, shoulder_y + 0.35 * height)));
+        pose
+    }
+
+    #[test]
+    fn goal_line_scores_above_scattered_crowd() {
+        let config = StanceConfig::default();
+        // Seven side-on players along a diagonal goal line
+        let on_line = |i: usize, side_on: bool| {
+            person(100.0 + 40.0 * i as f32, 300.0 + 30.0 * i as f32, 80.0, side_on, None)
+        };
+        let line: Vec<Pose> = (0..7).map(|i| on_line(i, true)).collect();
+        let features = pose_features(&line, &config);
+        assert!(features.lined_up > 0.95, "{:?}", features);
+        assert!(!features.throwing);
+
+        // The same players facing the camera, or side-on but scattered
+        let facing: Vec<Pose> = (0..7).map(|i| on_line(i, false)).collect();
+        assert_eq!(pose_features(&facing, &config).lined_up, 0.0);
+        let scattered: Vec<Pose> = [(100.0, 300.0), (400.0, 320.0), (180.0, 520.0), (350.0, 450.0), (120.0, 420.0)]
+            .iter()
+            .map(|&(x, y)| person(x, y, 80.0, true, None))
+            .collect();
+        assert!(pose_features(&scattered, &config).lined_up < 0.5);
+
+        // Two groups far apart along the same line are not one line-up
+        let split: Vec<Pose> = [100.0, 140.0, 180.0, 800.0, 840.0, 880.0]
+            .iter()
+            .map(|&x| person(x, 300.0, 80.0, true, None))
+            .collect();
+        assert_eq!(pose_features(&split, &config).lined_up, 0.0);
+        assert!(pose_features(&split[..3], &config).lined_up > 0.95);
+
+        // Two ready players are only part of a line
+        let pair = pose_features(&line[..2], &config).lined_up;
+        assert!(pair > 0.5 && pair < 0.7, "{}", pair);
+        assert_eq!(pose_features(&[], &config), PoseFeatures::default());
+    }
+
+    #[test]
+    fn arm_extended_at_shoulder_height_is_a_throw() {
+        let config = StanceConfig::default();
+        let shoulder_y = 300.0 - 0.8 * 80.0;
+        let thrower = person(100.0, 300.0, 80.0, true, Some((140.0, shoulder_y + 4.0)));
+        assert!(pose_features(&[thrower], &config).throwing);
+
+        // Hanging arms and a half-raised hand are not throws
+        let standing = person(100.0, 300.0, 80.0, true, None);
+        let waving = person(100.0, 300.0, 80.0, true, Some((104.0, shoulder_y - 10.0)));
+        assert!(!pose_features(&[standing, waving], &config).throwing);
+    }
+
+    #[test]
+    fn adjusted_score_rises_with_line_ups_and_drops_on_throws() {
+        let config = StanceConfig::default();
+        let lined = PoseFeatures { lined_up: 1.0, throwing: false };
+        let empty = PoseFeatures::default();
+
+        assert_eq!(stance_adjusted_score(0.6, &empty, &empty, &config), 0.6);
+        // Both sides must be lined up
+        assert_eq!(stance_adjusted_score(0.6, &lined, &empty, &config), 0.6);
+        let boosted = stance_adjusted_score(0.6, &lined, &lined, &config);
+        assert!((boosted - 0.72).abs() < 1e-6, "{}", boosted);
+
+        let pull = PoseFeatures { lined_up: 1.0, throwing: true };
+        let thrown = stance_adjusted_score(0.6, &pull, &lined, &config);
+        assert!((thrown - 0.36).abs() < 1e-6, "{}", thrown);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::format::GameFormat;
+
+/// Raw (un-normalized) player counts for a single frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawCounts {
+    pub left: f32,
+    pub right: f32,
+    pub field: f32,
+}
+
+/// Configuration for team-size estimation.
+#[derive(Clone, Debug)]
+pub struct TeamSizeConfig {
+    /// Minimum players in each end zone for a frame to look like a line-up.
+    pub min_side_count: f32,
+    /// Maximum players on the field during a line-up.
+    pub max_field_count: f32,
+    /// Maximum per-side count change between consecutive frames of a plateau.
+    pub max_step: f32,
+    /// Minimum consecutive line-up frames for a plateau to be used.
+    pub min_plateau_frames: usize,
+    /// Percentile of per-plateau side counts taken as the full line-up count.
+    /// Above the median because occlusion only ever hides players.
+    pub percentile: f32,
+    /// Number of plateaus at which the sample-size term of the confidence saturates.
+    pub saturation_plateaus: usize,
+}
+
+impl Default for TeamSizeConfig {
+    fn default() -> Self {
+        Self {
+            min_side_count: 2.0,
+            max_field_count: 2.0,
+            max_step: 1.0,
+            min_plateau_frames: 8,
+            percentile: 0.8,
+            saturation_plateaus: 6,
+        }
+    }
+}
+
+/// Proposed team size with a confidence in `[0, 1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamSizeEstimate {
+    pub team_size: u32,
+    pub confidence: f32,
+    /// Number of pre-point plateaus the estimate is based on.
+    pub plateaus: usize,
+    /// Percentile per-side count across plateaus, before snapping to a team size.
+    pub observed_count: f32,
+}
+
+impl TeamSizeEstimate {
+    /// Game format implied by the estimated team size.
+    pub fn game_format(&self) -> GameFormat {
+        GameFormat::from_team_size(self.team_size)
+    }
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_by(f32::total_cmp);
+    values[values.len() / 2]
+}
+
+/// Team sizes of the known formats, largest first.
+fn candidates() -> Vec<u32> {
+    let mut sizes: Vec<u32> = GameFormat::ALL.iter().map(|f| f.team_size()).collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+/// Nearest candidate to `count`; ties go to the larger size since occlusion undercounts.
+fn nearest(count: f32, sizes: &[u32]) -> u32 {
+    let mut best = sizes[0];
+    for &size in sizes {
+        if (count - size as f32).abs() < (count - best as f32).abs() {
+            best = size;
+        }
+    }
+    best
+}
+
+/// Estimate players per team from raw per-frame counts.
+///
+/// Finds pre-point plateaus (both end zones occupied, field nearly empty,
+/// counts steady), takes the median count of each side per plateau, and snaps
+/// a high percentile of those to the nearest known team size. Returns `None`
+/// when no plateau is long enough.
+pub fn estimate_team_size(counts: &[RawCounts], config: &TeamSizeConfig) -> Option<TeamSizeEstimate> {
+    // Non-finite counts (e.g. from a broken calibration) never belong to a line-up
+    let is_line_up = |c: &RawCounts| {
+        c.left.is_finite()
+            && c.right.is_finite()
+            && c.left >= config.min_side_count
+            && c.right >= config.min_side_count
+            && c.field <= config.max_field_count
+    };
+
+    // Split into runs of steady line-up frames
+    let mut plateaus: Vec<&[RawCounts]> = Vec::new();
+    let mut start: Option<usize> = None;
+    for i in 0..=counts.len() {
+        let continues = i < counts.len()
+            && is_line_up(&counts[i])
+            && start.is_none_or(|_| {
+                let prev = &counts[i - 1];
+                (counts[i].left - prev.left).abs() <= config.max_step
+                    && (counts[i].right - prev.right).abs() <= config.max_step
+            });
+
+        if continues {
+            if start.is_none() {
+                start = Some(i);
+            }
+            continue;
+        }
+
+        if let Some(s) = start.take() {
+            if i - s >= config.min_plateau_frames {
+                plateaus.push(&counts[s..i]);
+            }
+        }
+        // A frame that broke a steady run may still open the next one
+        if i < counts.len() && is_line_up(&counts[i]) {
+            start = Some(i);
+        }
+    }
+
+    if plateaus.is_empty() {
+        return None;
+    }
+
+    let mut samples: Vec<f32> = Vec::with_capacity(plateaus.len() * 2);
+    for plateau in &plateaus {
+        let mut left: Vec<f32> = plateau.iter().map(|c| c.left).collect();
+        let mut right: Vec<f32> = plateau.iter().map(|c| c.right).collect();
+        samples.push(median(&mut left));
+        samples.push(median(&mut right));
+    }
+    samples.sort_by(f32::total_cmp);
+
+    let rank = ((samples.len() - 1) as f32 * config.percentile.clamp(0.0, 1.0)).round() as usize;
+    let observed_count = samples[rank];
+
+    let sizes = candidates();
+    let team_size = nearest(observed_count, &sizes);
+
+    // Share of plateau sides that agree with the chosen size, tolerating one occluded player
+    let agreeing = samples
+        .iter()
+        .filter(|&&s| {
+            nearest(s, &sizes) == team_size || (s < team_size as f32 && team_size as f32 - s <= 1.0)
+        })
+        .count();
+    let agreement = agreeing as f32 / samples.len() as f32;
+
+    // How cleanly the observed count separates from the runner-up size
+    let d = (observed_count - team_size as f32).abs();
+    let d2 = sizes
+        .iter()
+        .filter(|&&s| s != team_size)
+        .map(|&s| (observed_count - s as f32).abs())
+        .fold(f32::INFINITY, f32::min);
+    let separation = if d + d2 > 0.0 { (d2 - d) / (d2 + d) } else { 0.0 };
+
+    let sample_factor =
+        (plateaus.len() as f32 / config.saturation_plateaus.max(1) as f32).min(1.0);
+
+    let confidence = (agreement * (0.5 + 0.5 * separation) * sample_factor).clamp(0.0, 1.0);
+
+    Some(TeamSizeEstimate {
+        team_size,
+        confidence,
+        plateaus: plateaus.len(),
+        observed_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a game trace: `points` pre-point plateaus of `line_up` players per
+    /// side, separated by live play, with every `occlude_every`-th frame losing a player.
+    fn game(line_up: f32, points: usize, occlude_every: usize) -> Vec<RawCounts> {
+        let mut counts = Vec::new();
+        for _ in 0..points {
+            for i in 0..20 {
+                let hidden = if occlude_every > 0 && i % occlude_every == 0 { 1.0 } else { 0.0 };
+                counts.push(RawCounts { left: line_up - hidden, right: line_up, field: 0.0 });
+            }
+            for _ in 0..30 {
+                counts.push(RawCounts { left: 1.0, right: 0.0, field: line_up * 2.0 - 1.0 });
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn sevens_game_estimates_seven() {
+        let estimate = estimate_team_size(&game(7.0, 8, 4), &TeamSizeConfig::default()).unwrap();
+        assert_eq!(estimate.team_size, 7);
+        assert_eq!(estimate.plateaus, 8);
+        assert!(estimate.confidence > 0.8, "confidence: {}", estimate.confidence);
+    }
+
+    #[test]
+    fn fours_game_estimates_four() {
+        let estimate = estimate_team_size(&game(4.0, 8, 5), &TeamSizeConfig::default()).unwrap();
+        assert_eq!(estimate.team_size, 4);
+        assert_eq!(estimate.game_format(), GameFormat::Fours);
+    }
+
+    #[test]
+    fn occluded_sevens_line_up_is_not_mistaken_for_fives() {
+        // Two players hidden on one side throughout: median 5 on the left, 7 on the right
+        let counts: Vec<RawCounts> = game(7.0, 6, 0)
+            .into_iter()
+            .map(|c| if c.field == 0.0 { RawCounts { left: c.left - 2.0, ..c } } else { c })
+            .collect();
+        let estimate = estimate_team_size(&counts, &TeamSizeConfig::default()).unwrap();
+        assert_eq!(estimate.team_size, 7);
+        assert!(estimate.confidence < 0.8, "confidence: {}", estimate.confidence);
+    }
+
+    #[test]
+    fn few_plateaus_lower_confidence() {
+        let config = TeamSizeConfig::default();
+        let many = estimate_team_size(&game(5.0, 8, 0), &config).unwrap();
+        let few = estimate_team_size(&game(5.0, 2, 0), &config).unwrap();
+        assert_eq!(few.team_size, 5);
+        assert!(few.confidence < many.confidence);
+    }
+
+    #[test]
+    fn non_finite_counts_are_skipped() {
+        let mut counts = game(7.0, 8, 0);
+        counts[5].left = f32::NAN;
+        counts[60].right = f32::INFINITY;
+        counts[110].field = f32::NAN;
+        let estimate = estimate_team_size(&counts, &TeamSizeConfig::default()).unwrap();
+        assert_eq!(estimate.team_size, 7);
+        assert!(estimate.observed_count.is_finite());
+
+        let counts = vec![RawCounts { left: f32::NAN, right: f32::NAN, field: 0.0 }; 100];
+        assert!(estimate_team_size(&counts, &TeamSizeConfig::default()).is_none());
+    }
+
+    #[test]
+    fn no_line_up_returns_none() {
+        let counts = vec![RawCounts { left: 1.0, right: 0.0, field: 10.0 }; 100];
+        assert!(estimate_team_size(&counts, &TeamSizeConfig::default()).is_none());
+    }
+}
@@ -0,0 +1,151 @@
+// WGSL port of metal/metal_detect.metal. Each invocation checks one frame index.
+// Keep in sync with the Metal shader and `is_cliff_at`.
+
+// Detector configuration packed into a struct
+struct DetectorParams {
+    min_drop: f32,
+    min_prepoint_duration: f32,
+    min_post_duration: f32,
+    max_post_proba: f32,
+    absolute_threshold: f32,
+    min_gap: f32,
+    smoothing_window: f32,
+    video_start_prepoint_threshold: f32,
+}
+
+@group(0) @binding(0) var<storage, read> scores: array<f32>;
+@group(0) @binding(1) var<uniform> params: DetectorParams;
+@group(0) @binding(2) var<storage, read_write> detected: array<u32>;
+
+// Median windows are held in fixed-size arrays; the host falls back to the CPU
+// for configs with longer windows.
+const MAX_WINDOW: u32 = 30u;
+
+// Compute moving average for smoothing
+fn compute_smoothed_at(i: u32, window: u32) -> f32 {
+    var start = 0u;
+    if (i >= window - 1u) {
+        start = i - window + 1u;
+    }
+    let end = i + 1u;
+
+    var sum = 0.0;
+    for (var j = start; j < end; j++) {
+        sum += scores[j];
+    }
+    return sum / f32(end - start);
+}
+
+// Sort a small array in-place (for median calculation)
+fn sort_small_array(arr: ptr<function, array<f32, 30>>, count: u32) {
+    for (var i = 0u; i < count; i++) {
+        for (var j = i + 1u; j < count; j++) {
+            if ((*arr)[j] < (*arr)[i]) {
+                let tmp = (*arr)[i];
+                (*arr)[i] = (*arr)[j];
+                (*arr)[j] = tmp;
+            }
+        }
+    }
+}
+
+fn pre_median(start_pre: u32, pre_len: u32, window: u32) -> f32 {
+    var values: array<f32, 30>;
+    let count = min(pre_len, MAX_WINDOW);
+    for (var j = 0u; j < count; j++) {
+        values[j] = compute_smoothed_at(start_pre + j, window);
+    }
+    sort_small_array(&values, count);
+    return values[count / 2u];
+}
+
+// Main kernel: check if frame at index i is a cliff
+@compute @workgroup_size(64)
+fn detect_cliffs(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let len = arrayLength(&scores);
+    let i = gid.x;
+    if (i >= len) {
+        return;
+    }
+
+    let min_pre = u32(params.min_prepoint_duration);
+    let min_post = u32(params.min_post_duration);
+    let window = u32(params.smoothing_window);
+
+    // Bounds check - only require enough post-context for early points
+    if (i + min_post >= len || i + 1u >= len) {
+        detected[i] = 0u;
+        return;
+    }
+
+    // Compute smoothed values at i and i+1
+    let smoothed_i = compute_smoothed_at(i, window);
+    let smoothed_i_next = compute_smoothed_at(i + 1u, window);
+
+    // Check drop
+    let drop = smoothed_i - smoothed_i_next;
+    var start_w = 0u;
+    if (i >= window - 1u) {
+        start_w = i - window + 1u;
+    }
+    let cumulative_drop = compute_smoothed_at(start_w, window) - smoothed_i_next;
+    let effective_drop = max(drop, cumulative_drop);
+
+    if (effective_drop < params.min_drop) {
+        detected[i] = 0u;
+        return;
+    }
+
+    if (smoothed_i_next > params.absolute_threshold) {
+        detected[i] = 0u;
+        return;
+    }
+
+    // Check pre-point plateau with support for very early points
+    var start_pre = 0u;
+    if (i >= min_pre) {
+        start_pre = i - min_pre;
+    }
+    let pre_len = i - start_pre;
+
+    if (pre_len > 0u) {
+        var threshold = params.video_start_prepoint_threshold;
+        if (pre_len >= min_pre) {
+            // Normal case: enough prepoint frames, use strict 0.5 threshold
+            threshold = 0.5;
+        }
+        if (pre_median(start_pre, pre_len, window) < threshold) {
+            detected[i] = 0u;
+            return;
+        }
+    }
+
+    // Check post-point stability (median of post window <= max_post_proba)
+    let post_start = i + 1u;
+    let post_end = min(post_start + min_post, len);
+    let post_len = post_end - post_start;
+
+    if (post_len < min_post) {
+        detected[i] = 0u;
+        return;
+    }
+
+    var post_values: array<f32, 30>;
+    let count = min(post_len, MAX_WINDOW);
+    for (var j = 0u; j < count; j++) {
+        post_values[j] = scores[post_start + j];
+    }
+    sort_small_array(&post_values, count);
+    // Empty post window: the CPU median of nothing is 0.0
+    var median_post = 0.0;
+    if (count > 0u) {
+        median_post = post_values[count / 2u];
+    }
+
+    if (median_post > params.max_post_proba) {
+        detected[i] = 0u;
+        return;
+    }
+
+    detected[i] = 1u;
+}